@@ -0,0 +1,192 @@
+use crate::config::Config;
+use crate::indexer::Track;
+use crate::player;
+use anyhow::Result;
+use directories::ProjectDirs;
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Whether `path` lies under one of the `audiobook_dirs`.
+pub fn is_audiobook(cfg: &Config, path: &str) -> bool {
+    let path = Path::new(path);
+    cfg.audiobook_dirs.iter().any(|dir| path.starts_with(dir))
+}
+
+/// `tracks` minus the audiobook chapters, for play-all and shuffled queues.
+pub fn without_audiobooks<'a, T: Borrow<Track>>(cfg: &Config, tracks: &'a [T]) -> Vec<&'a Track> {
+    tracks
+        .iter()
+        .map(Borrow::borrow)
+        .filter(|t| !is_audiobook(cfg, &t.path))
+        .collect()
+}
+
+// mpv's resume files, kept apart from the user's own watch_later
+fn watch_later_dir() -> Option<PathBuf> {
+    ProjectDirs::from("com", "furqanhun", "mpv-music")
+        .map(|dirs| dirs.data_dir().join("watch_later"))
+}
+
+// a run of digits compares as a number: "Chapter 2" before "Chapter 10"
+fn chapter_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let number = |chars: &mut std::iter::Peekable<std::str::Chars>| {
+                    let mut digits = String::new();
+                    while let Some(d) = chars.next_if(char::is_ascii_digit) {
+                        digits.push(d);
+                    }
+                    digits.trim_start_matches('0').to_string()
+                };
+                let (x, y) = (number(&mut a), number(&mut b));
+                match x.len().cmp(&y.len()).then_with(|| x.cmp(&y)) {
+                    Ordering::Equal => continue,
+                    other => return other,
+                }
+            }
+            (Some(x), Some(y)) => {
+                match x.to_lowercase().cmp(y.to_lowercase()) {
+                    Ordering::Equal => {}
+                    other => return other,
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+/// The chapter the book was left in: the file with the newest resume entry.
+/// mpv writes "# <path>" atop each entry with --write-filename-in-watch-later-config.
+fn last_stopped(watch_later: &Path, files: &[String]) -> Option<usize> {
+    let mut newest: Option<(SystemTime, usize)> = None;
+    for entry in std::fs::read_dir(watch_later).ok()?.flatten() {
+        let Ok(file) = std::fs::File::open(entry.path()) else {
+            continue;
+        };
+        let mut first = String::new();
+        if BufReader::new(file).read_line(&mut first).is_err() {
+            continue;
+        }
+        let Some(path) = first.trim_end().strip_prefix("# ") else {
+            continue;
+        };
+        let Some(index) = files.iter().position(|f| f == path) else {
+            continue;
+        };
+        let modified = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .unwrap_or(SystemTime::UNIX_EPOCH);
+        if newest.is_none_or(|(time, _)| modified > time) {
+            newest = Some((modified, index));
+        }
+    }
+    newest.map(|(_, index)| index)
+}
+
+/// Plays a book's files in chapter order, once, at `audiobook_speed`, from
+/// where it was last stopped. mpv keeps the position inside the chapter.
+pub fn play(mut files: Vec<String>, cfg: &Config, extra_args: &[String]) -> Result<()> {
+    files.sort_by(|a, b| chapter_cmp(a, b));
+
+    let mut args = vec![
+        "--save-position-on-quit".to_string(),
+        "--write-filename-in-watch-later-config".to_string(),
+    ];
+    if let Some(dir) = watch_later_dir() {
+        let _ = std::fs::create_dir_all(&dir);
+        if let Some(start) = last_stopped(&dir, &files) {
+            log::info!(
+                "Resuming audiobook at chapter {}: {}",
+                start + 1,
+                files[start]
+            );
+            args.push(format!("--playlist-start={}", start));
+        }
+        args.push(format!("--watch-later-dir={}", dir.display()));
+    }
+    if cfg.audiobook_speed != 1.0 {
+        args.push(format!("--speed={}", cfg.audiobook_speed));
+    }
+    // the user's own args still win
+    args.extend_from_slice(extra_args);
+
+    let book_cfg = Config {
+        shuffle: false,
+        loop_mode: "no".to_string(),
+        ..cfg.clone()
+    };
+    player::play_files(&files, &book_cfg, &args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audiobooks_stay_out_of_music_queues() {
+        let cfg = Config {
+            audiobook_dirs: vec![PathBuf::from("/m/Books")],
+            ..Default::default()
+        };
+        let track = |path: &str| Track {
+            path: path.to_string(),
+            ..Default::default()
+        };
+        let tracks = [
+            track("/m/Books/Dune/01.mp3"),
+            track("/m/Rock/a.flac"),
+            track("/m/Bookshelf/b.flac"),
+        ];
+        let music: Vec<&str> = without_audiobooks(&cfg, &tracks)
+            .iter()
+            .map(|t| t.path.as_str())
+            .collect();
+        assert_eq!(music, ["/m/Rock/a.flac", "/m/Bookshelf/b.flac"]);
+    }
+
+    #[test]
+    fn test_chapter_order() {
+        let mut files = vec![
+            "/b/Chapter 10.mp3",
+            "/b/chapter 2.mp3",
+            "/b/Chapter 1.mp3",
+            "/b/Intro.mp3",
+            "/b/CD2/01.mp3",
+            "/b/CD1/02.mp3",
+        ];
+        files.sort_by(|a, b| chapter_cmp(a, b));
+        assert_eq!(
+            files,
+            [
+                "/b/CD1/02.mp3",
+                "/b/CD2/01.mp3",
+                "/b/Chapter 1.mp3",
+                "/b/chapter 2.mp3",
+                "/b/Chapter 10.mp3",
+                "/b/Intro.mp3",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_last_stopped_reads_resume_entries() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::write(dir.join("AAA"), "# /b/02.mp3\nstart=812.5\n").unwrap();
+        std::fs::write(dir.join("BBB"), "# /elsewhere.mp3\nstart=3\n").unwrap();
+
+        let files = ["/b/01.mp3", "/b/02.mp3", "/b/03.mp3"].map(String::from);
+        assert_eq!(last_stopped(dir, &files), Some(1));
+        assert_eq!(last_stopped(dir, &files[2..]), None);
+    }
+}
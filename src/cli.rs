@@ -1,4 +1,5 @@
 use clap::Parser;
+use std::path::PathBuf;
 
 #[derive(Parser, Clone, Debug)]
 #[command(
@@ -12,6 +13,21 @@ pub struct Cli {
     #[arg(index = 1, help = "Directly play a file, directory, or URL")]
     pub target: Option<String>,
 
+    #[arg(
+        long,
+        requires = "target",
+        help = "For a playlist URL target: list its entries and pick which to play"
+    )]
+    pub expand: bool,
+
+    #[arg(
+        long,
+        visible_alias = "all-formats",
+        requires = "target",
+        help = "For a URL target: list the available formats and pick one to play"
+    )]
+    pub yt_formats: bool,
+
     // indexing
     #[arg(
         short = 'r',
@@ -23,11 +39,65 @@ pub struct Cli {
     #[arg(long, help = "Force a full re-scan of the library.")]
     pub reindex: bool,
 
+    #[arg(
+        long,
+        conflicts_with_all = ["refresh_index", "reindex"],
+        help = "Never read or write the music index this run (needs a TARGET to play)"
+    )]
+    pub no_index: bool,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        value_parser = ["json", "bin"],
+        help = "Load the index as JSON Lines or through its faster binary copy this run (overrides index_format)"
+    )]
+    pub index_format: Option<String>,
+
+    #[arg(
+        long,
+        conflicts_with = "target",
+        help = "Re-read tags of tracks still marked UNKNOWN, even if the file looks unchanged."
+    )]
+    pub reindex_missing_tags: bool,
+
+    #[arg(
+        long,
+        help = "Rebuild the index, timing every file probe, and report the slowest files"
+    )]
+    pub profile_scan: bool,
+
+    #[arg(
+        long,
+        help = "Check that every indexed file still exists and is readable (read-only, exits 1 on problems)"
+    )]
+    pub verify: bool,
+
     // actions
     #[cfg(feature = "update")]
     #[arg(short = 'u', long, help = "Update the application")]
     pub update: bool,
 
+    #[arg(
+        long,
+        help = "Ignore the editor, pager and browser set in the config and refuse --update (same as disable_hooks)"
+    )]
+    pub safe_mode: bool,
+
+    #[arg(
+        long,
+        help = "Print the versions of mpv-music, mpv, yt-dlp, ffmpeg and the JS runtime (for bug reports)"
+    )]
+    pub versions: bool,
+
+    #[cfg(feature = "lyrics")]
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Show a track's lyrics: its .lrc, or a lookup on lrclib.net (needs fetch_lyrics)"
+    )]
+    pub lyrics: Option<String>,
+
     #[arg(
         long,
         num_args = 1..,
@@ -48,6 +118,61 @@ pub struct Cli {
     #[arg(long, help = "Open the Interactive Directory Manager")]
     pub manage_dirs: bool,
 
+    #[arg(long, help = "Walk through tracks with UNKNOWN tags and fix them")]
+    pub fix_tags: bool,
+
+    #[arg(
+        long,
+        requires = "fix_tags",
+        help = "With --fix-tags: fill artist/album from folder names in one batch"
+    )]
+    pub from_path: bool,
+
+    #[arg(
+        long,
+        requires = "out",
+        help = "Transcode the filtered tracks (-g/-a/-b/-t...) with ffmpeg into --out"
+    )]
+    pub transcode: bool,
+
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        requires = "transcode",
+        help = "With --transcode: target format (mp3, m4a, opus, ogg, flac). Defaults to preferred_audio_codec, then mp3"
+    )]
+    pub to: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "DIR",
+        requires = "transcode",
+        help = "With --transcode: output directory (folder layout is kept)"
+    )]
+    pub out: Option<PathBuf>,
+
+    #[arg(
+        long,
+        conflicts_with = "transcode",
+        help = "Rename the filtered tracks (-g/-a/-b/-t...) after their tags, see --pattern"
+    )]
+    pub rename: bool,
+
+    #[arg(
+        long,
+        value_name = "TEMPLATE",
+        requires = "rename",
+        help = "With --rename: name template, '/' makes subfolders below the music dir (default '{track:02} - {artist} - {title}')"
+    )]
+    pub pattern: Option<String>,
+
+    #[arg(
+        long,
+        requires = "rename",
+        help = "With --rename: only print what would be renamed"
+    )]
+    pub dry_run: bool,
+
     // conf/log
     #[arg(
         short = 'c',
@@ -72,10 +197,255 @@ pub struct Cli {
     #[arg(long, visible_alias = "rm-log", help = "Delete log file")]
     pub remove_log: bool,
 
+    #[arg(long, help = "Delete cached YouTube search results")]
+    pub clear_search_cache: bool,
+
+    #[arg(
+        long,
+        visible_alias = "rm-index",
+        help = "Delete the music index (the next run rebuilds it)"
+    )]
+    pub clear_index: bool,
+
+    #[arg(
+        long,
+        requires = "clear_index",
+        help = "With --clear-index, also delete the search and tool caches"
+    )]
+    pub all: bool,
+
+    #[arg(
+        short = 'y',
+        long,
+        visible_alias = "force",
+        help = "Delete without asking (--remove-config, --remove-log, --clear-index). Without a terminal nothing asks anyway"
+    )]
+    pub yes: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        help = "Move an indexed track to the trash and drop it from the index"
+    )]
+    pub delete_track: Option<String>,
+
+    #[arg(
+        long,
+        requires = "delete_track",
+        help = "With --delete-track, delete the file permanently instead of trashing it"
+    )]
+    pub purge: bool,
+
+    // shell completion helpers, print matching values from the index
+    #[arg(
+        long,
+        value_name = "PREFIX",
+        num_args = 0..=1,
+        default_missing_value = "",
+        help = "Print indexed artists starting with PREFIX (for shell completion)"
+    )]
+    pub complete_artist: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "CMD",
+        value_parser = ["radio"],
+        help = "Send a command to the running player: 'radio' appends tracks like the one playing"
+    )]
+    pub ipc_command: Option<String>,
+
+    #[arg(
+        long,
+        help = "Print index stats (counts, size, mtimes) as one JSON object, for monitoring"
+    )]
+    pub dump_index_stats: bool,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Merge another music_index.jsonl (e.g. another machine's) into this one, newer entries win"
+    )]
+    pub merge_index: Option<PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "FILE",
+        help = "Write the play history as a scrobbler.log (or CSV for a .csv FILE) to import into Last.fm & co. --since limits it to recent plays"
+    )]
+    pub export_history: Option<PathBuf>,
+
+    #[arg(
+        long,
+        group = "tag_stats",
+        help = "Print a bar chart of tracks per genre (honors filters)"
+    )]
+    pub genre_stats: bool,
+
+    #[arg(
+        long,
+        group = "tag_stats",
+        help = "Print a bar chart of tracks per artist (honors filters)"
+    )]
+    pub artist_stats: bool,
+
+    #[arg(
+        long,
+        group = "tag_stats",
+        help = "Print a bar chart of tracks per album (honors filters)"
+    )]
+    pub album_stats: bool,
+
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "tag_stats",
+        help = "Only show the N biggest groups in a --*-stats report"
+    )]
+    pub top: Option<usize>,
+
+    #[arg(
+        long,
+        requires = "tag_stats",
+        help = "Print the --*-stats report as JSON instead of bars"
+    )]
+    pub json: bool,
+
+    #[arg(
+        long,
+        value_name = "PREFIX",
+        num_args = 0..=1,
+        default_missing_value = "",
+        help = "Print indexed albums starting with PREFIX (for shell completion)"
+    )]
+    pub complete_album: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "PREFIX",
+        num_args = 0..=1,
+        default_missing_value = "",
+        help = "Print indexed genres starting with PREFIX (for shell completion)"
+    )]
+    pub complete_genre: Option<String>,
+
     // playback
     #[arg(short = 'p', long, help = "Play all tracks immediately")]
     pub play_all: bool,
 
+    #[arg(
+        long,
+        conflicts_with = "last",
+        help = "Play only the first matching track (index order, or --year/-b order)"
+    )]
+    pub first: bool,
+
+    #[arg(long, help = "Play only the last matching track")]
+    pub last: bool,
+
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with = "target",
+        help = "Play a file and the rest of its album after it (its folder, by name, if it isn't indexed)"
+    )]
+    pub play_after: Option<String>,
+
+    #[arg(
+        long,
+        visible_alias = "interactive-filter",
+        conflicts_with_all = ["first", "last", "play_all"],
+        help = "Always hand-pick from the matches in Track Mode, even a single one"
+    )]
+    pub pick: bool,
+
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Play N random tracks (honors filters and avoid_recent_hours)"
+    )]
+    pub random: Option<usize>,
+
+    #[arg(
+        long,
+        conflicts_with = "random",
+        help = "Play one random album in track order (honors filters)"
+    )]
+    pub random_album: bool,
+
+    #[arg(
+        long,
+        requires = "random_album",
+        help = "With --random-album: also pick UNKNOWN albums and single-track releases"
+    )]
+    pub include_singles: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["random", "random_album"],
+        help = "Play today's album: the same one all day, a new one tomorrow (honors filters)"
+    )]
+    pub album_of_the_day: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["random", "random_album", "album_of_the_day"],
+        help = "Play the album of the most recently added track (honors filters)"
+    )]
+    pub play_newest: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["random", "random_album", "album_of_the_day", "play_newest"],
+        help = "Play just the most recently added track(s)"
+    )]
+    pub play_newest_track: bool,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["random", "random_album", "album_of_the_day", "play_newest", "play_newest_track"],
+        help = "Play a random track, then the rest of its album (honors filters and --seed)"
+    )]
+    pub surprise: bool,
+
+    #[arg(
+        long,
+        value_name = "NAME",
+        conflicts_with_all = ["random", "random_album", "album_of_the_day", "play_newest", "play_newest_track", "surprise"],
+        help = "Shuffle every track whose BPM tag fits a mood_presets range, e.g. chill or workout (honors filters)"
+    )]
+    pub mood: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        num_args = 0..=1,
+        default_missing_value = "25",
+        conflicts_with_all = ["random", "random_album", "album_of_the_day", "play_newest", "play_newest_track", "surprise", "mood"],
+        help = "Play the N (default 25) tracks you've played most lately, recent plays count more (honors filters)"
+    )]
+    pub most_played: Option<usize>,
+
+    #[arg(
+        long,
+        conflicts_with_all = ["random", "random_album", "album_of_the_day", "play_newest", "play_newest_track", "surprise", "mood", "most_played"],
+        help = "Shuffle with every music dir taking turns, so small dirs aren't drowned out (honors filters)"
+    )]
+    pub balanced_shuffle: bool,
+
+    #[arg(
+        long,
+        value_name = "N",
+        requires = "balanced_shuffle",
+        help = "With --balanced-shuffle: stop after N tracks"
+    )]
+    pub limit: Option<usize>,
+
+    #[arg(
+        long,
+        help = "Browse classical music by composer, then work (see read_classical_tags)"
+    )]
+    pub work: bool,
+
     #[arg(
             short = 'l',
             long,
@@ -87,6 +457,14 @@ pub struct Cli {
     #[arg(long, help = "Allow video files")]
     pub video_ok: bool,
 
+    #[arg(
+        long,
+        requires = "target",
+        conflicts_with_all = ["expand", "yt_formats"],
+        help = "Play this one TARGET (a file or URL) with a window and video, nothing else changes (same as prefixing it with 'video:')"
+    )]
+    pub video_once: bool,
+
     #[arg(
         long,
         help = "Force disable video files (overrides config/negate --video-ok)"
@@ -107,12 +485,41 @@ pub struct Cli {
         )]
     pub loop_arg: Option<String>,
 
+    #[arg(
+        long,
+        help = "Start mpv in the background and return right away (control it over IPC)"
+    )]
+    pub detach: bool,
+
     #[arg(long, help = "Disable all looping")]
     pub no_loop: bool,
 
     #[arg(long, help = "Loop the current track (Repeat One)")]
     pub repeat: bool,
 
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u32).range(1..),
+        conflicts_with_all = ["loop_arg", "no_loop", "repeat"],
+        help = "Play the whole queue N times, then stop (unlike --loop inf or --repeat)"
+    )]
+    pub repeat_queue: Option<u32>,
+
+    #[arg(
+        long,
+        visible_alias = "downmix",
+        help = "Downmix audio to mono (for single-speaker setups)"
+    )]
+    pub mono: bool,
+
+    #[arg(
+        long,
+        value_name = "SECS",
+        help = "Keep mpv around after the queue ends, quit once it has been idle this long"
+    )]
+    pub idle_timeout: Option<u64>,
+
     #[arg(
         short = 'e',
         long,
@@ -154,6 +561,73 @@ pub struct Cli {
         )]
     pub title: Option<Option<String>>,
 
+    #[arg(
+        long,
+        value_name = "KBPS",
+        help = "Only tracks with at least this bitrate (unknown bitrate excluded)"
+    )]
+    pub min_bitrate: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "KBPS",
+        help = "Only tracks with at most this bitrate (unknown bitrate excluded)"
+    )]
+    pub max_bitrate: Option<u32>,
+
+    #[arg(
+        long,
+        value_name = "SIZE",
+        value_parser = parse_size,
+        help = "Only files of at least this size (e.g. 500K, 5M, 1.5G)"
+    )]
+    pub min_size: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "SIZE",
+        value_parser = parse_size,
+        help = "Only files of at most this size (e.g. 500K, 5M, 1.5G)"
+    )]
+    pub max_size: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "AGE",
+        value_parser = parse_age,
+        help = "Only tracks added within AGE (e.g. 12h, 3d, 2w, 6mo)"
+    )]
+    pub since: Option<u64>,
+
+    #[arg(
+        long,
+        value_name = "RANGE",
+        value_parser = parse_year_range,
+        help = "Only tracks from these years (1969, 1990-1999, 2000-, -1979), oldest album first"
+    )]
+    pub year: Option<YearRange>,
+
+    #[arg(
+        long,
+        value_name = "TEXT",
+        help = "Filter by the comment tag (partial, e.g. --comment 'workout,run')"
+    )]
+    pub comment: Option<String>,
+
+    #[arg(
+        long,
+        value_name = "N",
+        value_parser = clap::value_parser!(u8).range(1..=5),
+        help = "Only tracks rated N stars or more (rate with alt-r in Track Mode)"
+    )]
+    pub min_rating: Option<u8>,
+
+    #[arg(
+        long,
+        help = "Date --since and --play-newest by file mtime instead of when the track was first indexed"
+    )]
+    pub by_mtime: bool,
+
     // sys
     #[arg(short = 'v', long, action = clap::ArgAction::Count, help = "Display Verbose Information")]
     pub verbose: u8,
@@ -165,8 +639,21 @@ pub struct Cli {
     pub shuffle: bool,
     #[arg(long, help = "No Shuffle")]
     pub no_shuffle: bool,
+    #[arg(
+        long,
+        value_name = "N",
+        help = "Seed the shuffle for a reproducible order"
+    )]
+    pub seed: Option<u64>,
     #[arg(long, help = "Force serial (single-threaded) processing")]
     pub serial: bool,
+
+    #[arg(
+        short = 'q',
+        long,
+        help = "No scan progress, just the final track count (for cron and scripts)"
+    )]
+    pub quiet: bool,
     #[arg(
             long,
             visible_alias = "yt",
@@ -182,4 +669,277 @@ pub struct Cli {
     pub radio: Option<Option<String>>,
     #[arg(long, allow_hyphen_values = true, num_args = 1.., help = "Pass arguments to mpv")]
     pub mpv_args: Option<Vec<String>>,
+    #[arg(
+        index = 2,
+        last = true,
+        value_name = "MPV_ARGS",
+        help = "Everything after `--` goes to mpv as-is, after all other args (e.g. -- --start=30)"
+    )]
+    pub passthrough: Vec<String>,
+}
+
+/// "5M", "500k", "1.5G", "2MB" or plain bytes -> bytes (binary units, like `du -h`).
+fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+
+    let number: f64 = number
+        .parse()
+        .map_err(|_| format!("'{}' is not a size (try 500K, 5M, 1.5G)", s))?;
+    let multiplier: u64 = match unit
+        .trim()
+        .to_ascii_uppercase()
+        .trim_end_matches(['B', 'I'])
+    {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => return Err(format!("unknown size unit '{}' (use K, M, G or T)", unit)),
+    };
+    Ok((number * multiplier as f64) as u64)
+}
+
+/// "90m", "12h", "3d", "2w", "6mo", "1y" -> seconds.
+fn parse_age(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+
+    let number: u64 = number
+        .parse()
+        .map_err(|_| format!("'{}' is not an age (try 12h, 3d, 2w)", s))?;
+    let seconds: u64 = match unit.trim().to_ascii_lowercase().as_str() {
+        "m" | "min" => 60,
+        "h" => 3600,
+        "" | "d" => 86_400,
+        "w" => 7 * 86_400,
+        "mo" => 30 * 86_400,
+        "y" => 365 * 86_400,
+        _ => {
+            return Err(format!(
+                "unknown age unit '{}' (use m, h, d, w, mo or y)",
+                unit
+            ));
+        }
+    };
+    Ok(number.saturating_mul(seconds))
+}
+
+/// Inclusive year bounds from `--year`, either end may be open.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct YearRange {
+    pub from: Option<u16>,
+    pub to: Option<u16>,
+}
+
+impl YearRange {
+    pub fn contains(&self, year: Option<u16>) -> bool {
+        year.is_some_and(|y| self.from.is_none_or(|f| y >= f) && self.to.is_none_or(|t| y <= t))
+    }
+}
+
+/// "1969", "1990-1999", "2000-", "-1979" -> YearRange.
+fn parse_year_range(s: &str) -> Result<YearRange, String> {
+    let bound = |part: &str| -> Result<Option<u16>, String> {
+        let part = part.trim();
+        if part.is_empty() {
+            return Ok(None);
+        }
+        part.parse()
+            .map(Some)
+            .map_err(|_| format!("'{}' is not a year (try 1969, 1990-1999, 2000-)", s))
+    };
+
+    let range = match s.trim().split_once('-') {
+        Some((from, to)) => YearRange {
+            from: bound(from)?,
+            to: bound(to)?,
+        },
+        None => {
+            let year = bound(s)?;
+            YearRange {
+                from: year,
+                to: year,
+            }
+        }
+    };
+    if range.from.is_none() && range.to.is_none() {
+        return Err("give at least one year".to_string());
+    }
+    if let (Some(from), Some(to)) = (range.from, range.to)
+        && from > to
+    {
+        return Err(format!("{} comes after {}", from, to));
+    }
+    Ok(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size_suffixes() {
+        assert_eq!(parse_size("2048"), Ok(2048));
+        assert_eq!(parse_size("500K"), Ok(500 * 1024));
+        assert_eq!(parse_size("5m"), Ok(5 * 1024 * 1024));
+        assert_eq!(parse_size("1.5G"), Ok(3 * 512 * 1024 * 1024));
+        assert_eq!(parse_size("5MB"), parse_size("5MiB"));
+        assert!(parse_size("5X").is_err());
+        assert!(parse_size("big").is_err());
+    }
+
+    #[test]
+    fn test_parse_age_units() {
+        assert_eq!(parse_age("12h"), Ok(12 * 3600));
+        assert_eq!(parse_age("3"), Ok(3 * 86_400));
+        assert_eq!(parse_age("2w"), Ok(14 * 86_400));
+        assert_eq!(parse_age("6mo"), Ok(180 * 86_400));
+        assert!(parse_age("3 fortnights").is_err());
+    }
+
+    #[test]
+    fn test_passthrough_after_separator() {
+        let args = Cli::try_parse_from([
+            "mpv-music",
+            "-a",
+            "Radiohead",
+            "--",
+            "--start=30",
+            "--volume=80",
+        ])
+        .unwrap();
+        assert_eq!(args.artist, Some(Some("Radiohead".to_string())));
+        assert_eq!(args.passthrough, ["--start=30", "--volume=80"]);
+
+        let args = Cli::try_parse_from(["mpv-music", "song.mp3", "--", "--mute"]).unwrap();
+        assert_eq!(args.target.as_deref(), Some("song.mp3"));
+        assert_eq!(args.passthrough, ["--mute"]);
+    }
+
+    #[test]
+    fn test_clear_index_all_needs_clear_index() {
+        let args = Cli::try_parse_from(["mpv-music", "--rm-index", "--all"]).unwrap();
+        assert!(args.clear_index && args.all);
+        assert!(Cli::try_parse_from(["mpv-music", "--all"]).is_err());
+    }
+
+    #[test]
+    fn test_reindex_missing_tags_covers_the_whole_index() {
+        assert!(Cli::try_parse_from(["mpv-music", "--reindex-missing-tags"]).is_ok());
+        // a folder TARGET is a session scan, saving it would replace the index
+        assert!(
+            Cli::try_parse_from(["mpv-music", "--reindex-missing-tags", "/tmp/album"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_no_index_conflicts_with_scans() {
+        let args = Cli::try_parse_from(["mpv-music", "--no-index", "/tmp/album"]).unwrap();
+        assert!(args.no_index);
+        assert!(Cli::try_parse_from(["mpv-music", "--no-index", "-r"]).is_err());
+    }
+
+    #[test]
+    fn test_rename_flags() {
+        let args =
+            Cli::try_parse_from(["mpv-music", "-a", "Bowie", "--rename", "--dry-run"]).unwrap();
+        assert!(args.rename && args.dry_run && args.pattern.is_none());
+        assert!(Cli::try_parse_from(["mpv-music", "--dry-run"]).is_err());
+        assert!(Cli::try_parse_from(["mpv-music", "--pattern", "{title}"]).is_err());
+    }
+
+    #[test]
+    fn test_tag_stats_flags() {
+        let args =
+            Cli::try_parse_from(["mpv-music", "--genre-stats", "--top", "5", "--json"]).unwrap();
+        assert!(args.genre_stats && args.json && args.top == Some(5));
+        assert!(Cli::try_parse_from(["mpv-music", "--genre-stats", "--album-stats"]).is_err());
+        assert!(Cli::try_parse_from(["mpv-music", "--top", "5"]).is_err());
+        assert!(Cli::try_parse_from(["mpv-music", "--json"]).is_err());
+    }
+
+    #[test]
+    fn test_balanced_shuffle_limit() {
+        let args =
+            Cli::try_parse_from(["mpv-music", "--balanced-shuffle", "--limit", "50"]).unwrap();
+        assert!(args.balanced_shuffle && args.limit == Some(50));
+        assert!(Cli::try_parse_from(["mpv-music", "--limit", "50"]).is_err());
+        assert!(Cli::try_parse_from(["mpv-music", "--balanced-shuffle", "--random", "5"]).is_err());
+    }
+
+    #[test]
+    fn test_yes_skips_confirmation() {
+        let args = Cli::try_parse_from(["mpv-music", "--rm-conf", "-y"]).unwrap();
+        assert!(args.remove_config && args.yes);
+        assert!(
+            Cli::try_parse_from(["mpv-music", "--rm-log", "--force"])
+                .unwrap()
+                .yes
+        );
+    }
+
+    #[test]
+    fn test_index_format_values() {
+        let args = Cli::try_parse_from(["mpv-music", "--index-format", "bin"]).unwrap();
+        assert_eq!(args.index_format.as_deref(), Some("bin"));
+        assert!(Cli::try_parse_from(["mpv-music", "--index-format", "msgpack"]).is_err());
+    }
+
+    #[test]
+    fn test_min_rating_range() {
+        let args = Cli::try_parse_from(["mpv-music", "--min-rating", "4"]).unwrap();
+        assert_eq!(args.min_rating, Some(4));
+        assert!(Cli::try_parse_from(["mpv-music", "--min-rating", "0"]).is_err());
+        assert!(Cli::try_parse_from(["mpv-music", "--min-rating", "6"]).is_err());
+    }
+
+    #[test]
+    fn test_play_after_takes_a_path() {
+        let args = Cli::try_parse_from(["mpv-music", "--play-after", "/m/Album/05.flac"]).unwrap();
+        assert_eq!(args.play_after.as_deref(), Some("/m/Album/05.flac"));
+        assert!(Cli::try_parse_from(["mpv-music", "--play-after", "/m/a.flac", "/m/b"]).is_err());
+    }
+
+    #[test]
+    fn test_first_last_conflict() {
+        let args = Cli::try_parse_from(["mpv-music", "-a", "Bowie", "--first"]).unwrap();
+        assert!(args.first && !args.last);
+        assert!(Cli::try_parse_from(["mpv-music", "-a", "Bowie", "--first", "--last"]).is_err());
+
+        let args =
+            Cli::try_parse_from(["mpv-music", "-g", "jazz", "--interactive-filter"]).unwrap();
+        assert!(args.pick);
+        assert!(Cli::try_parse_from(["mpv-music", "-g", "jazz", "--pick", "--first"]).is_err());
+    }
+
+    #[test]
+    fn test_repeat_queue_validation() {
+        let args = Cli::try_parse_from(["mpv-music", "--repeat-queue", "3"]).unwrap();
+        assert_eq!(args.repeat_queue, Some(3));
+        assert!(Cli::try_parse_from(["mpv-music", "--repeat-queue", "0"]).is_err());
+        assert!(Cli::try_parse_from(["mpv-music", "--repeat-queue", "2", "--repeat"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_year_range() {
+        let range = |from, to| Ok(YearRange { from, to });
+        assert_eq!(parse_year_range("1969"), range(Some(1969), Some(1969)));
+        assert_eq!(parse_year_range("1990-1999"), range(Some(1990), Some(1999)));
+        assert_eq!(parse_year_range("2000-"), range(Some(2000), None));
+        assert_eq!(parse_year_range("-1979"), range(None, Some(1979)));
+        assert!(parse_year_range("1999-1990").is_err());
+        assert!(parse_year_range("-").is_err());
+        assert!(parse_year_range("sixties").is_err());
+
+        let nineties = parse_year_range("1990-1999").unwrap();
+        assert!(nineties.contains(Some(1994)));
+        assert!(!nineties.contains(Some(2001)));
+        assert!(!nineties.contains(None));
+    }
 }
@@ -1,7 +1,9 @@
 use anyhow::{Context, Result};
 use directories::{ProjectDirs, UserDirs};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
 
 pub const DEFAULT_YTDLP_USER_AGENT: &str =
     "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:153.0) Gecko/20100101 Firefox/153.0";
@@ -10,31 +12,314 @@ fn default_ytdlp_useragent() -> String {
     "default".to_string()
 }
 
+fn default_true() -> bool {
+    true
+}
+
+/// A `url_resolvers` entry: URLs matching the `pattern` regex get `ytdl_opts`
+/// added to mpv's --ytdl-raw-options ("" for options without a value).
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct UrlResolver {
+    pub pattern: String,
+    #[serde(default)]
+    pub ytdl_opts: BTreeMap<String, String>,
+}
+
+// the one site rule that used to be hardcoded: YouTube playlist links play the
+// whole list, not just the video they point at
+fn default_url_resolvers() -> Vec<UrlResolver> {
+    vec![UrlResolver {
+        pattern: r"[?&]list=".to_string(),
+        ytdl_opts: BTreeMap::from([("yes-playlist".to_string(), String::new())]),
+    }]
+}
+
+// inclusive BPM ranges for --mood
+fn default_mood_presets() -> HashMap<String, (u32, u32)> {
+    HashMap::from([
+        ("chill".to_string(), (0, 99)),
+        ("workout".to_string(), (131, 999)),
+    ])
+}
+
+fn default_scrobble_threshold_percent() -> u8 {
+    50
+}
+
+fn default_scrobble_threshold_secs() -> u32 {
+    240
+}
+
+fn default_play_decay_half_life_days() -> u32 {
+    30
+}
+
+fn default_http_status_bind() -> String {
+    "127.0.0.1".to_string()
+}
+
+const DETAILED_STATUS_MSG: &str = "▶ ${?metadata/artist:${metadata/artist} - }${?metadata/title:${metadata/title}}${!metadata/title:${media-title}} • ${time-pos} / ${duration} • (${percent-pos}%)";
+
+/// Named `status_msg_override` values. "progress" also turns mpv's terminal bar on.
+pub const STATUS_PRESETS: &[(&str, &str)] = &[
+    (
+        "minimal",
+        "▶ ${?metadata/title:${metadata/title}}${!metadata/title:${media-title}} • ${time-pos}",
+    ),
+    ("detailed", DETAILED_STATUS_MSG),
+    (
+        "progress",
+        "▶ ${?metadata/title:${metadata/title}}${!metadata/title:${media-title}} • ${time-pos} / ${duration}",
+    ),
+];
+
+/// The status line to force instead of the one in `mpv_default_args`, if any.
+/// Returns `(message, wants_osd_bar)`.
+pub fn status_msg(cfg: &Config) -> Option<(String, bool)> {
+    let value = cfg.status_msg_override.trim();
+    if value.is_empty() {
+        return None;
+    }
+    match STATUS_PRESETS.iter().find(|(name, _)| *name == value) {
+        Some((name, msg)) => Some((msg.to_string(), *name == "progress")),
+        None => Some((value.to_string(), false)),
+    }
+}
+
+// "22:00" -> minutes into the day
+fn parse_clock(value: &str) -> Option<u16> {
+    let (hours, minutes) = value.trim().split_once(':')?;
+    let (hours, minutes): (u16, u16) = (hours.parse().ok()?, minutes.parse().ok()?);
+    (hours < 24 && minutes < 60).then_some(hours * 60 + minutes)
+}
+
+/// The `quiet_hours` volume cap if `minute` (minutes into the local day) falls
+/// in the window. A window like 22:00-07:00 runs over midnight.
+pub fn quiet_hours_cap(cfg: &Config, minute: u16) -> Option<u8> {
+    let (start, end, cap) = cfg.quiet_hours.as_ref()?;
+    let (start, end) = (parse_clock(start)?, parse_clock(end)?);
+    let inside = if start <= end {
+        (start..end).contains(&minute)
+    } else {
+        minute >= start || minute < end
+    };
+    inside.then_some(*cap)
+}
+
+// mpv gets it as a plain argument, but a stray newline or escape byte still
+// wrecks the terminal, and `$(`/backticks are always a copy-paste mistake here
+fn unsafe_status_msg(msg: &str) -> bool {
+    msg.chars().any(char::is_control) || msg.contains('`') || msg.contains("$(")
+}
+
+fn default_glyph_preset() -> String {
+    "emoji".to_string()
+}
+
+fn default_audiobook_speed() -> f64 {
+    1.0
+}
+
+fn default_lazy_track_threshold() -> usize {
+    50_000
+}
+
+fn default_min_term_cols() -> u16 {
+    60
+}
+
+fn default_min_term_rows() -> u16 {
+    15
+}
+
+fn default_single_threshold() -> usize {
+    1
+}
+
+fn default_index_format() -> String {
+    "json".to_string()
+}
+
+fn default_cover_filenames() -> Vec<String> {
+    [
+        "cover.jpg",
+        "cover.png",
+        "folder.jpg",
+        "folder.png",
+        "front.jpg",
+        "front.png",
+        "album.jpg",
+        "albumart.jpg",
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
+fn default_search_cache_max_entries() -> usize {
+    100
+}
+
+// missing keys (older or hand-trimmed configs) take the Default values below
 #[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
 pub struct Config {
     pub shuffle: bool,
+    #[serde(default = "default_true")]
+    pub shuffle_once: bool, // true = we shuffle queues (seeded, fixed order), false = mpv's own --shuffle
+    #[serde(default)]
+    pub group_queue_by_album: bool, // Track Mode picks play album by album, in track order. shuffle wins
     pub loop_mode: String, // "playlist", "track", "no", "inf", "5"
     pub volume: u8,
+    #[serde(default)]
+    pub volume_overrides: HashMap<String, u8>, // genre or artist -> volume, for queues that all share it
+    #[serde(default)]
+    pub quiet_hours: Option<(String, String, u8)>, // ("22:00", "07:00", 30): volume never above 30 in that window (local time)
+    #[serde(default = "default_mood_presets")]
+    pub mood_presets: HashMap<String, (u32, u32)>, // --mood name -> inclusive BPM range
+    #[serde(default)]
+    pub force_mono: bool, // downmix to a single channel (mono speakers)
 
     pub music_dirs: Vec<PathBuf>,
     pub video_ok: bool,
     #[serde(default)]
     pub watch: bool,
     #[serde(default)]
+    pub show_window: Option<bool>, // unset = follow `watch`
+    #[serde(default)]
+    pub decode_video: Option<bool>, // unset = follow `watch`; true without a window decodes to --vo=null
+    #[serde(default)]
+    pub preferred_audio_codec: String, // "opus", "aac"... for streamed audio and --transcode. empty = any
+    #[serde(default)]
     pub scan_hidden_dirs: bool,
+    #[serde(default = "default_true")]
+    pub dedup_by_inode: bool, // a file reachable through a symlink or bind mount too is indexed once
     pub serial_mode: bool,
+    #[serde(default)]
+    pub return_to_menu_after_play: bool, // false = quit once a picker's selection has played
+    #[serde(default)]
+    pub background_refresh: bool, // the main menu opens on the loaded index while a rescan runs behind it
+    #[serde(default)]
+    pub dir_autoplay_next: bool, // Directory Mode rolls on into the next folder (ctrl-n toggles it live)
+    #[serde(default)]
+    pub dir_mode_recursive: bool, // Directory Mode also lists parent folders, playing their whole subtree (alt-t toggles it live)
+    #[serde(default)]
+    pub audiobook_dirs: Vec<PathBuf>, // audiobooks/podcasts: played in chapter order and resumed, never shuffled or used for radio
+    #[serde(default = "default_audiobook_speed")]
+    pub audiobook_speed: f64, // mpv --speed for audiobook_dirs
+    #[serde(default = "default_lazy_track_threshold")]
+    pub lazy_track_threshold: usize, // track mode searches as you type above this (0 = never)
+    #[serde(default)]
+    pub remember_queries: bool, // pickers reopen with the query they were left with (this session)
+    #[serde(default = "default_min_term_cols")]
+    pub min_term_cols: u16, // pickers refuse to open in a smaller terminal (0 = no check)
+    #[serde(default = "default_min_term_rows")]
+    pub min_term_rows: u16,
+    #[serde(default = "default_glyph_preset")]
+    pub glyph_preset: String, // "emoji" or "ascii", for terminals/fonts that show emoji as boxes
+    #[serde(default)]
+    pub glyphs: HashMap<String, String>, // per-icon overrides on top of the preset, e.g. tracks = "\u{f001}"
+    #[serde(default)]
+    pub group_singles: bool, // album picker folds small albums into "Singles · <artist>"
+    #[serde(default = "default_single_threshold")]
+    pub single_threshold: usize, // albums with this many tracks or fewer count as singles
+    #[serde(default)]
+    pub preferred_tag_type: Option<String>, // e.g. "ID3v2", read before the file's primary tag
+    #[serde(default)]
+    pub read_classical_tags: bool, // index composer/work/movement tags (needs a --reindex)
+    #[serde(default)]
+    pub prefer_original_year: bool, // --year and chronological ordering use the original release year of reissues
+    #[serde(default)]
+    pub split_artists: bool, // "A feat. B", "A & B", "A, B" count as two artists (needs a --reindex)
+    #[serde(default = "default_index_format")]
+    pub index_format: String, // "bin" loads through a binary copy of the JSONL index, rebuilt when the JSONL changes
 
     pub ytdlp_ejs_remote_github: bool,
     #[serde(default = "default_ytdlp_useragent")]
     pub ytdlp_useragent: String,
+    #[serde(default)]
+    pub ytdlp_cookies: Option<PathBuf>, // cookies.txt for age/login restricted videos
+    #[serde(default)]
+    pub ytdlp_cookies_from_browser: Option<String>, // e.g. "firefox", used when no cookies file is set
+    #[serde(default = "default_url_resolvers")]
+    pub url_resolvers: Vec<UrlResolver>, // per-site yt-dlp options, every matching entry applies
+    #[serde(default = "default_search_cache_max_entries")]
+    pub search_cache_max_entries: usize, // least recently used queries are dropped past this (0 = no limit)
+    #[serde(default)]
+    pub search_adds_to_queue: bool, // ENTER on search results stages them (alt-p plays now) instead of playing
     pub enable_file_logging: bool,
 
     pub audio_exts: Vec<String>,
     pub video_exts: Vec<String>,
     pub playlist_exts: Vec<String>,
+    #[serde(default = "default_cover_filenames")]
+    pub cover_filenames: Vec<String>, // album art next to the tracks, first match wins (case-insensitive)
+    #[serde(default)]
+    pub write_ratings_to_tags: bool, // alt-r ratings also go into the file's tag (POPM), not just ratings.json
+    #[serde(default)]
+    pub strict_target_ext: bool, // refuse direct targets with an unknown extension that don't look like media
+
+    #[serde(default)]
+    pub editor: String, // empty = $EDITOR, then notepad / nano
+    #[serde(default)]
+    pub pager: String, // empty = $PAGER, then more / less
+    #[serde(default)]
+    pub browser: String, // empty = $BROWSER, then xdg-open / open / start
+    #[serde(default)]
+    pub enrich_command: String, // shell command, new/changed tracks in as JSON Lines, enriched ones out
+    #[serde(default)]
+    pub disable_hooks: bool, // safe mode: ignore the tools above and refuse the self-update
 
     pub mpv_default_args: Vec<String>,
+    #[serde(default)]
+    pub status_msg_override: String, // empty = keep the one above; "minimal", "detailed", "progress" or a raw mpv format
+    #[serde(default)]
+    pub allow_sidecar_args: bool, // off by default, a sidecar can make mpv do anything
+    #[serde(default = "default_true")]
+    pub show_banner: bool, // false drops --term-playing-msg entirely
+    #[serde(default = "default_true")]
+    pub clear_screen_on_play: bool, // ignored in debug mode (never clears)
+
+    #[serde(default)]
+    pub record_history: bool, // opt-in, local files only
+    #[serde(default = "default_scrobble_threshold_percent")]
+    pub scrobble_threshold_percent: u8, // a play counts after this much of the track...
+    #[serde(default = "default_scrobble_threshold_secs")]
+    pub scrobble_threshold_secs: u32, // ...or this many seconds, whichever comes first
+    #[serde(default = "default_true")]
+    pub set_terminal_title: bool, // "Artist - Title" in the terminal title while mpv plays
+    #[serde(default)]
+    pub avoid_recent_hours: u32, // 0 = off, skip tracks played within N hours in --random
+    #[serde(default = "default_play_decay_half_life_days")]
+    pub play_decay_half_life_days: u32, // --most-played: a play counts half as much after this many days. 0 = all-time counts
 
+    #[serde(default)]
+    pub detach_player: bool, // start mpv in the background and return at once, control it over IPC
+    #[serde(default = "default_true")]
+    pub idle_quit: bool, // false keeps mpv alive (--idle=yes) once the queue ends
+    #[serde(default)]
+    pub idle_timeout: u64, // seconds, 0 = off. quits an idle mpv via IPC
+    #[serde(default)]
+    pub radio_after_queue: bool, // append same-artist/genre radio as the queue's last track starts
+
+    #[serde(default)]
+    pub http_status_port: u16, // 0 = off. needs a build with the http-status feature
+    #[serde(default = "default_http_status_bind")]
+    pub http_status_bind: String,
+    #[serde(default)]
+    pub fetch_lyrics: bool, // look up missing lyrics on lrclib.net. needs a build with the lyrics feature
+    #[serde(default)]
+    pub save_lyrics: bool, // keep fetched lyrics as a .lrc next to the track
+
+    #[serde(skip, default)]
+    pub shuffle_seed: u64, // picked per run (or --seed), drives every shuffle we do ourselves
+    #[serde(skip, default)]
+    pub profile_scan: bool, // --profile-scan, time every probe and report the slowest
+    #[serde(skip, default)]
+    pub quiet_scan: bool, // no spinner, for scans running behind a picker
+    #[serde(skip, default)]
+    pub no_scan_progress: bool, // --quiet, only the "Indexed N tracks" summary
+    #[serde(skip, default)]
+    pub volume_locked: bool, // --volume was given, it beats volume_overrides
     #[serde(skip, default)]
     pub ytdlp_available: bool,
     #[serde(skip, default)]
@@ -58,19 +343,53 @@ impl Default for Config {
         }
 
         let banner_text = "╔══  MPV-MUSIC  ══╗";
-        let status_msg = "▶ ${?metadata/artist:${metadata/artist} - }${?metadata/title:${metadata/title}}${!metadata/title:${media-title}} • ${time-pos} / ${duration} • (${percent-pos}%)";
+        let status_msg = DETAILED_STATUS_MSG;
 
         Self {
             shuffle: true,
+            shuffle_once: true,
+            group_queue_by_album: false,
             loop_mode: "inf".to_string(),
             volume: 100,
+            volume_overrides: HashMap::new(),
+            quiet_hours: None,
+            mood_presets: default_mood_presets(),
+            force_mono: false,
             music_dirs,
             video_ok: false,
             watch: false,
+            show_window: None,
+            decode_video: None,
+            preferred_audio_codec: String::new(),
             scan_hidden_dirs: false,
+            dedup_by_inode: true,
             serial_mode: false,
+            return_to_menu_after_play: false,
+            background_refresh: false,
+            dir_autoplay_next: false,
+            dir_mode_recursive: false,
+            audiobook_dirs: Vec::new(),
+            audiobook_speed: default_audiobook_speed(),
+            lazy_track_threshold: default_lazy_track_threshold(),
+            remember_queries: false,
+            min_term_cols: default_min_term_cols(),
+            min_term_rows: default_min_term_rows(),
+            glyph_preset: default_glyph_preset(),
+            glyphs: HashMap::new(),
+            group_singles: false,
+            single_threshold: default_single_threshold(),
+            preferred_tag_type: None,
+            read_classical_tags: false,
+            prefer_original_year: false,
+            split_artists: false,
+            index_format: default_index_format(),
             ytdlp_ejs_remote_github: false,
             ytdlp_useragent: default_ytdlp_useragent(),
+            ytdlp_cookies: None,
+            ytdlp_cookies_from_browser: None,
+            url_resolvers: default_url_resolvers(),
+            search_cache_max_entries: default_search_cache_max_entries(),
+            search_adds_to_queue: false,
             enable_file_logging: true,
             audio_exts: vec![
                 "mp3", "flac", "wav", "m4a", "aac", "ogg", "opus", "wma", "alac", "aiff", "amr",
@@ -85,10 +404,18 @@ impl Default for Config {
             .into_iter()
             .map(String::from)
             .collect(),
-            playlist_exts: vec!["m3u", "m3u8", "pls"]
+            playlist_exts: vec!["m3u", "m3u8", "pls", "xspf", "asx"]
                 .into_iter()
                 .map(String::from)
                 .collect(),
+            cover_filenames: default_cover_filenames(),
+            write_ratings_to_tags: false,
+            strict_target_ext: false,
+            editor: String::new(),
+            pager: String::new(),
+            enrich_command: String::new(),
+            disable_hooks: false,
+            browser: String::new(),
             mpv_default_args: vec![
                 "--no-video".to_string(),
                 "--audio-display=no".to_string(),
@@ -98,24 +425,172 @@ impl Default for Config {
                 format!("--term-playing-msg={}", banner_text),
                 format!("--term-status-msg={}", status_msg),
             ],
+            status_msg_override: String::new(),
+            allow_sidecar_args: false,
+            show_banner: true,
+            clear_screen_on_play: true,
+            record_history: false,
+            scrobble_threshold_percent: default_scrobble_threshold_percent(),
+            scrobble_threshold_secs: default_scrobble_threshold_secs(),
+            set_terminal_title: true,
+            avoid_recent_hours: 0,
+            play_decay_half_life_days: default_play_decay_half_life_days(),
+            detach_player: false,
+            idle_quit: true,
+            idle_timeout: 0,
+            radio_after_queue: false,
+            http_status_port: 0,
+            http_status_bind: default_http_status_bind(),
+            fetch_lyrics: false,
+            save_lyrics: false,
+            shuffle_seed: 0,
+            profile_scan: false,
+            quiet_scan: false,
+            no_scan_progress: false,
+            volume_locked: false,
             ytdlp_available: false,
             ytdlp_is_nightly: false,
         }
     }
 }
 
-/// If no configuration exists, it creates one with default values.
-pub fn load(override_path: Option<PathBuf>) -> Result<Config> {
-    log::debug!("Initializing config load sequence");
+impl Config {
+    pub fn wants_window(&self) -> bool {
+        self.show_window.unwrap_or(self.watch)
+    }
+
+    pub fn wants_video(&self) -> bool {
+        self.decode_video.unwrap_or(self.watch)
+    }
+}
+
+/// Values `preferred_audio_codec` understands.
+pub const AUDIO_CODECS: &[&str] = &["opus", "aac", "m4a", "mp3", "vorbis", "ogg", "flac"];
+
+/// The spelling a music dir is stored and matched in: no Windows verbatim prefix
+/// (`\\?\C:\Music` -> `C:\Music`, `\\?\UNC\srv\share` -> `\\srv\share`), which
+/// older versions wrote via `std::fs::canonicalize`, and no trailing separator.
+pub fn normalize_dir(path: &Path) -> PathBuf {
+    let raw = path.to_string_lossy();
+    let plain = if let Some(share) = raw.strip_prefix(r"\\?\UNC\") {
+        format!(r"\\{}", share)
+    } else if let Some(rest) = raw.strip_prefix(r"\\?\")
+        && rest.as_bytes().get(1) == Some(&b':')
+    {
+        rest.to_string()
+    } else {
+        raw.to_string()
+    };
+
+    let trimmed = plain.trim_end_matches(['/', '\\']);
+    // "/" and "C:\" stay as they are
+    if trimmed.is_empty() || trimmed.ends_with(':') {
+        return PathBuf::from(plain);
+    }
+    PathBuf::from(trimmed)
+}
+
+/// Whether two music dir spellings point at the same folder. Windows paths are
+/// case-insensitive.
+pub fn same_dir(a: &Path, b: &Path) -> bool {
+    let (a, b) = (normalize_dir(a), normalize_dir(b));
+    if cfg!(windows) {
+        a.to_string_lossy()
+            .eq_ignore_ascii_case(&b.to_string_lossy())
+    } else {
+        a == b
+    }
+}
 
-    let config_path = match override_path {
-        Some(path) => path,
+fn config_path(override_path: Option<PathBuf>) -> Result<PathBuf> {
+    match override_path {
+        Some(path) => Ok(path),
         None => {
             let dirs = ProjectDirs::from("com", "furqanhun", "mpv-music")
                 .context("Could not determine config paths")?;
-            dirs.config_dir().join("config.toml")
+            Ok(dirs.config_dir().join("config.toml"))
         }
+    }
+}
+
+/// Reads the config if there is one, without creating it or printing warnings.
+/// For the utility flags that run before the real load.
+pub fn peek(override_path: Option<PathBuf>) -> Option<Config> {
+    let content = std::fs::read_to_string(config_path(override_path).ok()?).ok()?;
+    toml::from_str(&content).ok()
+}
+
+// first non-empty of: CLI arg -> config -> env var -> platform default
+fn pick_tool(cli: Option<&str>, configured: &str, env: Option<String>, fallback: &str) -> String {
+    cli.map(str::to_string)
+        .into_iter()
+        .chain(Some(configured.to_string()))
+        .chain(env)
+        .map(|s| s.trim().to_string())
+        .find(|s| !s.is_empty())
+        .unwrap_or_else(|| fallback.to_string())
+}
+
+// with disable_hooks a command from the config file never runs, the CLI arg,
+// env var and platform default still do
+fn configured_tool<'a>(cfg: Option<&'a Config>, value: &'a str, what: &str) -> &'a str {
+    if cfg.is_some_and(|c| c.disable_hooks) && !value.trim().is_empty() {
+        log::warn!("Safe mode: skipped the configured {} '{}'", what, value);
+        return "";
+    }
+    value
+}
+
+/// The `enrich_command` hook for freshly indexed tracks, None when unset or in safe mode.
+pub fn enrich_command(cfg: &Config) -> Option<&str> {
+    let command = configured_tool(Some(cfg), &cfg.enrich_command, "enrich_command").trim();
+    (!command.is_empty()).then_some(command)
+}
+
+/// Editor to open the config with: CLI arg -> `editor` -> `$EDITOR` -> notepad / nano.
+pub fn resolve_editor(cli: Option<&str>, cfg: Option<&Config>) -> String {
+    let fallback = if cfg!(windows) { "notepad" } else { "nano" };
+    let configured = configured_tool(
+        cfg,
+        cfg.map(|c| c.editor.as_str()).unwrap_or_default(),
+        "editor",
+    );
+    pick_tool(cli, configured, std::env::var("EDITOR").ok(), fallback)
+}
+
+/// Pager for the log: CLI arg -> `pager` -> `$PAGER` -> more / less.
+pub fn resolve_pager(cli: Option<&str>, cfg: Option<&Config>) -> String {
+    let fallback = if cfg!(windows) { "more" } else { "less" };
+    let configured = configured_tool(
+        cfg,
+        cfg.map(|c| c.pager.as_str()).unwrap_or_default(),
+        "pager",
+    );
+    pick_tool(cli, configured, std::env::var("PAGER").ok(), fallback)
+}
+
+/// Browser for links: `browser` -> `$BROWSER` (first entry) -> start / open / xdg-open.
+pub fn resolve_browser(cfg: &Config) -> String {
+    let fallback = if cfg!(windows) {
+        "start"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
     };
+    // $BROWSER may be a ':'-separated list of candidates
+    let env = std::env::var("BROWSER")
+        .ok()
+        .and_then(|b| b.split(':').next().map(str::to_string));
+    let configured = configured_tool(Some(cfg), &cfg.browser, "browser");
+    pick_tool(None, configured, env, fallback)
+}
+
+/// If no configuration exists, it creates one with default values.
+pub fn load(override_path: Option<PathBuf>) -> Result<Config> {
+    log::debug!("Initializing config load sequence");
+
+    let config_path = config_path(override_path)?;
 
     let config_dir = config_path
         .parent()
@@ -137,11 +612,33 @@ pub fn load(override_path: Option<PathBuf>) -> Result<Config> {
     log::info!("Loading configuration from: {:?}", config_path);
     let content = std::fs::read_to_string(&config_path)?;
 
-    let mut cfg: Config = toml::from_str(&content).context("Failed to parse config.toml")?;
+    let mut warnings = Vec::new();
 
-    log::debug!("Successfully parsed {} bytes of TOML", content.len());
+    let mut cfg: Config = if content.trim().is_empty() {
+        // truncated to nothing, there's nothing worth keeping a backup of
+        warnings.push(format!(
+            "{:?} was empty. Wrote the defaults back.",
+            config_path
+        ));
+        let default_cfg = Config::default();
+        std::fs::write(&config_path, toml::to_string_pretty(&default_cfg)?)?;
+        default_cfg
+    } else {
+        match toml::from_str(&content) {
+            Ok(cfg) => cfg,
+            Err(e) => {
+                let ask = std::io::stdin().is_terminal() && std::io::stderr().is_terminal();
+                let (cfg, backup) = recover_broken(&config_path, &e, ask)?;
+                warnings.push(format!(
+                    "Config was reset to defaults, the broken one is saved as {:?}.",
+                    backup
+                ));
+                cfg
+            }
+        }
+    };
 
-    let mut warnings = Vec::new();
+    log::debug!("Successfully parsed {} bytes of TOML", content.len());
 
     let legacy_ua = "Mozilla/5.0 (Windows NT 10.0; Win64; x64; rv:109.0) Gecko/20100101 Firefox/114.0";
     if cfg.ytdlp_useragent == legacy_ua {
@@ -150,6 +647,47 @@ pub fn load(override_path: Option<PathBuf>) -> Result<Config> {
         warnings.push("Migrated legacy yt-dlp user agent to the new default.".to_string());
     }
 
+    if let Some(cookies) = &cfg.ytdlp_cookies
+        && !cookies.is_file()
+    {
+        warnings.push(format!(
+            "ytdlp_cookies file {:?} not found. Ignoring it.",
+            cookies
+        ));
+        cfg.ytdlp_cookies = None;
+    }
+
+    cfg.url_resolvers.retain_mut(|resolver| {
+        if let Err(e) = regex::Regex::new(&resolver.pattern) {
+            warnings.push(format!(
+                "url_resolvers pattern '{}' isn't a valid regex ({}). Ignoring it.",
+                resolver.pattern,
+                e.to_string().lines().last().unwrap_or_default().trim()
+            ));
+            return false;
+        }
+        // the raw options list is comma separated, a comma in a value would split it
+        resolver.ytdl_opts.retain(|opt, value| {
+            let ok = !opt.contains([',', '=']) && !value.contains(',');
+            if !ok {
+                warnings.push(format!(
+                    "url_resolvers: '{}={}' can't contain commas. Ignoring it.",
+                    opt, value
+                ));
+            }
+            ok
+        });
+        true
+    });
+
+    if unsafe_status_msg(&cfg.status_msg_override) {
+        warnings.push(
+            "status_msg_override contains control characters, backticks or '$('. Ignoring it."
+                .to_string(),
+        );
+        cfg.status_msg_override.clear();
+    }
+
     if cfg.volume > 130 {
         warnings.push(format!(
             "Volume {} exceeds maximum (130). Reseting to 100.",
@@ -158,6 +696,58 @@ pub fn load(override_path: Option<PathBuf>) -> Result<Config> {
         cfg.volume = 100;
     }
 
+    if let Some(name) = &cfg.preferred_tag_type
+        && crate::indexer::parse_tag_type(name).is_none()
+    {
+        warnings.push(format!(
+            "preferred_tag_type '{}' is not a tag type (try ID3v2, ID3v1, VorbisComments, MP4ilst, APE). Using the primary tag.",
+            name
+        ));
+        cfg.preferred_tag_type = None;
+    }
+
+    if let Some((start, end, cap)) = &cfg.quiet_hours {
+        if parse_clock(start).is_none() || parse_clock(end).is_none() {
+            warnings.push(format!(
+                "quiet_hours times '{}'-'{}' aren't HH:MM. Ignoring them.",
+                start, end
+            ));
+            cfg.quiet_hours = None;
+        } else if *cap > 130 {
+            warnings.push(format!(
+                "quiet_hours cap {} exceeds maximum (130). Ignoring it.",
+                cap
+            ));
+            cfg.quiet_hours = None;
+        }
+    }
+
+    cfg.volume_overrides.retain(|key, volume| {
+        if *volume > 130 {
+            warnings.push(format!(
+                "volume_overrides: {} for '{}' exceeds maximum (130). Ignoring it.",
+                volume, key
+            ));
+        }
+        *volume <= 130
+    });
+
+    if !cfg.idle_quit && cfg.idle_timeout == 0 {
+        warnings.push(
+            "idle_quit = false without an idle_timeout keeps mpv open after the queue ends until you quit it yourself, and mpv-music waits for it. Set idle_timeout to have it quit on its own."
+                .to_string(),
+        );
+    }
+
+    cfg.index_format = cfg.index_format.trim().to_ascii_lowercase();
+    if cfg.index_format != "json" && cfg.index_format != "bin" {
+        warnings.push(format!(
+            "Invalid index_format '{}' (json or bin). Using json.",
+            cfg.index_format
+        ));
+        cfg.index_format = default_index_format();
+    }
+
     let valid_loop_modes = ["inf", "playlist", "no", "off", "false", "track", "file"];
     let is_numeric = cfg.loop_mode.chars().all(|c| c.is_numeric());
 
@@ -169,6 +759,55 @@ pub fn load(override_path: Option<PathBuf>) -> Result<Config> {
         cfg.loop_mode = "inf".to_string();
     }
 
+    let codec = cfg.preferred_audio_codec.trim().to_ascii_lowercase();
+    if !codec.is_empty() && !AUDIO_CODECS.contains(&codec.as_str()) {
+        warnings.push(format!(
+            "Unknown preferred_audio_codec '{}' (use {}). Ignoring it.",
+            cfg.preferred_audio_codec,
+            AUDIO_CODECS.join(", ")
+        ));
+        cfg.preferred_audio_codec.clear();
+    }
+
+    if !crate::glyphs::PRESETS.contains(&cfg.glyph_preset.as_str()) {
+        warnings.push(format!(
+            "Unknown glyph_preset '{}' (use {}). Defaulting to 'emoji'.",
+            cfg.glyph_preset,
+            crate::glyphs::PRESETS.join(", ")
+        ));
+        cfg.glyph_preset = default_glyph_preset();
+    }
+    cfg.glyphs.retain(|key, _| {
+        let known = crate::glyphs::is_key(key);
+        if !known {
+            warnings.push(format!("Unknown glyph '{}' in [glyphs]. Ignoring it.", key));
+        }
+        known
+    });
+
+    // configs from before normalize_dir may hold \\?\ paths the index never uses
+    let mut dirs: Vec<PathBuf> = Vec::new();
+    for dir in &cfg.music_dirs {
+        if !dirs.iter().any(|d| same_dir(d, dir)) {
+            dirs.push(normalize_dir(dir));
+        }
+    }
+    cfg.music_dirs = dirs;
+
+    cfg.audiobook_dirs = cfg
+        .audiobook_dirs
+        .iter()
+        .map(|d| normalize_dir(d))
+        .collect();
+
+    if !(0.25..=4.0).contains(&cfg.audiobook_speed) {
+        warnings.push(format!(
+            "audiobook_speed {} is outside 0.25-4. Reseting to 1.",
+            cfg.audiobook_speed
+        ));
+        cfg.audiobook_speed = default_audiobook_speed();
+    }
+
     if cfg.music_dirs.is_empty() {
         warnings.push(
             "No music directories configured. Run 'mpv-music --manage-dirs' to add folders."
@@ -186,6 +825,60 @@ pub fn load(override_path: Option<PathBuf>) -> Result<Config> {
     Ok(cfg)
 }
 
+// a config that doesn't parse (crashed editor, full disk) would otherwise lock the
+// user out: keep it as <name>.bak and start over from the defaults
+fn recover_broken(
+    config_path: &Path,
+    error: &toml::de::Error,
+    ask: bool,
+) -> Result<(Config, PathBuf)> {
+    log::error!("Failed to parse {:?}: {}", config_path, error);
+    eprintln!(
+        "\x1b[31;1m[Config Error]\x1b[0m Could not parse {:?}:\n{}",
+        config_path, error
+    );
+
+    if ask {
+        eprint!("Reset it to defaults? The broken file is kept as a .bak [Y/n] > ");
+        std::io::stderr().flush()?;
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        if input.trim().eq_ignore_ascii_case("n") {
+            anyhow::bail!("Failed to parse config.toml: {}", error);
+        }
+    }
+
+    let file_name = config_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "config.toml".to_string());
+    let backup = config_path.with_file_name(format!("{}.bak", file_name));
+    std::fs::copy(config_path, &backup)
+        .with_context(|| format!("Could not back up the broken config to {:?}", backup))?;
+
+    let default_cfg = Config::default();
+    std::fs::write(config_path, toml::to_string_pretty(&default_cfg)?)?;
+    log::warn!(
+        "Reset {:?} to defaults, backup at {:?}",
+        config_path,
+        backup
+    );
+    Ok((default_cfg, backup))
+}
+
+/// yt-dlp options for the configured cookies, as `(option, value)`.
+/// A cookies file wins over reading them from a browser.
+pub fn ytdlp_cookie_options(cfg: &Config) -> Option<(&'static str, String)> {
+    if let Some(path) = &cfg.ytdlp_cookies {
+        Some(("cookies", path.to_string_lossy().to_string()))
+    } else {
+        cfg.ytdlp_cookies_from_browser
+            .as_ref()
+            .filter(|b| !b.trim().is_empty())
+            .map(|b| ("cookies-from-browser", b.trim().to_string()))
+    }
+}
+
 pub fn save(config: &Config) -> Result<()> {
     let dirs = ProjectDirs::from("com", "furqanhun", "mpv-music")
         .context("Could not determine config paths")?;
@@ -204,6 +897,82 @@ pub fn save(config: &Config) -> Result<()> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_status_msg_presets_and_validation() {
+        let mut cfg = Config::default();
+        assert_eq!(status_msg(&cfg), None);
+
+        cfg.status_msg_override = "progress".to_string();
+        let (msg, bar) = status_msg(&cfg).unwrap();
+        assert!(msg.contains("${duration}") && bar);
+
+        cfg.status_msg_override = "♪ ${media-title}".to_string();
+        assert_eq!(
+            status_msg(&cfg),
+            Some(("♪ ${media-title}".to_string(), false))
+        );
+
+        assert!(!unsafe_status_msg("▶ ${media-title} • ${time-pos}"));
+        assert!(unsafe_status_msg("line\nbreak"));
+        assert!(unsafe_status_msg("$(rm -rf ~)"));
+    }
+
+    #[test]
+    fn test_normalize_dir_strips_verbatim_prefix() {
+        let norm = |p: &str| normalize_dir(Path::new(p)).to_string_lossy().to_string();
+        assert_eq!(norm(r"\\?\C:\Users\me\Music"), r"C:\Users\me\Music");
+        assert_eq!(norm(r"\\?\UNC\nas\music\"), r"\\nas\music");
+        assert_eq!(norm(r"C:\Music\"), r"C:\Music");
+        assert_eq!(norm(r"C:\"), r"C:\");
+        assert_eq!(norm("/home/me/Music/"), "/home/me/Music");
+        assert_eq!(norm("/"), "/");
+        assert!(same_dir(
+            Path::new(r"\\?\D:\Audio"),
+            Path::new(r"D:\Audio\")
+        ));
+    }
+
+    // what add_directory stores has to match what remove_directory is typed as
+    #[cfg(windows)]
+    #[test]
+    fn test_windows_dir_round_trip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let verbatim = std::fs::canonicalize(dir).unwrap();
+        let stored = normalize_dir(&dunce::canonicalize(dir).unwrap());
+        let typed = dir.to_string_lossy().to_uppercase();
+
+        assert!(verbatim.to_string_lossy().starts_with(r"\\?\"));
+        assert!(!stored.to_string_lossy().starts_with(r"\\?\"));
+        assert!(same_dir(&stored, &verbatim));
+        assert!(same_dir(&stored, Path::new(&typed)));
+    }
+
+    #[test]
+    fn test_tool_fallback_order() {
+        let env = || Some("vim".to_string());
+        assert_eq!(pick_tool(Some("code"), "hx", env(), "nano"), "code");
+        assert_eq!(pick_tool(None, "hx", env(), "nano"), "hx");
+        assert_eq!(pick_tool(None, "", env(), "nano"), "vim");
+        assert_eq!(pick_tool(None, " ", Some(String::new()), "nano"), "nano");
+    }
+
+    #[test]
+    fn test_safe_mode_skips_configured_tools() {
+        let mut cfg = Config {
+            editor: "evil-editor".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            configured_tool(Some(&cfg), &cfg.editor, "editor"),
+            "evil-editor"
+        );
+        cfg.disable_hooks = true;
+        assert_eq!(configured_tool(Some(&cfg), &cfg.editor, "editor"), "");
+        // an explicit CLI choice is not a config hook
+        assert_eq!(resolve_editor(Some("vi"), Some(&cfg)), "vi");
+    }
+
     #[test]
     fn test_default_config_values() {
         let cfg = Config::default();
@@ -214,6 +983,27 @@ mod tests {
         assert!(!cfg.watch);
     }
 
+    #[test]
+    fn test_quiet_hours_over_midnight() {
+        let cfg = Config {
+            quiet_hours: Some(("22:00".to_string(), "07:00".to_string(), 30)),
+            ..Default::default()
+        };
+        assert_eq!(quiet_hours_cap(&cfg, 23 * 60), Some(30));
+        assert_eq!(quiet_hours_cap(&cfg, 6 * 60 + 59), Some(30));
+        assert_eq!(quiet_hours_cap(&cfg, 7 * 60), None);
+        assert_eq!(quiet_hours_cap(&cfg, 12 * 60), None);
+
+        let day = Config {
+            quiet_hours: Some(("13:00".to_string(), "15:30".to_string(), 50)),
+            ..Default::default()
+        };
+        assert_eq!(quiet_hours_cap(&day, 14 * 60), Some(50));
+        assert_eq!(quiet_hours_cap(&day, 16 * 60), None);
+        assert_eq!(parse_clock("24:00"), None);
+        assert_eq!(quiet_hours_cap(&Config::default(), 0), None);
+    }
+
     #[test]
     fn test_default_music_dirs_not_empty() {
         let cfg = Config::default();
@@ -239,6 +1029,8 @@ mod tests {
         // Playlist extensions
         assert!(cfg.playlist_exts.contains(&"m3u".to_string()));
         assert!(cfg.playlist_exts.contains(&"m3u8".to_string()));
+        assert!(cfg.playlist_exts.contains(&"xspf".to_string()));
+        assert!(cfg.playlist_exts.contains(&"asx".to_string()));
     }
 
     #[test]
@@ -311,6 +1103,25 @@ mod tests {
         assert!(!cfg.ytdlp_is_nightly);
     }
 
+    #[test]
+    fn test_banner_defaults_preserve_clearing() {
+        let cfg = Config::default();
+        assert!(cfg.show_banner);
+        assert!(cfg.clear_screen_on_play);
+    }
+
+    #[test]
+    fn test_banner_fields_default_when_missing() {
+        let mut value = toml::Value::try_from(Config::default()).unwrap();
+        let table = value.as_table_mut().unwrap();
+        table.remove("show_banner");
+        table.remove("clear_screen_on_play");
+
+        let cfg: Config = value.try_into().unwrap();
+        assert!(cfg.show_banner);
+        assert!(cfg.clear_screen_on_play);
+    }
+
     #[test]
     fn test_mpv_default_args_present() {
         let cfg = Config::default();
@@ -321,4 +1132,31 @@ mod tests {
                 .any(|arg| arg.contains("--no-video"))
         );
     }
+
+    #[test]
+    fn test_partial_config_fills_defaults() {
+        let cfg: Config = toml::from_str("volume = 80\nshuffle = false\n").unwrap();
+        assert_eq!(cfg.volume, 80);
+        assert!(!cfg.shuffle);
+        assert_eq!(cfg.loop_mode, "inf");
+        assert!(!cfg.audio_exts.is_empty());
+    }
+
+    #[test]
+    fn test_broken_config_is_backed_up() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let path = dir.join("config.toml");
+        std::fs::write(&path, "volume = 80\nmusic_dirs = [\"/mu").unwrap();
+
+        let error = toml::from_str::<Config>(&std::fs::read_to_string(&path).unwrap()).unwrap_err();
+        let (cfg, backup) = recover_broken(&path, &error, false).unwrap();
+        let kept = std::fs::read_to_string(&backup).unwrap();
+        let rewritten: Config = toml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+
+        assert_eq!(backup, dir.join("config.toml.bak"));
+        assert!(kept.ends_with("[\"/mu"));
+        assert_eq!(cfg.volume, 100);
+        assert_eq!(rewritten.volume, 100);
+    }
 }
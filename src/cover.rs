@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Looks up the cover image next to a track (cover.jpg, folder.jpg...), for
+/// tracks without embedded art. Each album dir is listed once per lookup, so
+/// browsing an album doesn't read the folder for every track.
+pub struct Covers {
+    // cover_filenames from the config, trimmed and lowercased
+    names: Vec<String>,
+    // album dir -> its cover
+    cache: Mutex<HashMap<PathBuf, Option<PathBuf>>>,
+}
+
+impl Covers {
+    pub fn new(names: &[String]) -> Covers {
+        Covers {
+            names: names
+                .iter()
+                .map(|n| n.trim().to_lowercase())
+                .filter(|n| !n.is_empty())
+                .collect(),
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The cover for `track_path`. Names are matched case-insensitively, earlier
+    /// names in `cover_filenames` win.
+    pub fn find(&self, track_path: &str) -> Option<PathBuf> {
+        let dir = Path::new(track_path).parent()?.to_path_buf();
+
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(cached) = cache.get(&dir) {
+            return cached.clone();
+        }
+
+        let found = pick_cover(&dir, &self.names);
+        log::trace!("Cover for {:?}: {:?}", dir, found);
+        cache.insert(dir, found.clone());
+        found
+    }
+}
+
+fn pick_cover(dir: &Path, names: &[String]) -> Option<PathBuf> {
+    let files: Vec<PathBuf> = std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect();
+
+    names.iter().find_map(|wanted| {
+        files
+            .iter()
+            .find(|f| {
+                f.file_name()
+                    .is_some_and(|n| n.to_string_lossy().to_lowercase() == *wanted)
+            })
+            .cloned()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cover_name_priority() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        for name in ["01 - Song.flac", "Folder.JPG", "front.png"] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+
+        let names = ["cover.jpg", "folder.jpg", "front.png"].map(String::from);
+        let picked = pick_cover(dir, &names);
+        let none = pick_cover(dir, &["cover.jpg".to_string()]);
+
+        assert_eq!(picked, Some(dir.join("Folder.JPG")));
+        assert_eq!(none, None);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::config::Config;
+use crate::tool_cache::{self, Deps};
 use anyhow::Result;
 use std::process::{Command, Stdio, exit};
 
@@ -6,6 +7,41 @@ use std::process::{Command, Stdio, exit};
 use std::os::windows::process::CommandExt;
 
 pub fn check(cfg: &mut Config) -> Result<()> {
+    // mpv is looked up every time, a cached check mustn't hide that it's gone
+    let mpv_cmd = if cfg!(windows) { "mpv.com" } else { "mpv" };
+    if let Some(deps) = tool_cache::cached_deps()
+        && on_path(mpv_cmd)
+    {
+        log::info!(
+            "Dependencies: using cached check (mpv found, yt-dlp available: {}, nightly: {})",
+            deps.ytdlp_available,
+            deps.ytdlp_is_nightly
+        );
+        cfg.ytdlp_available = deps.ytdlp_available;
+        cfg.ytdlp_is_nightly = deps.ytdlp_is_nightly;
+        return Ok(());
+    }
+
+    probe(cfg)?;
+    // only reached with mpv present, a missing mpv exits before this. A missing
+    // yt-dlp isn't cached, so installing it shows up on the next launch
+    if cfg.ytdlp_available {
+        tool_cache::store_deps(Deps {
+            ytdlp_available: cfg.ytdlp_available,
+            ytdlp_is_nightly: cfg.ytdlp_is_nightly,
+        });
+    }
+    Ok(())
+}
+
+// whether `bin` is an executable file in one of PATH's dirs, without spawning anything
+fn on_path(bin: &str) -> bool {
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(bin).is_file()))
+        .unwrap_or(false)
+}
+
+fn probe(cfg: &mut Config) -> Result<()> {
     log::info!("Checking external dependencies...");
 
     // Spawn both processes WITHOUT waiting (true parallelism without thread overhead)
@@ -45,12 +81,7 @@ pub fn check(cfg: &mut Config) -> Result<()> {
     match mpv_output {
         Ok(output) => {
             let raw_output = String::from_utf8_lossy(&output.stdout);
-            let mpv_line = raw_output.lines().next().unwrap_or("Unknown Version");
-            let ffmpeg_line = raw_output
-                .lines()
-                .find(|l| l.contains("FFmpeg version"))
-                .map(|s| s.trim())
-                .unwrap_or("FFmpeg version: Unknown");
+            let (mpv_line, ffmpeg_line) = mpv_lines(&raw_output);
 
             log::info!("Dependency 'mpv': Found");
             log::info!(" └─ {}", mpv_line);
@@ -118,8 +149,135 @@ pub fn check(cfg: &mut Config) -> Result<()> {
     Ok(())
 }
 
+// "mpv v0.40.0 Copyright..." and the "FFmpeg version: ..." it was built against
+fn mpv_lines(raw: &str) -> (&str, &str) {
+    let mpv_line = raw.lines().next().unwrap_or("Unknown Version");
+    let ffmpeg_line = raw
+        .lines()
+        .find(|l| l.contains("FFmpeg version"))
+        .map(|s| s.trim())
+        .unwrap_or("FFmpeg version: Unknown");
+    (mpv_line, ffmpeg_line)
+}
+
+// stdout of `<bin> <arg>`, None when it's missing or fails
+fn tool_output(bin: &str, arg: &str) -> Option<String> {
+    let mut command = Command::new(bin);
+    command.arg(arg).stdin(Stdio::null()).stderr(Stdio::null());
+
+    #[cfg(windows)]
+    command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let output = command.output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// `--versions`: this build plus every external tool it talks to, for bug reports.
+/// Missing tools show up as "not found" instead of stopping anything.
+pub fn print_versions() {
+    const NOT_FOUND: &str = "not found";
+    let features: Vec<&str> = [
+        ("update", cfg!(feature = "update")),
+        ("http-status", cfg!(feature = "http-status")),
+        ("lyrics", cfg!(feature = "lyrics")),
+    ]
+    .into_iter()
+    .filter_map(|(name, on)| on.then_some(name))
+    .collect();
+
+    println!(
+        "mpv-music  {} ({}-{}, features: {})",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        std::env::consts::ARCH,
+        if features.is_empty() {
+            "none".to_string()
+        } else {
+            features.join(", ")
+        }
+    );
+
+    let mpv_cmd = if cfg!(windows) { "mpv.com" } else { "mpv" };
+    match tool_output(mpv_cmd, "--version") {
+        Some(raw) => {
+            let (mpv_line, ffmpeg_line) = mpv_lines(&raw);
+            println!("mpv        {}", mpv_line);
+            println!("           {}", ffmpeg_line);
+        }
+        None => println!("mpv        {}", NOT_FOUND),
+    }
+
+    match tool_output("yt-dlp", "--version") {
+        Some(version) => {
+            let channel = if version.split('.').count() >= 4 || version.contains("nightly") {
+                "nightly"
+            } else {
+                "stable"
+            };
+            println!("yt-dlp     {} ({})", version, channel);
+        }
+        None => println!("yt-dlp     {}", NOT_FOUND),
+    }
+
+    let ffmpeg = tool_output("ffmpeg", "-version");
+    let ffmpeg_line = ffmpeg.as_deref().and_then(|raw| raw.lines().next());
+    println!("ffmpeg     {}", ffmpeg_line.unwrap_or(NOT_FOUND));
+
+    let runtime = match crate::player::detect_js_runtime() {
+        crate::tool_cache::JsRuntime::Missing => NOT_FOUND.to_string(),
+        found => format!("{:?}", found).to_lowercase(),
+    };
+    println!("JS runtime {}", runtime);
+}
+
+/// First line of `ffmpeg -version`, None when ffmpeg isn't installed.
+/// Only --transcode needs it, so it's checked on demand rather than at startup.
+pub fn ffmpeg_version() -> Option<String> {
+    let mut command = Command::new("ffmpeg");
+    command
+        .arg("-version")
+        .stdin(Stdio::null())
+        .stderr(Stdio::null());
+
+    #[cfg(windows)]
+    command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    match command.output() {
+        Ok(output) if output.status.success() => {
+            let version = String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .next()
+                .unwrap_or("ffmpeg (unknown version)")
+                .to_string();
+            log::info!("Dependency 'ffmpeg': Found ({})", version);
+            Some(version)
+        }
+        _ => {
+            log::warn!("Dependency 'ffmpeg' not found.");
+            None
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mpv_version_lines() {
+        let raw = "mpv v0.40.0 Copyright © 2000-2025 mpv/MPlayer/mplayer2 projects\n built on Jan  1\nFFmpeg version: 7.1.1\nFFmpeg library versions:\n";
+        assert_eq!(
+            mpv_lines(raw),
+            (
+                "mpv v0.40.0 Copyright © 2000-2025 mpv/MPlayer/mplayer2 projects",
+                "FFmpeg version: 7.1.1"
+            )
+        );
+        assert_eq!(mpv_lines("").1, "FFmpeg version: Unknown");
+    }
 
     #[test]
     fn test_version_parsing_nightly() {
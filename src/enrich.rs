@@ -0,0 +1,160 @@
+use crate::indexer::Track;
+use anyhow::{Context, Result, bail};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Probed tracks handed to `enrich_command` at once, one process per batch.
+pub const BATCH: usize = 200;
+
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    }
+}
+
+fn run(command: &str, input: String) -> Result<String> {
+    let mut child = shell(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Could not run enrich_command '{}'", command))?;
+
+    // written from its own thread, a hook that answers as it reads would
+    // otherwise fill the stdout pipe while we're still feeding stdin
+    let mut stdin = child.stdin.take().context("enrich_command has no stdin")?;
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output()?;
+    let _ = writer.join();
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stderr.trim().is_empty() {
+        log::debug!("enrich_command stderr: {}", stderr.trim());
+    }
+    if !output.status.success() {
+        bail!("enrich_command exited with {}", output.status);
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// one line back per track sent, in order. A line that isn't a Track keeps the
+// original; the scan's own bookkeeping (path, mtime, size...) is never the hook's
+fn merge(batch: Vec<Track>, stdout: &str) -> Vec<Track> {
+    let lines: Vec<&str> = stdout.lines().filter(|l| !l.trim().is_empty()).collect();
+    if lines.len() != batch.len() {
+        log::warn!(
+            "enrich_command returned {} lines for {} tracks, keeping them as probed",
+            lines.len(),
+            batch.len()
+        );
+        return batch;
+    }
+
+    batch
+        .into_iter()
+        .zip(lines)
+        .map(
+            |(original, line)| match serde_json::from_str::<Track>(line) {
+                Ok(enriched) => Track {
+                    path: original.path,
+                    mtime: original.mtime,
+                    size: original.size,
+                    first_indexed: original.first_indexed,
+                    media_type: original.media_type,
+                    ..enriched
+                },
+                Err(e) => {
+                    log::warn!(
+                        "enrich_command output for {} isn't a track: {}",
+                        original.path,
+                        e
+                    );
+                    original
+                }
+            },
+        )
+        .collect()
+}
+
+/// Pipes freshly probed tracks through the user's `enrich_command` as JSON
+/// Lines and takes back the fields it changed. Any failure keeps the batch
+/// as it was probed.
+pub fn enrich(command: &str, batch: Vec<Track>) -> Vec<Track> {
+    let mut input = String::new();
+    for track in &batch {
+        match serde_json::to_string(track) {
+            Ok(line) => {
+                input.push_str(&line);
+                input.push('\n');
+            }
+            Err(e) => {
+                log::warn!(
+                    "Could not serialize {} for enrich_command: {}",
+                    track.path,
+                    e
+                );
+                return batch;
+            }
+        }
+    }
+
+    match run(command, input) {
+        Ok(stdout) => {
+            log::debug!("enrich_command processed {} tracks", batch.len());
+            merge(batch, &stdout)
+        }
+        Err(e) => {
+            log::warn!("{:#}, keeping {} tracks as probed", e, batch.len());
+            batch
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(path: &str) -> Track {
+        Track {
+            path: path.to_string(),
+            title: "x".to_string(),
+            genre: "UNKNOWN".to_string(),
+            media_type: "audio".to_string(),
+            size: 10,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_merge_keeps_bookkeeping_and_bad_lines() {
+        let batch = vec![track("/m/a.mp3"), track("/m/b.mp3")];
+        let stdout = r#"{"path":"/elsewhere","title":"x","artist":"A","album":"B","genre":"Jazz","track_no":null,"disc_no":null,"bitrate":null,"mtime":0,"size":0,"first_indexed":0,"media_type":"video"}
+not json
+"#;
+        let merged = merge(batch.clone(), stdout);
+        assert_eq!(merged[0].genre, "Jazz");
+        assert_eq!(merged[0].path, "/m/a.mp3");
+        assert_eq!(merged[0].size, 10);
+        assert_eq!(merged[0].media_type, "audio");
+        assert_eq!(merged[1].genre, "UNKNOWN");
+
+        // a short answer can't be matched up, so nothing is taken
+        let short = merge(batch, "{}\n");
+        assert_eq!(short[0].genre, "UNKNOWN");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_enrich_through_a_command() {
+        let batch = vec![track("/m/a.mp3")];
+        let enriched = enrich("sed 's/UNKNOWN/Ambient/'", batch.clone());
+        assert_eq!(enriched[0].genre, "Ambient");
+        assert_eq!(enrich("exit 3", batch)[0].genre, "UNKNOWN");
+    }
+}
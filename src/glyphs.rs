@@ -0,0 +1,175 @@
+use crate::config::Config;
+
+/// The icons in picker prompts and previews. Each has a config key under
+/// `[glyphs]`, its emoji and a plain ASCII stand-in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Glyph {
+    Menu,
+    Tracks,
+    Video,
+    Folders,
+    Audiobook,
+    Playlists,
+    Stream,
+    Search,
+    Results,
+    Filter,
+    Genre,
+    Artist,
+    Album,
+    Composer,
+    Work,
+    Format,
+    Radio,
+    Settings,
+    Manage,
+    Remove,
+}
+
+const ALL: [Glyph; 20] = [
+    Glyph::Menu,
+    Glyph::Tracks,
+    Glyph::Video,
+    Glyph::Folders,
+    Glyph::Audiobook,
+    Glyph::Playlists,
+    Glyph::Stream,
+    Glyph::Search,
+    Glyph::Results,
+    Glyph::Filter,
+    Glyph::Genre,
+    Glyph::Artist,
+    Glyph::Album,
+    Glyph::Composer,
+    Glyph::Work,
+    Glyph::Format,
+    Glyph::Radio,
+    Glyph::Settings,
+    Glyph::Manage,
+    Glyph::Remove,
+];
+
+pub const PRESETS: [&str; 2] = ["emoji", "ascii"];
+
+impl Glyph {
+    fn key(self) -> &'static str {
+        match self {
+            Glyph::Menu => "menu",
+            Glyph::Tracks => "tracks",
+            Glyph::Video => "video",
+            Glyph::Folders => "folders",
+            Glyph::Audiobook => "audiobook",
+            Glyph::Playlists => "playlists",
+            Glyph::Stream => "stream",
+            Glyph::Search => "search",
+            Glyph::Results => "results",
+            Glyph::Filter => "filter",
+            Glyph::Genre => "genre",
+            Glyph::Artist => "artist",
+            Glyph::Album => "album",
+            Glyph::Composer => "composer",
+            Glyph::Work => "work",
+            Glyph::Format => "format",
+            Glyph::Radio => "radio",
+            Glyph::Settings => "settings",
+            Glyph::Manage => "manage",
+            Glyph::Remove => "remove",
+        }
+    }
+
+    fn emoji(self) -> &'static str {
+        match self {
+            Glyph::Menu => "🎧",
+            Glyph::Tracks => "🎵",
+            Glyph::Video => "🎬",
+            Glyph::Folders => "📁",
+            Glyph::Audiobook => "📖",
+            Glyph::Playlists | Glyph::Work => "📜",
+            Glyph::Stream => "📺",
+            Glyph::Search | Glyph::Filter => "🔎",
+            Glyph::Results => "🎯",
+            Glyph::Genre => "🏷️",
+            Glyph::Artist => "🎤",
+            Glyph::Album => "💿",
+            Glyph::Composer => "🎼",
+            Glyph::Format => "🎚️",
+            Glyph::Radio => "📻",
+            Glyph::Settings => "⚙️",
+            Glyph::Manage => "📂",
+            Glyph::Remove => "🗑️",
+        }
+    }
+
+    fn ascii(self) -> &'static str {
+        match self {
+            Glyph::Menu => ">>",
+            Glyph::Tracks => "*",
+            Glyph::Video => "[v]",
+            Glyph::Folders | Glyph::Manage => "[/]",
+            Glyph::Audiobook => "[b]",
+            Glyph::Playlists | Glyph::Work => "[=]",
+            Glyph::Stream => "[>]",
+            Glyph::Search | Glyph::Filter => "?",
+            Glyph::Results => "[?]",
+            Glyph::Genre => "#",
+            Glyph::Artist => "@",
+            Glyph::Album => "(o)",
+            Glyph::Composer => "&",
+            Glyph::Format => "~",
+            Glyph::Radio => "((.))",
+            Glyph::Settings => "%",
+            Glyph::Remove => "[x]",
+        }
+    }
+}
+
+pub fn is_key(key: &str) -> bool {
+    ALL.iter().any(|g| g.key() == key)
+}
+
+/// The glyph `cfg` asks for: its `[glyphs]` override, else the preset's.
+pub fn get(cfg: &Config, glyph: Glyph) -> String {
+    match cfg.glyphs.get(glyph.key()) {
+        Some(custom) => custom.clone(),
+        None if cfg.glyph_preset == "ascii" => glyph.ascii().to_string(),
+        None => glyph.emoji().to_string(),
+    }
+}
+
+/// "🎵 Tracks > ". Emoji with a variation selector (⚙️, 🗑️) render a column
+/// narrower than they claim in most terminals, they get a second space.
+pub fn prompt(cfg: &Config, glyph: Glyph, label: &str) -> String {
+    let icon = get(cfg, glyph);
+    let gap = if icon.ends_with('\u{FE0F}') {
+        "  "
+    } else {
+        " "
+    };
+    if icon.is_empty() {
+        format!("{} > ", label)
+    } else {
+        format!("{}{}{} > ", icon, gap, label)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preset_and_overrides() {
+        let ascii = Config {
+            glyph_preset: "ascii".to_string(),
+            glyphs: [("tracks".to_string(), "\u{f001}".to_string())].into(),
+            ..Default::default()
+        };
+        assert_eq!(get(&ascii, Glyph::Tracks), "\u{f001}");
+        assert_eq!(get(&ascii, Glyph::Folders), "[/]");
+        assert_eq!(get(&Config::default(), Glyph::Menu), "🎧");
+        assert!(
+            ALL.iter()
+                .all(|g| !g.ascii().is_empty() && g.ascii().is_ascii())
+        );
+        assert!(is_key("radio") && !is_key("Radio"));
+    }
+}
@@ -0,0 +1,371 @@
+use anyhow::{Context, Result};
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Entry {
+    pub timestamp: u64,
+    pub path: String,
+}
+
+fn history_path() -> Result<PathBuf> {
+    let dirs = ProjectDirs::from("com", "furqanhun", "mpv-music")
+        .context("Could not determine data directory")?;
+    Ok(dirs.data_dir().join("history.jsonl"))
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Appends a single play to the history log.
+pub fn record(path: &str) -> Result<()> {
+    let history_path = history_path()?;
+    if let Some(parent) = history_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let entry = Entry {
+        timestamp: now(),
+        path: path.to_string(),
+    };
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&history_path)?;
+    serde_json::to_writer(&mut file, &entry)?;
+    writeln!(file)?;
+
+    log::debug!("History: recorded {}", path);
+    Ok(())
+}
+
+// tracks under this long count after SHORT_TRACK_SECS at most
+const SHORT_TRACK: f64 = 30.0;
+const SHORT_TRACK_SECS: f64 = 10.0;
+
+/// Seconds into a track before it counts as listened: `percent` of it or `secs`,
+/// whichever comes first. Streams and other unknown lengths only have `secs`.
+pub fn listen_threshold(duration: Option<f64>, percent: u8, secs: u32) -> f64 {
+    let absolute = f64::from(secs);
+    match duration.filter(|d| *d > 0.0) {
+        Some(length) => {
+            let share = length * f64::from(percent.min(100)) / 100.0;
+            let absolute = if length < SHORT_TRACK {
+                absolute.min(SHORT_TRACK_SECS)
+            } else {
+                absolute
+            };
+            share.min(absolute)
+        }
+        None => absolute,
+    }
+}
+
+/// Follows mpv's `path`, `duration` and `time-pos` and says when the current
+/// track has played long enough to go into the history, once per track.
+pub struct Listen {
+    percent: u8,
+    secs: u32,
+    path: Option<String>,
+    duration: Option<f64>,
+    counted: bool,
+}
+
+impl Listen {
+    pub fn new(percent: u8, secs: u32) -> Self {
+        Listen {
+            percent,
+            secs,
+            path: None,
+            duration: None,
+            counted: false,
+        }
+    }
+
+    /// Feeds one property change, returns the path once it has been listened to.
+    pub fn update(&mut self, name: &str, data: &Value) -> Option<String> {
+        match name {
+            "path" => {
+                self.path = data.as_str().map(String::from);
+                self.duration = None;
+                self.counted = false;
+                None
+            }
+            "duration" => {
+                self.duration = data.as_f64();
+                None
+            }
+            "time-pos" => {
+                let pos = data.as_f64()?;
+                if self.counted || pos < listen_threshold(self.duration, self.percent, self.secs) {
+                    return None;
+                }
+                self.counted = true;
+                self.path.clone()
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Loads the history log, skipping anything unreadable. Missing file = empty history.
+pub fn load() -> Vec<Entry> {
+    let Ok(path) = history_path() else {
+        return Vec::new();
+    };
+    let Ok(file) = File::open(&path) else {
+        return Vec::new();
+    };
+
+    BufReader::new(file)
+        .lines()
+        .map_while(|l| l.ok())
+        .filter_map(|l| serde_json::from_str::<Entry>(&l).ok())
+        .collect()
+}
+
+/// Paths played within the last `hours` hours.
+pub fn recent_paths(entries: &[Entry], hours: u32, now: u64) -> HashSet<String> {
+    let cutoff = now.saturating_sub(u64::from(hours) * 3600);
+    entries
+        .iter()
+        .filter(|e| e.timestamp >= cutoff)
+        .map(|e| e.path.clone())
+        .collect()
+}
+
+/// Play counts where each play loses half its weight every `half_life_days`,
+/// so what's on repeat lately outranks old favourites. 0 days = plain counts.
+pub fn compute_decayed_scores(
+    entries: &[Entry],
+    half_life_days: u32,
+    now: u64,
+) -> HashMap<String, f64> {
+    let half_life = f64::from(half_life_days) * 86_400.0;
+    let mut scores: HashMap<String, f64> = HashMap::new();
+    for entry in entries {
+        let weight = if half_life > 0.0 {
+            let age = now.saturating_sub(entry.timestamp) as f64;
+            0.5_f64.powf(age / half_life)
+        } else {
+            1.0
+        };
+        *scores.entry(entry.path.clone()).or_default() += weight;
+    }
+    scores
+}
+
+/// A history export: the file's text, the plays in it and the plays left out.
+pub struct Export {
+    pub text: String,
+    pub plays: usize,
+    pub skipped: usize,
+}
+
+// scrobbler.log fields are tab separated, CSV ones quoted when they need it
+fn log_field(value: &str) -> String {
+    value.replace(['\t', '\n', '\r'], " ")
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Writes `entries` as a Rockbox/Audioscrobbler `scrobbler.log`, or as CSV
+/// (timestamp,artist,album,title,duration) with `csv`. Tags come from the
+/// index; plays of tracks it no longer has, or without an artist and title,
+/// are skipped since no scrobble service takes them.
+pub fn export(entries: &[Entry], tracks: &[crate::indexer::Track], csv: bool) -> Export {
+    let by_path: HashMap<&str, &crate::indexer::Track> =
+        tracks.iter().map(|t| (t.path.as_str(), t)).collect();
+    let mut text = if csv {
+        "timestamp,artist,album,title,duration\n".to_string()
+    } else {
+        format!(
+            "#AUDIOSCROBBLER/1.1\n#TZ/UTC\n#CLIENT/mpv-music {}\n",
+            env!("CARGO_PKG_VERSION")
+        )
+    };
+
+    let (mut plays, mut skipped) = (0, 0);
+    for entry in entries {
+        let Some(track) = by_path
+            .get(entry.path.as_str())
+            .filter(|t| t.artist != "UNKNOWN" && t.title != "UNKNOWN")
+        else {
+            skipped += 1;
+            continue;
+        };
+        let album = if track.album == "UNKNOWN" {
+            ""
+        } else {
+            track.album.as_str()
+        };
+        let duration = track.duration.map(|d| d.to_string()).unwrap_or_default();
+        let line = if csv {
+            format!(
+                "{},{},{},{},{}\n",
+                entry.timestamp,
+                csv_field(&track.artist),
+                csv_field(album),
+                csv_field(&track.title),
+                duration
+            )
+        } else {
+            // artist, album, title, track no, seconds, L(istened), time, mbid
+            format!(
+                "{}\t{}\t{}\t{}\t{}\tL\t{}\t\n",
+                log_field(&track.artist),
+                log_field(album),
+                log_field(&track.title),
+                track.track_no.map(|n| n.to_string()).unwrap_or_default(),
+                duration,
+                entry.timestamp
+            )
+        };
+        text.push_str(&line);
+        plays += 1;
+    }
+    Export {
+        text,
+        plays,
+        skipped,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(timestamp: u64, path: &str) -> Entry {
+        Entry {
+            timestamp,
+            path: path.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_recent_paths_window() {
+        let now = 200_000;
+        let entries = vec![
+            entry(now - 60, "/music/a.mp3"),
+            entry(now - 3 * 3600, "/music/b.mp3"),
+            entry(now - 30 * 3600, "/music/c.mp3"),
+        ];
+
+        let recent = recent_paths(&entries, 4, now);
+        assert!(recent.contains("/music/a.mp3"));
+        assert!(recent.contains("/music/b.mp3"));
+        assert!(!recent.contains("/music/c.mp3"));
+    }
+
+    #[test]
+    fn test_listen_threshold() {
+        // half of a 3 minute track, 4 minutes into a long one
+        assert_eq!(listen_threshold(Some(180.0), 50, 240), 90.0);
+        assert_eq!(listen_threshold(Some(1200.0), 50, 240), 240.0);
+        assert_eq!(listen_threshold(Some(20.0), 100, 240), 10.0);
+        assert_eq!(listen_threshold(None, 50, 240), 240.0);
+        assert_eq!(listen_threshold(Some(180.0), 0, 240), 0.0);
+    }
+
+    #[test]
+    fn test_listen_counts_once_per_track() {
+        use serde_json::json;
+        let mut listen = Listen::new(50, 240);
+        assert_eq!(listen.update("path", &json!("/m/a.mp3")), None);
+        assert_eq!(listen.update("duration", &json!(100.0)), None);
+        assert_eq!(listen.update("time-pos", &json!(10.0)), None);
+        assert_eq!(
+            listen.update("time-pos", &json!(50.5)).as_deref(),
+            Some("/m/a.mp3")
+        );
+        assert_eq!(listen.update("time-pos", &json!(60.0)), None);
+
+        // skipped through before the threshold, never recorded
+        listen.update("path", &json!("/m/b.mp3"));
+        listen.update("duration", &json!(100.0));
+        assert_eq!(listen.update("time-pos", &json!(5.0)), None);
+        assert_eq!(listen.update("path", &json!("/m/c.mp3")), None);
+    }
+
+    #[test]
+    fn test_decayed_scores_favor_recent_plays() {
+        let day = 86_400;
+        let now = 1000 * day;
+        // three plays a year ago against two this week
+        let entries = vec![
+            entry(now - 365 * day, "/music/old.mp3"),
+            entry(now - 366 * day, "/music/old.mp3"),
+            entry(now - 367 * day, "/music/old.mp3"),
+            entry(now - day, "/music/new.mp3"),
+            entry(now, "/music/new.mp3"),
+        ];
+
+        let decayed = compute_decayed_scores(&entries, 30, now);
+        assert!(decayed["/music/new.mp3"] > decayed["/music/old.mp3"]);
+        assert!((decayed["/music/new.mp3"] - 1.977).abs() < 0.01);
+
+        let plain = compute_decayed_scores(&entries, 0, now);
+        assert_eq!(plain["/music/old.mp3"], 3.0);
+        assert!(compute_decayed_scores(&[], 30, now).is_empty());
+    }
+
+    #[test]
+    fn test_recent_paths_zero_hours() {
+        let now = 200_000;
+        let entries = vec![entry(now - 60, "/music/a.mp3")];
+        assert!(recent_paths(&entries, 0, now).is_empty());
+    }
+
+    #[test]
+    fn test_export_formats() {
+        let track = |path: &str, artist: &str| crate::indexer::Track {
+            path: path.to_string(),
+            artist: artist.to_string(),
+            album: "UNKNOWN".to_string(),
+            title: "Hey, You".to_string(),
+            duration: Some(285),
+            track_no: Some(3),
+            ..Default::default()
+        };
+        let tracks = vec![
+            track("/music/a.mp3", "Pink Floyd"),
+            track("/music/b.mp3", "UNKNOWN"),
+        ];
+        let entries = vec![
+            entry(1_700_000_000, "/music/a.mp3"),
+            entry(1_700_000_500, "/music/b.mp3"),
+            entry(1_700_000_900, "/music/gone.mp3"),
+        ];
+
+        let log = export(&entries, &tracks, false);
+        assert_eq!((log.plays, log.skipped), (1, 2));
+        assert!(log.text.starts_with("#AUDIOSCROBBLER/1.1\n#TZ/UTC\n"));
+        assert!(
+            log.text
+                .ends_with("Pink Floyd\t\tHey, You\t3\t285\tL\t1700000000\t\n")
+        );
+
+        let csv = export(&entries, &tracks, true);
+        assert_eq!(
+            csv.text,
+            "timestamp,artist,album,title,duration\n1700000000,Pink Floyd,,\"Hey, You\",285\n"
+        );
+    }
+}
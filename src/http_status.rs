@@ -0,0 +1,133 @@
+use crate::config::Config;
+use crate::ipc;
+use serde_json::{Map, Value, json};
+use std::sync::{Arc, Mutex, OnceLock};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+type NowPlaying = Arc<Mutex<Map<String, Value>>>;
+
+// what a remote display needs, kept in sync by an IPC observer
+const PROPERTIES: &[&str] = &[
+    "media-title",
+    "path",
+    "metadata",
+    "pause",
+    "time-pos",
+    "duration",
+    "playlist-pos",
+    "playlist-count",
+];
+
+static NOW_PLAYING: OnceLock<NowPlaying> = OnceLock::new();
+static SERVER_STARTED: OnceLock<()> = OnceLock::new();
+
+fn now_playing() -> &'static NowPlaying {
+    NOW_PLAYING.get_or_init(NowPlaying::default)
+}
+
+fn is_loopback(bind: &str) -> bool {
+    matches!(bind, "127.0.0.1" | "localhost" | "::1")
+}
+
+/// Hooks the current mpv instance up to the status server, starting the
+/// server itself on first use. The socket path is per-process, so one server
+/// outlives every mpv launch in the session.
+pub fn attach(socket: &str, config: &Config) {
+    let observer = ipc::observe(socket.to_string(), PROPERTIES, |name, data| {
+        if let Ok(mut state) = now_playing().lock() {
+            state.insert(name.to_string(), data.clone());
+        }
+    });
+    std::thread::spawn(move || {
+        let _ = observer.join();
+        if let Ok(mut state) = now_playing().lock() {
+            state.clear();
+        }
+    });
+
+    let socket = socket.to_string();
+    let bind = config.http_status_bind.clone();
+    let port = config.http_status_port;
+    SERVER_STARTED.get_or_init(|| {
+        if !is_loopback(&bind) {
+            log::warn!("HTTP status server exposed on {}:{}", bind, port);
+            eprintln!(
+                "⚠️  HTTP status server is listening on {}:{} with NO authentication.\n   Anyone on that network can see what's playing and skip/pause it.",
+                bind, port
+            );
+        }
+        std::thread::spawn(move || serve(&bind, port, &socket));
+    });
+}
+
+fn serve(bind: &str, port: u16, socket: &str) {
+    let server = match Server::http((bind, port)) {
+        Ok(s) => s,
+        Err(e) => {
+            log::warn!("HTTP status server failed to bind {}:{}: {}", bind, port, e);
+            return;
+        }
+    };
+    log::info!("HTTP status server listening on http://{}:{}", bind, port);
+
+    for request in server.incoming_requests() {
+        let (code, body) = route(request.method(), request.url(), socket);
+        respond(request, code, body);
+    }
+}
+
+fn route(method: &Method, url: &str, socket: &str) -> (u16, Value) {
+    let path = url.split('?').next().unwrap_or(url);
+    let command: &[&str] = match (method, path) {
+        (Method::Get, "/" | "/status") => return (200, status()),
+        (Method::Post, "/next") => &["playlist-next"],
+        (Method::Post, "/prev") => &["playlist-prev"],
+        (Method::Post, "/pause") => &["cycle", "pause"],
+        (_, "/" | "/status" | "/next" | "/prev" | "/pause") => {
+            return (405, json!({ "error": "method not allowed" }));
+        }
+        _ => return (404, json!({ "error": "not found" })),
+    };
+
+    match ipc::send_command(socket, command) {
+        Ok(()) => (200, json!({ "ok": true })),
+        Err(e) => {
+            log::debug!("HTTP status: '{}' failed: {}", path, e);
+            (503, json!({ "error": "mpv is not running" }))
+        }
+    }
+}
+
+fn status() -> Value {
+    let state = now_playing().lock().map(|s| s.clone()).unwrap_or_default();
+    let playing = !state.is_empty();
+
+    let mut body = Map::new();
+    body.insert("playing".to_string(), Value::Bool(playing));
+    body.extend(state);
+    Value::Object(body)
+}
+
+fn respond(request: Request, code: u16, body: Value) {
+    let mut response = Response::from_string(body.to_string()).with_status_code(code);
+    if let Ok(header) = Header::from_bytes("Content-Type", "application/json") {
+        response.add_header(header);
+    }
+    if let Err(e) = request.respond(response) {
+        log::debug!("HTTP status: failed to respond: {}", e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routes_without_mpv() {
+        let socket = "/nonexistent/mpv-music-test.sock";
+        assert_eq!(route(&Method::Get, "/status", socket).0, 200);
+        assert_eq!(route(&Method::Post, "/next", socket).0, 503);
+        assert_eq!(route(&Method::Get, "/next", socket).0, 405);
+        assert_eq!(route(&Method::Get, "/nope", socket).0, 404);
+    }
+}
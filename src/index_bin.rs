@@ -0,0 +1,153 @@
+use crate::indexer::Track;
+use anyhow::{Context, Result, bail};
+use bincode::{Decode, Encode};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+// bump VERSION whenever Track or Header change, older files are then rebuilt
+const MAGIC: [u8; 8] = *b"MPVMIDX\0";
+const VERSION: u8 = 2;
+// a damaged length mustn't have it reserve more than this up front
+const LIMIT: usize = 1 << 30;
+
+/// Which JSONL index a binary copy was made from: its size and mtime. A copy
+/// whose stamp doesn't match the JSONL on disk is stale.
+#[derive(Debug, Clone, Copy, PartialEq, Encode, Decode)]
+pub struct Stamp {
+    size: u64,
+    mtime_nanos: u64,
+}
+
+impl Stamp {
+    pub fn of(path: &Path) -> Option<Stamp> {
+        let meta = std::fs::metadata(path).ok()?;
+        let mtime = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Stamp {
+            size: meta.len(),
+            mtime_nanos: mtime.as_nanos() as u64,
+        })
+    }
+}
+
+// read on its own first, a stale or foreign copy isn't decoded any further
+#[derive(Encode, Decode)]
+struct Header {
+    magic: [u8; 8],
+    version: u8,
+    stamp: Stamp,
+}
+
+fn config() -> impl bincode::config::Config {
+    bincode::config::standard().with_limit::<LIMIT>()
+}
+
+/// Writes `tracks` to `path` as a binary copy of the JSONL index stamped with
+/// `stamp`. Goes through a temporary file, a crash never leaves half a copy.
+pub fn write(path: &Path, tracks: &[Track], stamp: Stamp) -> Result<()> {
+    let header = Header {
+        magic: MAGIC,
+        version: VERSION,
+        stamp,
+    };
+    let mut bytes = bincode::encode_to_vec(&header, config())?;
+    bytes.extend(bincode::encode_to_vec(tracks, config())?);
+
+    let temp_path = path.with_extension("bin.tmp");
+    std::fs::write(&temp_path, &bytes)
+        .with_context(|| format!("Could not write {:?}", temp_path))?;
+    std::fs::rename(&temp_path, path).with_context(|| format!("Could not replace {:?}", path))?;
+    Ok(())
+}
+
+/// The tracks in the binary copy at `path`, None when it was made from another
+/// JSONL than `stamp` describes (or by another version). Errors mean it's damaged.
+pub fn read(path: &Path, stamp: Stamp) -> Result<Option<Vec<Track>>> {
+    let buf = std::fs::read(path).with_context(|| format!("Could not read {:?}", path))?;
+
+    let (header, used): (Header, usize) = bincode::decode_from_slice(&buf, config())
+        .with_context(|| format!("{:?} isn't a binary index", path))?;
+    if header.magic != MAGIC {
+        bail!("{:?} isn't a binary index", path);
+    }
+    if header.version != VERSION || header.stamp != stamp {
+        return Ok(None);
+    }
+
+    let (tracks, read): (Vec<Track>, usize) = bincode::decode_from_slice(&buf[used..], config())
+        .with_context(|| format!("{:?} is damaged", path))?;
+    if used + read != buf.len() {
+        bail!(
+            "{:?} has {} bytes past its last track",
+            path,
+            buf.len() - used - read
+        );
+    }
+    Ok(Some(tracks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_and_stale_copies() {
+        let full = Track {
+            path: "/m/Bach/01 – Präludium.flac".to_string(),
+            title: "Präludium".to_string(),
+            artist: "Glenn Gould".to_string(),
+            album: "WTC I".to_string(),
+            album_artist: "Glenn Gould".to_string(),
+            artists: vec!["A".to_string(), "B".to_string()],
+            genre: "Classical".to_string(),
+            comment: "1962".to_string(),
+            track_no: Some(1),
+            disc_no: Some(1),
+            track_total: Some(24),
+            disc_total: Some(2),
+            bitrate: Some(900),
+            duration: Some(141),
+            bpm: Some(72),
+            year: Some(1963),
+            original_year: Some(1962),
+            composer: Some("J.S. Bach".to_string()),
+            grouping: Some("BWV 846".to_string()),
+            work: Some("Prelude and Fugue in C".to_string()),
+            movement: Some("Präludium".to_string()),
+            movement_no: Some(1),
+            mtime: 1_700_000_000,
+            size: 12_345_678,
+            first_indexed: 1_600_000_000,
+            media_type: "audio".to_string(),
+        };
+        let bare = Track {
+            path: "/m/x.mp3".to_string(),
+            media_type: "audio".to_string(),
+            ..Default::default()
+        };
+        let tracks = vec![full, bare];
+
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("index.bin");
+        let stamp = Stamp {
+            size: 10,
+            mtime_nanos: 20,
+        };
+        write(&path, &tracks, stamp).unwrap();
+
+        let back = read(&path, stamp).unwrap().unwrap();
+        let json = |t: &[Track]| serde_json::to_string(t).unwrap();
+        assert_eq!(json(&back), json(&tracks));
+
+        // the JSONL moved on since the copy was made
+        let newer = Stamp {
+            mtime_nanos: 21,
+            ..stamp
+        };
+        assert!(read(&path, newer).unwrap().is_none());
+
+        // cut short: an error, not a silently shorter library
+        let bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &bytes[..bytes.len() - 3]).unwrap();
+        assert!(read(&path, stamp).is_err());
+    }
+}
@@ -1,27 +1,70 @@
-use anyhow::{Context, Result};
+use anyhow::{Context, Result, bail};
+use bincode::{Decode, Encode};
 use directories::ProjectDirs;
 use indicatif::{ProgressBar, ProgressStyle};
 use lofty::prelude::*;
 use lofty::probe::Probe;
+use lofty::tag::TagType;
 use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap, HashSet};
 use std::fs::File;
-use std::io::{BufReader, BufWriter, Write};
-use std::time::{Duration, SystemTime};
-use walkdir::WalkDir;
+use std::io::{BufRead, BufReader, BufWriter, IsTerminal, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant, SystemTime};
 
 use crate::config::Config;
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+// Encode/Decode are for the binary copy of the index (index_bin)
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, Clone, Default)]
 pub struct Track {
     pub path: String,
     pub title: String,
     pub artist: String,
     pub album: String,
+    #[serde(default)]
+    pub album_artist: String, // empty when untagged
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub artists: Vec<String>, // every credited artist, only set when there is more than one
     pub genre: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub comment: String, // free text, searchable but never a picker facet
+    #[serde(default)]
+    pub track_no: Option<u32>,
+    #[serde(default)]
+    pub disc_no: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track_total: Option<u32>, // TRACKTOTAL, tracks on this disc per the tags
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub disc_total: Option<u32>,
+    #[serde(default)]
+    pub bitrate: Option<u32>, // kbps, None for playlists and unreadable files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<u32>, // seconds, None for playlists and unreadable files
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bpm: Option<u32>, // from the BPM tag, rounded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub year: Option<u16>, // release/recording date of this edition
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_year: Option<u16>, // first release, differs for reissues and remasters
+    // classical tags, only read with `read_classical_tags` and left out of the index when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub composer: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grouping: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub work: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub movement: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub movement_no: Option<u32>,
     pub mtime: u64,
     pub size: u64,
+    #[serde(default)]
+    pub first_indexed: u64, // when a scan first saw this path, unlike mtime a re-tag doesn't move it
     pub media_type: String,
 }
 
@@ -41,12 +84,499 @@ pub(crate) fn parse_filename_metadata(filename: &str) -> (String, String) {
     }
 }
 
+// lightweight view of an index line, enough to decide cache hits
+// without materialising the whole Track
+#[derive(Deserialize)]
+struct IndexProbe {
+    path: String,
+    mtime: u64,
+    size: u64,
+    #[serde(default)]
+    first_indexed: u64,
+}
+
+struct CacheSlot {
+    mtime: u64,
+    size: u64,
+    first_indexed: u64,
+    offset: u64,
+}
+
+// entries from before first_indexed existed get their mtime, the best guess left
+fn first_seen(first_indexed: u64, mtime: u64) -> u64 {
+    if first_indexed > 0 {
+        first_indexed
+    } else {
+        mtime
+    }
+}
+
+/// Memory-frugal view of the previous index used during incremental scans.
+/// Only path -> (mtime, size, offset) is kept in memory; unchanged entries are
+/// rehydrated by seeking back into the old file, through a handle per worker.
+struct OldIndex {
+    path: PathBuf,
+    by_path: HashMap<String, CacheSlot>,
+    // (size, mtime, filename) -> offset, for moved/renamed files
+    by_attr: HashMap<(u64, u64, String), u64>,
+}
+
+impl OldIndex {
+    fn open(index_path: &Path) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(index_path)?);
+        let mut by_path = HashMap::new();
+        let mut by_attr = HashMap::new();
+
+        let mut line = String::new();
+        let mut offset = 0u64;
+        loop {
+            line.clear();
+            let read = reader.read_line(&mut line)?;
+            if read == 0 {
+                break;
+            }
+
+            if let Ok(probe) = serde_json::from_str::<IndexProbe>(line.trim_end()) {
+                if let Some(fname) = Path::new(&probe.path).file_name() {
+                    let key = (probe.size, probe.mtime, fname.to_string_lossy().to_string());
+                    by_attr.entry(key).or_insert(offset);
+                }
+                by_path.insert(
+                    probe.path,
+                    CacheSlot {
+                        mtime: probe.mtime,
+                        size: probe.size,
+                        first_indexed: first_seen(probe.first_indexed, probe.mtime),
+                        offset,
+                    },
+                );
+            }
+            offset += read as u64;
+        }
+
+        Ok(Self {
+            path: index_path.to_path_buf(),
+            by_path,
+            by_attr,
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.by_path.len()
+    }
+
+    // a worker's own handle, they'd only queue up behind a shared one
+    fn handle(&self) -> Option<File> {
+        File::open(&self.path).ok()
+    }
+
+    // `is_it` checks the entry read back is the one the offset was taken for,
+    // should the file have changed under us
+    fn rehydrate(
+        &self,
+        file: &mut File,
+        offset: u64,
+        is_it: impl FnOnce(&Track) -> bool,
+    ) -> Option<Track> {
+        file.seek(SeekFrom::Start(offset)).ok()?;
+
+        let mut line = String::new();
+        BufReader::new(file).read_line(&mut line).ok()?;
+        serde_json::from_str::<Track>(line.trim_end())
+            .ok()
+            .filter(is_it)
+    }
+}
+
+// --no-index: every read and write of the index goes through index_path, which
+// refuses once this is set
+static DISABLED: AtomicBool = AtomicBool::new(false);
+
+/// Turns the on-disk index off for the rest of the run (`--no-index`).
+pub fn disable() {
+    DISABLED.store(true, Ordering::Relaxed);
+    log::info!("Index disabled for this run");
+}
+
+// files between two progress lines when stderr isn't a terminal
+const PLAIN_PROGRESS_EVERY: u64 = 5000;
+
+fn index_path() -> Result<PathBuf> {
+    if DISABLED.load(Ordering::Relaxed) {
+        bail!("The music index is off for this run (--no-index)");
+    }
+    let dirs = ProjectDirs::from("com", "furqanhun", "mpv-music")
+        .context("Could not determine data directory")?;
+    Ok(dirs.data_dir().join("music_index.jsonl"))
+}
+
+fn binary_path() -> Result<PathBuf> {
+    Ok(index_path()?.with_extension("bin"))
+}
+
+/// Deletes the index and the directory stamps that describe it. Returns false
+/// if there was no index.
+pub fn clear_index() -> Result<bool> {
+    let path = index_path()?;
+    // stamps without their index would vouch for entries that are gone
+    let stamps = dir_stamps_path()?;
+    if stamps.exists() {
+        std::fs::remove_file(&stamps).with_context(|| format!("Failed to remove {:?}", stamps))?;
+    }
+    let binary = binary_path()?;
+    if binary.exists() {
+        std::fs::remove_file(&binary).with_context(|| format!("Failed to remove {:?}", binary))?;
+    }
+    if !path.exists() {
+        return Ok(false);
+    }
+    std::fs::remove_file(&path).with_context(|| format!("Failed to remove {:?}", path))?;
+    log::info!("Index removed: {:?}", path);
+    Ok(true)
+}
+
+#[derive(Debug, PartialEq, Eq, Hash)]
+enum FileId {
+    #[cfg(unix)]
+    Inode(u64, u64),
+    #[cfg(not(unix))]
+    Path(PathBuf),
+}
+
+// what the path points at in the end: device+inode on Unix, the resolved path elsewhere
+fn file_identity(path: &Path) -> Option<FileId> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let meta = std::fs::metadata(path).ok()?;
+        Some(FileId::Inode(meta.dev(), meta.ino()))
+    }
+    #[cfg(not(unix))]
+    {
+        dunce::canonicalize(path).ok().map(FileId::Path)
+    }
+}
+
+fn dir_stamps_path() -> Result<PathBuf> {
+    Ok(index_path()?.with_file_name("dir_stamps.json"))
+}
+
+/// What a directory looked like when the index was last written. While its mtime
+/// holds, nothing was added, removed or renamed in it, so an incremental scan
+/// reuses the index entries and subdir list instead of listing it again.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct DirStamp {
+    mtime: u64, // nanoseconds, seconds are too coarse for a copy that lands right after a scan
+    subdirs: Vec<String>,
+    tracks: usize, // index entries directly inside, fewer means the index lost some
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct DirStamps {
+    // settings that decide which files get indexed, a change means listing everything again
+    fingerprint: String,
+    dirs: HashMap<String, DirStamp>,
+}
+
+impl DirStamps {
+    fn fingerprint(config: &Config) -> String {
+        let sorted = |exts: &[String]| {
+            let mut exts: Vec<String> = to_set(exts).into_iter().collect();
+            exts.sort();
+            exts.join(",")
+        };
+        format!(
+            "{}|{}|{}|video:{}|hidden:{}|dedup:{}",
+            sorted(&config.audio_exts),
+            sorted(&config.video_exts),
+            sorted(&config.playlist_exts),
+            config.video_ok,
+            config.scan_hidden_dirs,
+            config.dedup_by_inode
+        )
+    }
+
+    fn load(fingerprint: &str) -> Option<Self> {
+        let text = std::fs::read_to_string(dir_stamps_path().ok()?).ok()?;
+        let stamps: Self = serde_json::from_str(&text).ok()?;
+        if stamps.fingerprint != fingerprint {
+            log::info!("Scan settings changed since the last scan, listing every directory");
+            return None;
+        }
+        Some(stamps)
+    }
+
+    // only once the index these stamps describe is safely on disk
+    fn save(&self) {
+        let result = dir_stamps_path().and_then(|path| {
+            std::fs::write(&path, serde_json::to_string(self)?)?;
+            Ok(path)
+        });
+        match result {
+            Ok(path) => log::debug!("Saved {} directory stamps to {:?}", self.dirs.len(), path),
+            Err(e) => log::warn!("Failed to save directory stamps: {}", e),
+        }
+    }
+}
+
+fn mtime_nanos(meta: &std::fs::Metadata) -> u64 {
+    meta.modified()
+        .ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_nanos() as u64)
+}
+
+/// Depth-first walk that yields candidate files. Directories whose stamp still
+/// matches aren't listed: their indexed files and recorded subdirs stand in for
+/// the listing, and each of those files still gets the usual mtime/size check.
+struct DirWalker<'a> {
+    dirs: Vec<PathBuf>,
+    files: Vec<PathBuf>,
+    old: Option<&'a DirStamps>,
+    indexed: &'a HashMap<PathBuf, Vec<String>>,
+    fresh: &'a Mutex<HashMap<String, DirStamp>>,
+    reused: &'a AtomicUsize,
+    scan_hidden: bool,
+}
+
+impl Iterator for DirWalker<'_> {
+    type Item = PathBuf;
+
+    fn next(&mut self) -> Option<PathBuf> {
+        loop {
+            if let Some(file) = self.files.pop() {
+                return Some(file);
+            }
+            let dir = self.dirs.pop()?;
+            self.visit(dir);
+        }
+    }
+}
+
+impl DirWalker<'_> {
+    fn visit(&mut self, dir: PathBuf) {
+        let Ok(meta) = std::fs::metadata(&dir) else {
+            log::debug!("Can't stat directory {:?}, skipping", dir);
+            return;
+        };
+        let mtime = mtime_nanos(&meta);
+        let key = dir.to_string_lossy().to_string();
+        let indexed = self.indexed.get(&dir).map_or(&[][..], Vec::as_slice);
+
+        if let Some(stamp) = self.old.and_then(|old| old.dirs.get(&key))
+            && stamp.mtime == mtime
+            && stamp.tracks == indexed.len()
+        {
+            log::trace!("Directory unchanged, reusing its entries: {:?}", dir);
+            self.files.extend(indexed.iter().rev().map(PathBuf::from));
+            let mut subdirs = stamp.subdirs.clone();
+            subdirs.sort();
+            self.dirs
+                .extend(subdirs.iter().rev().map(|name| dir.join(name)));
+            self.fresh
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(key, stamp.clone());
+            self.reused.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            log::debug!("Can't list directory {:?}, skipping", dir);
+            return;
+        };
+        // listed by name, so every scan walks (and writes) in the same order
+        let mut entries: Vec<std::fs::DirEntry> = entries.flatten().collect();
+        entries.sort_by_key(|e| e.file_name());
+        let (mut subdirs, mut children, mut files) = (Vec::new(), Vec::new(), Vec::new());
+        let mut stampable = true;
+        for entry in entries {
+            let name = entry.file_name();
+            if !self.scan_hidden && name.to_string_lossy().starts_with('.') {
+                continue;
+            }
+            // symlinked directories aren't followed, symlinked files are
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                match name.to_str() {
+                    Some(utf8) => subdirs.push(utf8.to_string()),
+                    None => stampable = false,
+                }
+                children.push(entry.path());
+            } else {
+                files.push(entry.path());
+            }
+        }
+        // popped from the back, so reversed to visit and yield in listing order
+        self.files.extend(files.into_iter().rev());
+        self.dirs.extend(children.into_iter().rev());
+
+        if stampable {
+            self.fresh.lock().unwrap_or_else(|e| e.into_inner()).insert(
+                key,
+                DirStamp {
+                    mtime,
+                    subdirs,
+                    tracks: 0, // counted as the tracks come out of the scan
+                },
+            );
+        }
+    }
+}
+
+/// Scans the configured directories and returns every track in memory.
 pub fn scan(config: &Config, force: bool) -> Result<Vec<Track>> {
+    let mut tracks = Vec::new();
+    // the in-memory result isn't the index on disk, so its stamps aren't kept
+    scan_into(config, force, |track| tracks.push(track))?;
+    Ok(tracks)
+}
+
+/// Scans the configured directories and streams entries straight into a
+/// temporary index file, which then replaces `music_index.jsonl`. Entries below
+/// none of them (merged in from another machine) can't be scanned and are
+/// copied over from the old file as they are. Memory stays bounded by the cache
+/// map instead of the whole library.
+pub fn scan_to_index(config: &Config, force: bool) -> Result<usize> {
+    let index_path = index_path()?;
+    let mut writer = IndexWriter::create(&index_path)?;
+    let (count, stamps) = scan_into(config, force, |track| writer.push(&track))?;
+
+    let kept = copy_outside(&index_path, &config.music_dirs, &mut writer);
+    if kept > 0 {
+        log::info!("Kept {} entries outside the music dirs", kept);
+    }
+    let total = writer.finish()?;
+    stamps.save();
+    log::info!(
+        "Index replaced in place ({} scanned, {} entries): {:?}",
+        count,
+        total,
+        index_path
+    );
+    Ok(total)
+}
+
+fn under_any(path: &str, dirs: &[PathBuf]) -> bool {
+    dirs.iter().any(|dir| Path::new(path).starts_with(dir))
+}
+
+// copies the old index's entries a scan of the music dirs won't see, line by
+// line. Returns how many
+fn copy_outside(old: &Path, dirs: &[PathBuf], writer: &mut IndexWriter) -> usize {
+    let Ok(file) = File::open(old) else {
+        return 0;
+    };
+    let mut kept = 0;
+    for line in BufReader::new(file).lines().map_while(|l| l.ok()) {
+        let Ok(probe) = serde_json::from_str::<IndexProbe>(&line) else {
+            continue;
+        };
+        if !under_any(&probe.path, dirs) {
+            writer.push_line(&line);
+            kept += 1;
+        }
+    }
+    kept
+}
+
+/// Writes an index one line per track as they come, into a temporary file
+/// that replaces the index in `finish`. A crash never leaves half an index, and
+/// a scan still reading the old file keeps its handle on it.
+struct IndexWriter {
+    target: PathBuf,
+    temp: PathBuf,
+    out: BufWriter<File>,
+    written: usize,
+    // the first failed write, reported by `finish`
+    error: Option<std::io::Error>,
+}
+
+impl IndexWriter {
+    fn create(target: &Path) -> Result<Self> {
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let temp = target.with_extension("jsonl.tmp");
+        let out = BufWriter::new(
+            File::create(&temp).with_context(|| format!("Could not create {:?}", temp))?,
+        );
+        Ok(Self {
+            target: target.to_path_buf(),
+            temp,
+            out,
+            written: 0,
+            error: None,
+        })
+    }
+
+    fn push(&mut self, track: &Track) {
+        match serde_json::to_string(track) {
+            Ok(line) => self.push_line(&line),
+            Err(e) => {
+                self.error.get_or_insert(e.into());
+            }
+        }
+    }
+
+    fn push_line(&mut self, line: &str) {
+        if self.error.is_some() {
+            return;
+        }
+        match writeln!(self.out, "{}", line.trim_end()) {
+            Ok(()) => self.written += 1,
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    /// Swaps the temporary file in, returns how many entries it holds.
+    fn finish(mut self) -> Result<usize> {
+        let flushed = match self.error.take() {
+            Some(e) => Err(e),
+            None => self.out.flush(),
+        };
+        drop(self.out);
+        if let Err(e) = flushed {
+            let _ = std::fs::remove_file(&self.temp);
+            return Err(e).context("Failed to write index entries");
+        }
+        std::fs::rename(&self.temp, &self.target).context("Failed to swap index file")?;
+        Ok(self.written)
+    }
+}
+
+/// Drops the entries below `dir` from the index, once it's no longer a music
+/// dir (a rescan keeps whatever lies outside the music dirs). Returns how many.
+pub fn forget_dir(dir: &Path, format: &str) -> Result<usize> {
+    let (mut tracks, _) = load_index(format)?;
+    let before = tracks.len();
+    tracks.retain(|t| !Path::new(&t.path).starts_with(dir));
+    let dropped = before - tracks.len();
+    if dropped > 0 {
+        save(&tracks)?;
+        log::info!("Dropped {} index entries below {:?}", dropped, dir);
+    }
+    Ok(dropped)
+}
+
+/// Rebuilds the persistent index on disk and loads the fresh result.
+pub fn rescan(config: &Config, force: bool) -> Result<Vec<Track>> {
+    scan_to_index(config, force)?;
+    Ok(load_index(&config.index_format)?.0)
+}
+
+// files probed together: each chunk comes back in walk order, so the sink sees
+// the same order every scan without the whole library being held
+const SCAN_CHUNK: usize = 1024;
+
+fn scan_into<F>(config: &Config, force: bool, mut sink: F) -> Result<(usize, DirStamps)>
+where
+    F: FnMut(Track),
+{
     if config.music_dirs.is_empty() {
         log::warn!("Scan aborted: No music directories configured.");
         eprintln!("   Run 'mpv-music --add-dir <PATH>' to add your music folder.");
         eprintln!("   Or use 'mpv-music --manage-dirs' for the menu.");
-        return Ok(Vec::new());
+        return Ok((0, DirStamps::default()));
     }
 
     log::info!("Starting library scan. Force reindex: {}", force);
@@ -55,34 +585,59 @@ pub fn scan(config: &Config, force: bool) -> Result<Vec<Track>> {
     let video_exts = to_set(&config.video_exts);
     let playlist_exts = to_set(&config.playlist_exts);
 
-    // load existing index for caching and smart recovery
-    let (old_cache, recovery_map) = if !force {
-        if let Ok((old_tracks, _)) = load_index() {
-            log::info!("Cache loaded. Found {} existing entries", old_tracks.len());
-
-            let mut path_map = HashMap::new();
-            let mut attr_map = HashMap::new();
-
-            for t in old_tracks {
-                // primary cache: lookup by exact path
-                path_map.insert(t.path.clone(), t.clone());
+    // load existing index for caching and smart recovery. A forced reindex still
+    // reads it, but only to keep first_indexed
+    let old_index = match index_path().and_then(|p| OldIndex::open(&p)) {
+        Ok(old) => {
+            log::info!("Cache loaded. Found {} existing entries", old.len());
+            Some(old)
+        }
+        Err(e) => {
+            log::debug!("No usable cache: {}", e);
+            None
+        }
+    };
+    if force {
+        log::info!("Forced reindex requested. Ignoring existing cache");
+    }
 
-                // secondary cache: lookup by attributes (size + mtime + filename)
-                if let Some(fname) = std::path::Path::new(&t.path).file_name() {
-                    let key = (t.size, t.mtime, fname.to_string_lossy().to_string());
-                    attr_map.entry(key).or_insert(t);
-                }
+    let fingerprint = DirStamps::fingerprint(config);
+    let old_stamps = old_index
+        .as_ref()
+        .filter(|_| !force)
+        .and_then(|_| DirStamps::load(&fingerprint));
+    // the index grouped by folder, an unchanged folder's listing is rebuilt from it
+    let mut indexed: HashMap<PathBuf, Vec<String>> = HashMap::new();
+    if let (Some(_), Some(old)) = (&old_stamps, &old_index) {
+        for path in old.by_path.keys() {
+            if let Some(parent) = Path::new(path).parent() {
+                indexed
+                    .entry(parent.to_path_buf())
+                    .or_default()
+                    .push(path.clone());
             }
-            (path_map, attr_map)
-        } else {
-            (HashMap::new(), HashMap::new())
         }
+        for files in indexed.values_mut() {
+            files.sort();
+        }
+    }
+    let fresh_stamps = Mutex::new(HashMap::new());
+    let reused_dirs = AtomicUsize::new(0);
+    let scan_started = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+
+    // a spinner on a terminal; redirected (cron logs) it would only leave control
+    // codes behind, so a plain line every PLAIN_PROGRESS_EVERY files instead
+    let plain_progress =
+        !config.quiet_scan && !config.no_scan_progress && !std::io::stderr().is_terminal();
+    let pb = if config.quiet_scan || config.no_scan_progress || plain_progress {
+        ProgressBar::hidden()
     } else {
-        log::info!("Forced reindex requested. Ignoring existing cache");
-        (HashMap::new(), HashMap::new())
+        ProgressBar::new_spinner()
     };
-
-    let pb = ProgressBar::new_spinner();
+    let files_seen = AtomicU64::new(0);
     pb.set_style(
         ProgressStyle::default_spinner()
             .template("{spinner:.green} [{elapsed_precise}] {pos} tracks ({per_sec})")
@@ -91,209 +646,734 @@ pub fn scan(config: &Config, force: bool) -> Result<Vec<Track>> {
     pb.enable_steady_tick(Duration::from_millis(100));
 
     let scan_hidden = config.scan_hidden_dirs;
+    let opts = ProbeOpts::from_config(config);
+    let mut count = 0;
+    let profile = config.profile_scan.then(ScanProfile::default);
+    let enrich_command = crate::config::enrich_command(config);
+    let started = Instant::now();
     // scan loop
-    let tracks: Vec<Track> = config
-        .music_dirs
-        .iter()
-        .flat_map(|dir| {
-            log::info!("Walking directory: {:?}", dir);
-            WalkDir::new(dir)
-                .into_iter()
-                .filter_entry(move |e| {
-                    if scan_hidden {
-                        return true;
-                    }
-                    let is_hidden = e
-                        .file_name()
-                        .to_str()
-                        .map(|s| s.starts_with('.') && s != "." && s != "..")
-                        .unwrap_or(false);
-                    !is_hidden
-                })
-                .filter_map(|e| e.ok())
-        })
-        .par_bridge()
-        .filter_map(|entry| {
-            let path = entry.path();
-            if !path.is_file() {
-                return None;
-            }
+    for dir in &config.music_dirs {
+        log::info!("Walking directory: {:?}", dir);
+    }
+    let walker = DirWalker {
+        // "/music/" and "/music" are the same stamp
+        dirs: config
+            .music_dirs
+            .iter()
+            .rev()
+            .map(|dir| dir.components().collect())
+            .collect(),
+        files: Vec::new(),
+        old: old_stamps.as_ref(),
+        indexed: &indexed,
+        fresh: &fresh_stamps,
+        reused: &reused_dirs,
+        scan_hidden,
+    };
+    // the walk is serial, so which duplicate survives only depends on walk order
+    let dedup = config.dedup_by_inode;
+    let mut seen = HashSet::new();
+    let mut walker = walker.filter(move |path| {
+        if !dedup {
+            return true;
+        }
+        let Some(id) = file_identity(path) else {
+            return true;
+        };
+        let first = seen.insert(id);
+        if !first {
+            log::debug!("Skipping {:?}, same file as a path already indexed", path);
+        }
+        first
+    });
+    let profile_ref = profile.as_ref();
+    let mut paths = std::iter::from_fn(move || {
+        let start = Instant::now();
+        let next = walker.next();
+        if let Some(p) = profile_ref {
+            p.walk_nanos
+                .fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        }
+        next
+    });
+    let probe = |old_file: &mut Option<File>, entry: &PathBuf| -> Option<(Track, bool)> {
+        let path = entry.as_path();
 
-            // log::trace!("Examining file: {:?}", path);
+        // log::trace!("Examining file: {:?}", path);
 
-            let ext = path.extension()?.to_str()?.to_lowercase();
+        let ext = path.extension()?.to_str()?.to_lowercase();
 
-            let media_type = if audio_exts.contains(&ext) {
-                "audio"
-            } else if playlist_exts.contains(&ext) {
-                "playlist"
-            } else if config.video_ok && video_exts.contains(&ext) {
-                "video"
-            } else {
-                // log::trace!("Skipping non-media extension: .{}", ext);
-                return None;
-            };
-
-            pb.inc(1);
-
-            let metadata = entry.metadata().ok()?;
-            let mtime = metadata
-                .modified()
-                .unwrap_or(SystemTime::UNIX_EPOCH)
-                .duration_since(SystemTime::UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs();
-            let size = metadata.len();
-            let path_str = path.to_string_lossy().to_string();
+        let media_type = if audio_exts.contains(&ext) {
+            "audio"
+        } else if playlist_exts.contains(&ext) {
+            "playlist"
+        } else if config.video_ok && video_exts.contains(&ext) {
+            "video"
+        } else {
+            // log::trace!("Skipping non-media extension: .{}", ext);
+            return None;
+        };
 
+        let metadata = std::fs::metadata(path).ok().filter(|m| m.is_file())?;
+        pb.inc(1);
+        let seen = files_seen.fetch_add(1, Ordering::Relaxed) + 1;
+        if plain_progress && seen.is_multiple_of(PLAIN_PROGRESS_EVERY) {
+            eprintln!("Indexed {} tracks...", seen);
+        }
+
+        let mtime = metadata
+            .modified()
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let size = metadata.len();
+        let path_str = path.to_string_lossy().to_string();
+
+        if let (Some(old), Some(file)) = (old_index.as_ref().filter(|_| !force), old_file) {
             // smort check
-            if let Some(old_track) = old_cache
+            if let Some(slot) = old
+                .by_path
                 .get(&path_str)
-                .filter(|t| t.mtime == mtime && t.size == size)
+                .filter(|s| s.mtime == mtime && s.size == size)
+                && let Some(mut old_track) =
+                    old.rehydrate(file, slot.offset, |t| t.path == path_str)
             {
                 log::debug!("Cache hit (Unchanged): {}", path_str);
-                return Some(old_track.clone());
+                old_track.first_indexed = slot.first_indexed;
+                return Some((old_track, false));
             }
 
             let filename = path.file_name()?.to_string_lossy().to_string();
             let recovery_key = (size, mtime, filename);
-            if let Some(recovered) = recovery_map.get(&recovery_key) {
+            if let Some(offset) = old.by_attr.get(&recovery_key)
+                && let Some(mut recovered) = old.rehydrate(file, *offset, |t| {
+                    t.size == size && t.mtime == mtime && t.path.ends_with(&recovery_key.2)
+                })
+            {
                 log::debug!("Smart Recovery (Moved/Renamed): {}", path_str);
-                let mut new_entry = recovered.clone();
-                new_entry.path = path_str;
-                return Some(new_entry);
+                recovered.path = path_str;
+                recovered.first_indexed = first_seen(recovered.first_indexed, mtime);
+                return Some((recovered, false));
+            }
+        }
+
+        // a changed file keeps the date it was first seen, a new one gets this scan's
+        let first_indexed = old_index
+            .as_ref()
+            .and_then(|old| old.by_path.get(&path_str))
+            .map_or(scan_started, |slot| slot.first_indexed);
+
+        log::debug!("Cache miss: Probing {}", path_str);
+        let start = Instant::now();
+        let mut track = probe_file(path, path_str, media_type, mtime, size, &opts)?;
+        if let Some(p) = &profile {
+            p.record(start.elapsed(), &track.path);
+        }
+        track.first_indexed = first_indexed;
+        Some((track, true))
+    };
+    let mut tracks_per_dir: HashMap<String, usize> = HashMap::new();
+    loop {
+        let chunk: Vec<PathBuf> = paths.by_ref().take(SCAN_CHUNK).collect();
+        if chunk.is_empty() {
+            break;
+        }
+        let mut found: Vec<(Track, bool)> = chunk
+            .par_iter()
+            .map_init(|| old_index.as_ref().and_then(OldIndex::handle), probe)
+            .flatten()
+            .collect();
+
+        // only what was just probed goes through the hook, cache hits were enriched before
+        if let Some(command) = enrich_command {
+            let probed: Vec<usize> = (0..found.len()).filter(|&i| found[i].1).collect();
+            for batch in probed.chunks(crate::enrich::BATCH) {
+                let tracks = batch
+                    .iter()
+                    .map(|&i| std::mem::take(&mut found[i].0))
+                    .collect();
+                for (&i, track) in batch.iter().zip(crate::enrich::enrich(command, tracks)) {
+                    found[i].0 = track;
+                }
+            }
+        }
+
+        for (track, _) in found {
+            count += 1;
+            if let Some(parent) = Path::new(&track.path).parent() {
+                *tracks_per_dir
+                    .entry(parent.to_string_lossy().to_string())
+                    .or_default() += 1;
             }
+            sink(track);
+        }
+    }
 
-            log::debug!("Cache miss: Probing {}", path_str);
+    pb.finish_and_clear();
+    log::info!(
+        "Indexing session finished. Total valid tracks found: {}",
+        count
+    );
+    if let Some(p) = profile {
+        p.report(started.elapsed());
+    }
 
-            let (mut title, mut artist, mut album, mut genre);
+    // background scans stay silent, skim owns the terminal
+    if !config.music_dirs.is_empty() && !config.quiet_scan {
+        println!("Indexed {} tracks\n", count);
+    }
 
-            if media_type == "playlist" {
-                title = path
-                    .file_stem()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string();
-                artist = "Playlist".to_string();
-                album = "Playlists".to_string();
-                genre = "Playlist".to_string();
-            } else {
-                title = String::new();
-                artist = String::new();
-                album = String::new();
-                genre = String::new();
-
-                match Probe::open(path).and_then(|p| p.read()) {
-                    Ok(tagged_file) => {
-                        if let Some(tag) = tagged_file
-                            .primary_tag()
-                            .or_else(|| tagged_file.first_tag())
-                        {
-                            title = tag.title().map(|s| s.to_string()).unwrap_or_default();
-                            artist = tag.artist().map(|s| s.to_string()).unwrap_or_default();
-                            album = tag.album().map(|s| s.to_string()).unwrap_or_default();
-                            genre = tag.genre().map(|s| s.to_string()).unwrap_or_default();
-                        }
+    let mut dirs = fresh_stamps.into_inner().unwrap_or_default();
+    for (dir, stamp) in dirs.iter_mut() {
+        stamp.tracks = tracks_per_dir.get(dir).copied().unwrap_or(0);
+    }
+    log::info!(
+        "{} of {} directories unchanged since the last scan, reused without listing",
+        reused_dirs.into_inner(),
+        dirs.len()
+    );
+
+    Ok((count, DirStamps { fingerprint, dirs }))
+}
+
+const SLOWEST_SHOWN: usize = 20;
+
+/// Timings for `--profile-scan`. Probes run on every rayon thread, so the
+/// slowest ones sit in a size-capped min-heap behind a mutex.
+#[derive(Default)]
+struct ScanProfile {
+    slowest: Mutex<BinaryHeap<Reverse<(Duration, String)>>>,
+    probe_nanos: AtomicU64,
+    probed: AtomicUsize,
+    walk_nanos: AtomicU64,
+}
+
+impl ScanProfile {
+    fn record(&self, took: Duration, path: &str) {
+        self.probe_nanos
+            .fetch_add(took.as_nanos() as u64, Ordering::Relaxed);
+        self.probed.fetch_add(1, Ordering::Relaxed);
+
+        let mut heap = self.slowest.lock().unwrap_or_else(|e| e.into_inner());
+        if heap.len() < SLOWEST_SHOWN {
+            heap.push(Reverse((took, path.to_string())));
+        } else if heap
+            .peek()
+            .is_some_and(|Reverse((fastest, _))| took > *fastest)
+        {
+            heap.pop();
+            heap.push(Reverse((took, path.to_string())));
+        }
+    }
+
+    // slowest first
+    fn slowest(self) -> Vec<(Duration, String)> {
+        let heap = self.slowest.into_inner().unwrap_or_else(|e| e.into_inner());
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(t)| t)
+            .collect()
+    }
+
+    fn report(self, wall: Duration) {
+        let probed = self.probed.load(Ordering::Relaxed);
+        let probe = Duration::from_nanos(self.probe_nanos.load(Ordering::Relaxed));
+        let walk = Duration::from_nanos(self.walk_nanos.load(Ordering::Relaxed));
+
+        println!("\n⏱️  Scan profile");
+        println!("   Wall time:        {:.2?}", wall);
+        println!("   Walking dirs:     {:.2?}", walk);
+        println!(
+            "   Probing tags:     {:.2?} over {} files (summed across threads)",
+            probe, probed
+        );
+        if probed == 0 {
+            println!(
+                "   Nothing was probed, every file was a cache hit. Try --reindex --profile-scan."
+            );
+            return;
+        }
+
+        println!("\n   Slowest files to probe:");
+        for (took, path) in self.slowest() {
+            println!("   {:>10.2?}  {}", took, path);
+            log::info!("Slow probe: {:?} {}", took, path);
+        }
+    }
+}
+
+/// Config bits that shape a probe, resolved once per scan.
+struct ProbeOpts {
+    classical: bool,
+    split_artists: bool,
+    preferred_tag: Option<TagType>,
+}
+
+impl ProbeOpts {
+    fn from_config(config: &Config) -> Self {
+        Self {
+            classical: config.read_classical_tags,
+            split_artists: config.split_artists,
+            // config::load already warned about and dropped bad names
+            preferred_tag: config
+                .preferred_tag_type
+                .as_deref()
+                .and_then(parse_tag_type),
+        }
+    }
+}
+
+/// Maps a `preferred_tag_type` name ("ID3v2", "VorbisComments", "MP4ilst"...) to lofty's TagType.
+pub fn parse_tag_type(name: &str) -> Option<TagType> {
+    let normalized: String = name
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    match normalized.as_str() {
+        "ape" | "apev2" => Some(TagType::Ape),
+        "id3v1" => Some(TagType::Id3v1),
+        "id3v2" | "id3" => Some(TagType::Id3v2),
+        "mp4ilst" | "mp4" | "ilst" => Some(TagType::Mp4Ilst),
+        "vorbiscomments" | "vorbis" => Some(TagType::VorbisComments),
+        "riffinfo" | "riff" => Some(TagType::RiffInfo),
+        "aifftext" | "aiff" => Some(TagType::AiffText),
+        _ => None,
+    }
+}
+
+/// Reads tags for one file, falling back to "Artist - Title" filenames and UNKNOWN.
+fn probe_file(
+    path: &Path,
+    path_str: String,
+    media_type: &str,
+    mtime: u64,
+    size: u64,
+    opts: &ProbeOpts,
+) -> Option<Track> {
+    let (mut title, mut artist, mut album, mut genre);
+    let (mut album_artist, mut track_no, mut disc_no) = (String::new(), None, None);
+    let (mut track_total, mut disc_total) = (None, None);
+    let mut comment = String::new();
+    let (mut year, mut original_year) = (None, None);
+    let (mut bitrate, mut duration, mut bpm) = (None, None, None);
+    let mut extra = Classical::default();
+    let mut artists: Vec<String> = Vec::new();
+
+    if media_type == "playlist" {
+        title = path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        artist = "Playlist".to_string();
+        album = "Playlists".to_string();
+        genre = "Playlist".to_string();
+    } else {
+        title = String::new();
+        artist = String::new();
+        album = String::new();
+        genre = String::new();
+
+        match Probe::open(path).and_then(|p| p.read()) {
+            Ok(tagged_file) => {
+                let properties = tagged_file.properties();
+                bitrate = properties
+                    .audio_bitrate()
+                    .or_else(|| properties.overall_bitrate())
+                    .filter(|&kbps| kbps > 0);
+                duration = u32::try_from(properties.duration().as_secs())
+                    .ok()
+                    .filter(|&secs| secs > 0);
+                if let Some(tag) = opts
+                    .preferred_tag
+                    .and_then(|kind| tagged_file.tag(kind))
+                    .or_else(|| tagged_file.primary_tag())
+                    .or_else(|| tagged_file.first_tag())
+                {
+                    title = tag.title().map(|s| s.to_string()).unwrap_or_default();
+                    artist = tag.artist().map(|s| s.to_string()).unwrap_or_default();
+                    // repeated artist frames/fields, plus the ARTISTS list some taggers write
+                    let credited: Vec<&str> = tag.get_strings(ItemKey::TrackArtist).collect();
+                    if credited.len() > 1 {
+                        artist = credited.join("; ");
                     }
-                    Err(e) => {
-                        log::warn!("Metadata probe failed for '{}': {}", path_str, e);
+                    artists = credited
+                        .into_iter()
+                        .chain(tag.get_strings(ItemKey::TrackArtists))
+                        .map(|s| s.trim().to_string())
+                        .collect();
+                    album = tag.album().map(|s| s.to_string()).unwrap_or_default();
+                    genre = tag.genre().map(|s| s.to_string()).unwrap_or_default();
+                    comment = tag
+                        .comment()
+                        .map(|s| s.trim().to_string())
+                        .unwrap_or_default();
+                    album_artist = tag
+                        .get_string(ItemKey::AlbumArtist)
+                        .map(|s| s.trim().to_string())
+                        .unwrap_or_default();
+                    track_no = tag.track();
+                    disc_no = tag.disk();
+                    track_total = tag.track_total().filter(|&n| n > 0);
+                    disc_total = tag.disk_total().filter(|&n| n > 0);
+                    year = [ItemKey::Year, ItemKey::RecordingDate, ItemKey::ReleaseDate]
+                        .into_iter()
+                        .find_map(|key| tag.get_string(key).and_then(parse_year));
+                    original_year = tag
+                        .get_string(ItemKey::OriginalReleaseDate)
+                        .and_then(parse_year);
+                    bpm = [ItemKey::IntegerBpm, ItemKey::Bpm]
+                        .into_iter()
+                        .find_map(|key| tag.get_string(key).and_then(parse_bpm));
+                    if opts.classical {
+                        extra = Classical::read(tag);
                     }
                 }
             }
+            Err(e) => {
+                log::warn!("Metadata probe failed for '{}': {}", path_str, e);
+            }
+        }
+    }
 
-            if title.is_empty() {
-                let filename = path.file_stem()?.to_string_lossy().to_string();
+    if title.is_empty() {
+        let filename = path.file_stem()?.to_string_lossy().to_string();
 
-                let (parsed_artist, parsed_title) = parse_filename_metadata(&filename);
+        let (parsed_artist, parsed_title) = parse_filename_metadata(&filename);
 
-                title = parsed_title;
-                if artist.is_empty() && !parsed_artist.is_empty() {
-                    artist = parsed_artist;
-                }
-            }
-            if artist.is_empty() {
-                artist = "UNKNOWN".to_string();
-            }
-            if album.is_empty() {
-                album = "UNKNOWN".to_string();
-            }
-            if genre.is_empty() {
-                genre = "UNKNOWN".to_string();
+        title = parsed_title;
+        if artist.is_empty() && !parsed_artist.is_empty() {
+            artist = parsed_artist;
+        }
+    }
+    if artist.is_empty() {
+        artist = "UNKNOWN".to_string();
+    }
+    if artists.is_empty() && media_type != "playlist" {
+        artists.push(artist.clone());
+    }
+    if opts.split_artists {
+        artists = artists
+            .iter()
+            .flat_map(|a| split_artist_credit(a))
+            .collect();
+    }
+    let artists = distinct_artists(artists);
+    if album.is_empty() {
+        album = "UNKNOWN".to_string();
+    }
+    if genre.is_empty() {
+        genre = "UNKNOWN".to_string();
+    }
+
+    Some(Track {
+        path: path_str,
+        title,
+        artist,
+        album,
+        album_artist,
+        artists,
+        genre,
+        comment,
+        track_no,
+        disc_no,
+        track_total,
+        disc_total,
+        bitrate,
+        duration,
+        bpm,
+        year,
+        original_year,
+        composer: extra.composer,
+        grouping: extra.grouping,
+        work: extra.work,
+        movement: extra.movement,
+        movement_no: extra.movement_no,
+        mtime,
+        size,
+        first_indexed: 0, // the caller knows whether this path is new
+        media_type: media_type.to_string(),
+    })
+}
+
+/// The year out of a date tag: "1969", "1969-09-26", "1969/09", "19690926T..." all give 1969.
+pub(crate) fn parse_year(date: &str) -> Option<u16> {
+    let date = date.trim();
+    let digits = date.get(..4)?;
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    digits.parse().ok().filter(|&y| y > 0)
+}
+
+// "128", "127.9" and "128 BPM" are all 128, zero means untagged
+fn parse_bpm(value: &str) -> Option<u32> {
+    let number = value.split_whitespace().next()?;
+    let bpm: f64 = number.parse().ok()?;
+    (0.5..1000.0).contains(&bpm).then(|| bpm.round() as u32)
+}
+
+// separators of "A feat. B", "A & B", "A, B"... checked case-insensitively
+const ARTIST_SEPARATORS: &[&str] = &[
+    " (feat. ",
+    " [feat. ",
+    " (ft. ",
+    " [ft. ",
+    " feat. ",
+    " ft. ",
+    " featuring ",
+    " feat ",
+    " & ",
+    ", ",
+    ";",
+    " / ",
+];
+
+/// Splits an artist credit on the common featuring/collaboration conventions.
+fn split_artist_credit(credit: &str) -> Vec<String> {
+    // ascii lowercasing keeps byte offsets valid for `credit`
+    let lower = credit.to_ascii_lowercase();
+    let mut cuts: Vec<(usize, usize)> = Vec::new();
+    for sep in ARTIST_SEPARATORS {
+        for (i, _) in lower.match_indices(sep) {
+            let end = i + sep.len();
+            if !cuts.iter().any(|&(s, e)| i < e && end > s) {
+                cuts.push((i, end));
             }
+        }
+    }
+    cuts.sort_unstable();
 
-            Some(Track {
-                path: path_str,
-                title,
-                artist,
-                album,
-                genre,
-                mtime,
-                size,
-                media_type: media_type.to_string(),
-            })
+    let mut parts = Vec::new();
+    let mut start = 0;
+    for (s, e) in cuts {
+        parts.push(&credit[start..s]);
+        start = e;
+    }
+    parts.push(&credit[start..]);
+
+    parts
+        .into_iter()
+        .map(|p| {
+            let p = p.trim();
+            // the closing half of "(feat. B)", but not of "Artist (Live)"
+            match p.strip_suffix([')', ']']) {
+                Some(inner) if !p.contains(['(', '[']) => inner.trim(),
+                _ => p,
+            }
         })
+        .filter(|p| !p.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+// case-insensitive de-dup keeping first spellings, a lone artist needs no list
+fn distinct_artists(names: Vec<String>) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let names: Vec<String> = names
+        .into_iter()
+        .filter(|n| !n.is_empty() && seen.insert(n.to_lowercase()))
         .collect();
+    if names.len() > 1 { names } else { Vec::new() }
+}
 
-    pb.finish_with_message(format!("Indexed {} tracks", tracks.len()));
-    log::info!(
-        "Indexing session finished. Total valid tracks found: {}",
-        tracks.len()
-    );
+#[derive(Default)]
+struct Classical {
+    composer: Option<String>,
+    grouping: Option<String>,
+    work: Option<String>,
+    movement: Option<String>,
+    movement_no: Option<u32>,
+}
 
-    if !config.music_dirs.is_empty() {
-        println!();
+impl Classical {
+    fn read(tag: &lofty::tag::Tag) -> Self {
+        let text = |key| {
+            tag.get_string(key)
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        };
+        Self {
+            composer: text(ItemKey::Composer),
+            grouping: text(ItemKey::ContentGroup),
+            work: text(ItemKey::Work),
+            movement: text(ItemKey::Movement),
+            // "2" or "2/4" depending on the tagger
+            movement_no: text(ItemKey::MovementNumber)
+                .and_then(|n| n.split('/').next()?.trim().parse().ok()),
+        }
     }
+}
 
-    Ok(tracks)
+// every field read from the tags, what a reprobe reports as changed
+fn tags_differ(a: &Track, b: &Track) -> bool {
+    (
+        &a.title,
+        &a.artist,
+        &a.album,
+        &a.album_artist,
+        &a.genre,
+        &a.comment,
+    ) != (
+        &b.title,
+        &b.artist,
+        &b.album,
+        &b.album_artist,
+        &b.genre,
+        &b.comment,
+    ) || a.artists != b.artists
+        || (a.track_no, a.disc_no, a.track_total, a.disc_total)
+            != (b.track_no, b.disc_no, b.track_total, b.disc_total)
+        || (a.bpm, a.year, a.original_year) != (b.bpm, b.year, b.original_year)
+        || (&a.composer, &a.grouping, &a.work) != (&b.composer, &b.grouping, &b.work)
+        || (&a.movement, a.movement_no) != (&b.movement, b.movement_no)
+}
+
+/// Re-reads tags for the tracks matching `pred`, ignoring the mtime/size cache.
+/// For taggers that keep mtime, where an incremental scan never sees the change.
+/// Returns how many tracks came back with different metadata.
+pub fn reprobe_where<P>(tracks: &mut [Track], config: &Config, pred: P) -> usize
+where
+    P: Fn(&Track) -> bool + Sync,
+{
+    let opts = ProbeOpts::from_config(config);
+    let changed = AtomicUsize::new(0);
+    tracks.par_iter_mut().filter(|t| pred(t)).for_each(|track| {
+        let path = Path::new(&track.path);
+        let Ok(metadata) = std::fs::metadata(path) else {
+            log::debug!("Reprobe skipped (missing): {}", track.path);
+            return;
+        };
+        let mtime = metadata
+            .modified()
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let Some(fresh) = probe_file(
+            path,
+            track.path.clone(),
+            &track.media_type,
+            mtime,
+            metadata.len(),
+            &opts,
+        ) else {
+            return;
+        };
+        let fresh = Track {
+            first_indexed: track.first_indexed,
+            ..fresh
+        };
+        let differs = tags_differ(&fresh, track);
+        if differs {
+            log::debug!("Reprobe picked up new tags: {}", track.path);
+            changed.fetch_add(1, Ordering::Relaxed);
+        }
+        *track = fresh;
+    });
+    changed.into_inner()
 }
 
 /// Serializes a slice of `Track` items into a JSON Lines format file on disk.
 pub fn save(tracks: &[Track]) -> Result<()> {
-    let dirs = ProjectDirs::from("com", "furqanhun", "mpv-music")
-        .context("Could not determine data directory")?;
-
-    let data_dir = dirs.data_dir();
-    std::fs::create_dir_all(data_dir)?;
-
-    let index_path = dirs.data_dir().join("music_index.jsonl");
+    let index_path = index_path()?;
     log::info!(
         "Saving index ({} entries) to: {:?}",
         tracks.len(),
         index_path
     );
 
-    let file = File::create(&index_path)?;
-    let mut writer = BufWriter::new(file);
-
+    let mut writer = IndexWriter::create(&index_path)?;
     for track in tracks {
-        serde_json::to_writer(&mut writer, track)?;
-        writeln!(writer)?;
+        writer.push(track);
     }
-
-    writer.flush()?;
+    writer.finish()?;
     log::debug!("Index flush to disk complete.");
     Ok(())
 }
 
-pub fn load_index() -> Result<(Vec<Track>, bool)> {
-    let dirs = ProjectDirs::from("com", "furqanhun", "mpv-music")
-        .context("Could not determine data directory")?;
-    let index_path = dirs.data_dir().join("music_index.jsonl");
+/// Reads the index, repairing it when lines are corrupt. With `format` "bin"
+/// (`index_format`) it goes through a binary copy of `music_index.jsonl`, made
+/// again from the JSONL whenever that changes.
+pub fn load_index(format: &str) -> Result<(Vec<Track>, bool)> {
+    let index_path = index_path()?;
 
     if !index_path.exists() {
         log::debug!("No existing index file found at {:?}", index_path);
         return Ok((Vec::new(), false));
     }
 
+    let started = Instant::now();
+    let binary = format == "bin";
+    if binary && let Some(tracks) = load_binary(&index_path) {
+        log::debug!(
+            "Index loaded from its binary copy in {:?}. Loaded {} tracks.",
+            started.elapsed(),
+            tracks.len()
+        );
+        return Ok((tracks, false));
+    }
+
     log::info!("Loading index file from: {:?}", index_path);
-    let file = File::open(&index_path)?;
-    let reader = BufReader::new(file);
+    let (tracks, corrupt) = read_index(&index_path)?;
+    let needs_repair = corrupt > 0;
+
+    if needs_repair {
+        log::info!("Performing surgical repair on index...");
+        save(&tracks)?;
+    }
+
+    log::debug!(
+        "Index loaded successfully in {:?}. Loaded {} tracks.",
+        started.elapsed(),
+        tracks.len()
+    );
+    if binary {
+        save_binary(&index_path, &tracks);
+    }
+    Ok((tracks, needs_repair))
+}
+
+// the binary copy, None when there's none yet, it's stale or it's damaged
+fn load_binary(index_path: &Path) -> Option<Vec<Track>> {
+    let path = binary_path().ok()?;
+    if !path.exists() {
+        return None;
+    }
+    match crate::index_bin::read(&path, crate::index_bin::Stamp::of(index_path)?) {
+        Ok(Some(tracks)) => Some(tracks),
+        Ok(None) => {
+            log::info!("Binary index is out of date, reading the JSONL");
+            None
+        }
+        Err(e) => {
+            log::warn!("{:#}, reading the JSONL instead", e);
+            None
+        }
+    }
+}
+
+// best effort, the JSONL stays the index either way
+fn save_binary(index_path: &Path, tracks: &[Track]) {
+    let (Ok(path), Some(stamp)) = (binary_path(), crate::index_bin::Stamp::of(index_path)) else {
+        return;
+    };
+    match crate::index_bin::write(&path, tracks, stamp) {
+        Ok(()) => log::info!("Binary index written: {:?}", path),
+        Err(e) => log::warn!("Could not write the binary index: {:#}", e),
+    }
+}
+
+// every track in a JSONL index file, and how many lines weren't one
+fn read_index(path: &Path) -> Result<(Vec<Track>, usize)> {
+    let reader = BufReader::new(File::open(path)?);
     let mut tracks = Vec::new();
-    let mut needs_repair = false;
-    let mut line_count = 0;
+    let mut corrupt = 0;
 
-    for line in std::io::BufRead::lines(reader) {
-        line_count += 1;
+    for (n, line) in reader.lines().enumerate() {
         let l = line?;
         if l.trim().is_empty() {
             continue;
@@ -302,23 +1382,158 @@ pub fn load_index() -> Result<(Vec<Track>, bool)> {
         match serde_json::from_str::<Track>(&l) {
             Ok(t) => tracks.push(t),
             Err(e) => {
-                log::warn!(
-                    "Corruption detected on line {}: {}. Marking for repair...",
-                    line_count,
-                    e
-                );
-                needs_repair = true;
+                log::warn!("Corruption detected on line {} of {:?}: {}", n + 1, path, e);
+                corrupt += 1;
             }
         }
     }
+    Ok((tracks, corrupt))
+}
 
-    if needs_repair {
-        log::info!("Performing surgical repair on index...");
-        save(&tracks)?;
+/// What `--merge-index` did with the other file's entries.
+#[derive(Debug, Default, PartialEq)]
+pub struct MergeReport {
+    pub added: usize,
+    pub updated: usize,
+    pub skipped: usize,
+}
+
+// the entry for `path`, also under another spelling of it (a symlink, a relative
+// path). Only canonicalized when the path as written isn't known
+fn find_known(known: &HashMap<String, usize>, path: &str) -> Option<usize> {
+    known.get(path).copied().or_else(|| {
+        let canonical = dunce::canonicalize(path).ok()?;
+        known.get(canonical.to_string_lossy().as_ref()).copied()
+    })
+}
+
+// a file both sides know keeps the entry with the newer mtime (and the earlier first_indexed)
+fn merge_tracks(ours: &mut Vec<Track>, theirs: Vec<Track>) -> MergeReport {
+    let mut known: HashMap<String, usize> = ours
+        .iter()
+        .enumerate()
+        .map(|(i, t)| (t.path.clone(), i))
+        .collect();
+    let mut report = MergeReport::default();
+
+    for track in theirs {
+        match find_known(&known, &track.path) {
+            Some(i) if track.mtime > ours[i].mtime => {
+                let first_indexed = first_seen(ours[i].first_indexed, ours[i].mtime)
+                    .min(first_seen(track.first_indexed, track.mtime));
+                ours[i] = Track {
+                    first_indexed,
+                    ..track
+                };
+                report.updated += 1;
+            }
+            Some(_) => report.skipped += 1,
+            None => {
+                known.insert(track.path.clone(), ours.len());
+                ours.push(track);
+                report.added += 1;
+            }
+        }
     }
+    report
+}
 
-    log::debug!("Index loaded successfully. Loaded {} tracks.", tracks.len());
-    Ok((tracks, needs_repair))
+/// `--merge-index`: folds another index file (another machine's, say) into this
+/// one and saves it. Lines of it that aren't tracks count as skipped.
+pub fn merge_index(other: &Path) -> Result<MergeReport> {
+    let (theirs, corrupt) =
+        read_index(other).with_context(|| format!("Could not read {:?}", other))?;
+    if theirs.is_empty() && corrupt > 0 {
+        bail!(
+            "{:?} isn't an mpv-music index, none of its lines is a track",
+            other
+        );
+    }
+
+    // runs before the config is read
+    let (mut ours, _) = load_index("json")?;
+    let mut report = merge_tracks(&mut ours, theirs);
+    report.skipped += corrupt;
+    save(&ours)?;
+    log::info!("Merged {:?} into the index: {:?}", other, report);
+    Ok(report)
+}
+
+/// Machine readable summary of the index for `--dump-index-stats`.
+#[derive(Serialize, Debug, Default)]
+pub struct IndexStats {
+    pub tracks: usize,
+    pub media_types: BTreeMap<String, usize>,
+    pub artists: usize,
+    pub albums: usize,
+    pub genres: usize,
+    pub total_size_bytes: u64,
+    pub total_duration_secs: Option<u64>, // null until a scan has read durations
+    pub oldest_mtime: Option<u64>,
+    pub newest_mtime: Option<u64>,
+    pub index_path: String,
+    pub index_modified: Option<u64>,
+}
+
+impl IndexStats {
+    /// One pass over the loaded index, nothing gets probed or rescanned.
+    pub fn from_tracks(tracks: &[Track]) -> Self {
+        let mut stats = IndexStats {
+            tracks: tracks.len(),
+            ..Default::default()
+        };
+        let (mut artists, mut albums, mut genres) =
+            (HashSet::new(), HashSet::new(), HashSet::new());
+        // untagged fields read back as UNKNOWN, they're not an artist/album/genre
+        let tagged = |value: &str| !value.is_empty() && value != "UNKNOWN";
+
+        for t in tracks {
+            *stats.media_types.entry(t.media_type.clone()).or_default() += 1;
+            if t.media_type == "playlist" {
+                continue;
+            }
+            stats.total_size_bytes += t.size;
+            if let Some(secs) = t.duration {
+                *stats.total_duration_secs.get_or_insert(0) += u64::from(secs);
+            }
+            stats.oldest_mtime = Some(stats.oldest_mtime.map_or(t.mtime, |m| m.min(t.mtime)));
+            stats.newest_mtime = Some(stats.newest_mtime.map_or(t.mtime, |m| m.max(t.mtime)));
+
+            if tagged(&t.artist) {
+                artists.insert(t.artist.to_lowercase());
+            }
+            if tagged(&t.album) {
+                // same-named albums by different artists ("Greatest Hits") are different albums
+                albums.insert((
+                    crate::queue::album_artist_of(t).to_lowercase(),
+                    t.album.to_lowercase(),
+                ));
+            }
+            if tagged(&t.genre) {
+                genres.insert(t.genre.to_lowercase());
+            }
+        }
+
+        stats.artists = artists.len();
+        stats.albums = albums.len();
+        stats.genres = genres.len();
+        stats
+    }
+}
+
+/// Stats for the index on disk, including where it lives and when it was last written.
+pub fn index_stats() -> Result<IndexStats> {
+    let (tracks, _) = load_index("json")?;
+    let path = index_path()?;
+
+    let mut stats = IndexStats::from_tracks(&tracks);
+    stats.index_modified = std::fs::metadata(&path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(SystemTime::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs());
+    stats.index_path = path.to_string_lossy().to_string();
+    Ok(stats)
 }
 
 #[cfg(test)]
@@ -393,6 +1608,7 @@ mod tests {
             mtime: 1234567890,
             size: 1024,
             media_type: "audio".to_string(),
+            ..Default::default()
         };
 
         assert_eq!(track.artist, "Test Artist");
@@ -412,6 +1628,7 @@ mod tests {
             mtime: 12345,
             size: 1000,
             media_type: "audio".to_string(),
+            ..Default::default()
         };
 
         // Should be able to serialize to JSON
@@ -438,6 +1655,9 @@ mod tests {
         let track = track.unwrap();
         assert_eq!(track.artist, "Artist");
         assert_eq!(track.genre, "Rock");
+        // indexes written before album_artist/track_no/disc_no existed still load
+        assert!(track.album_artist.is_empty());
+        assert!(track.disc_no.is_none());
     }
 
     #[test]
@@ -468,4 +1688,416 @@ mod tests {
 
         assert!(set.is_empty());
     }
+
+    #[test]
+    fn test_reprobe_only_touches_matching_tracks() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        // untagged file, the reprobe falls back to "Artist - Title" from the name
+        let file = dir.join("Ado - Usseewa.mp3");
+        std::fs::write(&file, b"not really audio").unwrap();
+
+        let unknown = |path: String| Track {
+            path,
+            title: "old".to_string(),
+            artist: "UNKNOWN".to_string(),
+            album: "UNKNOWN".to_string(),
+            genre: "UNKNOWN".to_string(),
+            media_type: "audio".to_string(),
+            ..Default::default()
+        };
+        let mut tracks = vec![
+            unknown(file.to_string_lossy().to_string()),
+            unknown(dir.join("gone.mp3").to_string_lossy().to_string()),
+            Track {
+                title: "kept".to_string(),
+                ..unknown(file.to_string_lossy().to_string())
+            },
+        ];
+
+        let changed = reprobe_where(&mut tracks, &Config::default(), |t| t.title == "old");
+
+        assert_eq!(changed, 1);
+        assert_eq!(tracks[0].artist, "Ado");
+        assert_eq!(tracks[0].title, "Usseewa");
+        assert_eq!(tracks[1].title, "old");
+        assert_eq!(tracks[2].title, "kept");
+    }
+
+    #[test]
+    fn test_old_index_rehydrates_by_offset() {
+        let tmp = tempfile::tempdir().unwrap();
+        let path = tmp.path().join("index.jsonl");
+
+        let tracks: Vec<Track> = ["/music/a.mp3", "/music/sub/b.flac"]
+            .iter()
+            .enumerate()
+            .map(|(i, p)| Track {
+                path: p.to_string(),
+                title: format!("Title {}", i),
+                artist: "Artist".to_string(),
+                album: "Album".to_string(),
+                genre: "Genre".to_string(),
+                mtime: 100 + i as u64,
+                size: 1000 + i as u64,
+                media_type: "audio".to_string(),
+                ..Default::default()
+            })
+            .collect();
+
+        {
+            let mut file = File::create(&path).unwrap();
+            for t in &tracks {
+                serde_json::to_writer(&mut file, t).unwrap();
+                writeln!(file).unwrap();
+            }
+            writeln!(file, "{{corrupt line").unwrap();
+        }
+
+        let old = OldIndex::open(&path).unwrap();
+        assert_eq!(old.len(), 2);
+
+        let slot = old.by_path.get("/music/sub/b.flac").unwrap();
+        assert_eq!(slot.mtime, 101);
+        assert_eq!(slot.size, 1001);
+        // written before first_indexed existed, so mtime stands in
+        assert_eq!(slot.first_indexed, 101);
+
+        let mut file = old.handle().unwrap();
+        let rehydrated = old.rehydrate(&mut file, slot.offset, |t| t.path == "/music/sub/b.flac");
+        assert_eq!(rehydrated.unwrap().title, "Title 1");
+        // an offset that no longer points at the entry it was taken for
+        let stale = old.rehydrate(&mut file, slot.offset, |t| t.path == "/music/a.mp3");
+        assert!(stale.is_none());
+
+        let moved = old.by_attr.get(&(1000, 100, "a.mp3".to_string())).unwrap();
+        let recovered = old.rehydrate(&mut file, *moved, |_| true).unwrap();
+        assert_eq!(recovered.path, "/music/a.mp3");
+    }
+
+    #[test]
+    fn test_scan_profile_keeps_slowest() {
+        let profile = ScanProfile::default();
+        for ms in 1..=SLOWEST_SHOWN as u64 + 5 {
+            profile.record(Duration::from_millis(ms), &format!("/m/{}.flac", ms));
+        }
+        assert_eq!(profile.probed.load(Ordering::Relaxed), SLOWEST_SHOWN + 5);
+
+        let slowest = profile.slowest();
+        assert_eq!(slowest.len(), SLOWEST_SHOWN);
+        assert_eq!(slowest[0].1, "/m/25.flac");
+        assert_eq!(slowest.last().unwrap().0, Duration::from_millis(6));
+    }
+
+    #[test]
+    fn test_parse_tag_type_names() {
+        assert_eq!(parse_tag_type("ID3v2"), Some(TagType::Id3v2));
+        assert_eq!(
+            parse_tag_type("VorbisComments"),
+            Some(TagType::VorbisComments)
+        );
+        assert_eq!(parse_tag_type("MP4ilst"), Some(TagType::Mp4Ilst));
+        assert_eq!(parse_tag_type("id3 v1"), Some(TagType::Id3v1));
+        assert_eq!(parse_tag_type("id4"), None);
+    }
+
+    #[test]
+    fn test_unchanged_dirs_are_not_listed() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        std::fs::create_dir_all(root.join("a")).unwrap();
+        std::fs::create_dir_all(root.join("b")).unwrap();
+        std::fs::write(root.join("a/x.mp3"), b"").unwrap();
+        std::fs::write(root.join("b/y.mp3"), b"").unwrap();
+
+        let walk = |old: Option<&DirStamps>, indexed: &HashMap<PathBuf, Vec<String>>| {
+            let (fresh, reused) = (Mutex::new(HashMap::new()), AtomicUsize::new(0));
+            let mut files: Vec<PathBuf> = DirWalker {
+                dirs: vec![root.to_path_buf()],
+                files: Vec::new(),
+                old,
+                indexed,
+                fresh: &fresh,
+                reused: &reused,
+                scan_hidden: false,
+            }
+            .collect();
+            files.sort();
+            let mut dirs = fresh.into_inner().unwrap();
+            for (dir, stamp) in dirs.iter_mut() {
+                stamp.tracks = files
+                    .iter()
+                    .filter(|f| f.parent() == Some(Path::new(dir)))
+                    .count();
+            }
+            (
+                files,
+                DirStamps {
+                    fingerprint: String::new(),
+                    dirs,
+                },
+                reused.into_inner(),
+            )
+        };
+
+        let (first, stamps, reused) = walk(None, &HashMap::new());
+        assert_eq!(first, [root.join("a/x.mp3"), root.join("b/y.mp3")]);
+        assert_eq!((stamps.dirs.len(), reused), (3, 0));
+
+        let mut indexed: HashMap<PathBuf, Vec<String>> = HashMap::new();
+        for file in &first {
+            indexed
+                .entry(file.parent().unwrap().to_path_buf())
+                .or_default()
+                .push(file.to_string_lossy().to_string());
+        }
+        let (second, _, reused) = walk(Some(&stamps), &indexed);
+        assert_eq!((second, reused), (first.clone(), 3));
+
+        // a new file changes b's mtime, so b is listed again
+        std::fs::write(root.join("b/z.mp3"), b"").unwrap();
+        let (third, _, reused) = walk(Some(&stamps), &indexed);
+        assert_eq!(reused, 2);
+        assert_eq!(third.len(), 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_shares_identity() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::write(dir.join("song.mp3"), b"a").unwrap();
+        std::fs::write(dir.join("other.mp3"), b"b").unwrap();
+        std::os::unix::fs::symlink(dir.join("song.mp3"), dir.join("link.mp3")).unwrap();
+
+        let song = file_identity(&dir.join("song.mp3"));
+        let link = file_identity(&dir.join("link.mp3"));
+        let other = file_identity(&dir.join("other.mp3"));
+
+        assert!(song.is_some());
+        assert_eq!(song, link);
+        assert_ne!(song, other);
+    }
+
+    #[test]
+    fn test_index_stats() {
+        let track = |artist: &str, album: &str, media_type: &str, mtime: u64| Track {
+            artist: artist.to_string(),
+            album: album.to_string(),
+            genre: "Rock".to_string(),
+            media_type: media_type.to_string(),
+            mtime,
+            size: 100,
+            ..Default::default()
+        };
+        let tracks = [
+            track("A", "Greatest Hits", "audio", 30),
+            track("a", "Greatest Hits", "audio", 10),
+            track("B", "Greatest Hits", "video", 20),
+            track("", "", "playlist", 1),
+            Track {
+                genre: "UNKNOWN".to_string(),
+                ..track("UNKNOWN", "UNKNOWN", "audio", 15)
+            },
+        ];
+
+        let stats = IndexStats::from_tracks(&tracks);
+        assert_eq!(stats.tracks, 5);
+        assert_eq!(stats.media_types["audio"], 3);
+        assert_eq!(stats.media_types["playlist"], 1);
+        assert_eq!((stats.artists, stats.albums, stats.genres), (2, 2, 1));
+        assert_eq!(stats.total_size_bytes, 400);
+        assert_eq!(
+            (stats.oldest_mtime, stats.newest_mtime),
+            (Some(10), Some(30))
+        );
+        assert_eq!(stats.total_duration_secs, None);
+    }
+
+    #[test]
+    fn test_parse_year_partial_dates() {
+        assert_eq!(parse_year("1969"), Some(1969));
+        assert_eq!(parse_year(" 1969-09-26 "), Some(1969));
+        assert_eq!(parse_year("2011/03"), Some(2011));
+        assert_eq!(parse_year("20110304T120000"), Some(2011));
+        assert_eq!(parse_year("69"), None);
+        assert_eq!(parse_year("0000"), None);
+        assert_eq!(parse_year("Sept 1969"), None);
+    }
+
+    #[test]
+    fn test_parse_bpm_values() {
+        assert_eq!(parse_bpm("128"), Some(128));
+        assert_eq!(parse_bpm(" 127.6 "), Some(128));
+        assert_eq!(parse_bpm("90 BPM"), Some(90));
+        assert_eq!(parse_bpm("0"), None);
+        assert_eq!(parse_bpm("fast"), None);
+    }
+
+    #[test]
+    fn test_split_artist_credit() {
+        assert_eq!(
+            split_artist_credit("Daft Punk feat. Pharrell Williams & Nile Rodgers"),
+            ["Daft Punk", "Pharrell Williams", "Nile Rodgers"]
+        );
+        assert_eq!(split_artist_credit("Ado (FEAT. Vaundy)"), ["Ado", "Vaundy"]);
+        assert_eq!(split_artist_credit("Nirvana (Live)"), ["Nirvana (Live)"]);
+
+        let names = vec!["A".to_string(), "B".to_string(), "a".to_string()];
+        assert_eq!(distinct_artists(names), ["A", "B"]);
+        assert!(distinct_artists(vec!["Solo".to_string()]).is_empty());
+    }
+
+    #[test]
+    fn test_merge_prefers_newer_entries() {
+        let track = |path: &str, mtime: u64, genre: &str| Track {
+            path: path.to_string(),
+            mtime,
+            first_indexed: mtime,
+            genre: genre.to_string(),
+            ..Default::default()
+        };
+        let mut ours = vec![
+            track("/nonexistent/a.flac", 100, "Old"),
+            track("/nonexistent/b.flac", 500, "Kept"),
+        ];
+        let theirs = vec![
+            track("/nonexistent/a.flac", 200, "Retagged"),
+            track("/nonexistent/b.flac", 300, "Stale"),
+            track("/nas/c.flac", 50, "New"),
+            track("/nas/c.flac", 40, "Dup"),
+        ];
+
+        let report = merge_tracks(&mut ours, theirs);
+        assert_eq!(
+            report,
+            MergeReport {
+                added: 1,
+                updated: 1,
+                skipped: 2
+            }
+        );
+        let genres: Vec<&str> = ours.iter().map(|t| t.genre.as_str()).collect();
+        assert_eq!(genres, ["Retagged", "Kept", "New"]);
+        // the first sighting survives the update
+        assert_eq!(ours[0].first_indexed, 100);
+    }
+
+    #[test]
+    fn test_under_any_music_dir() {
+        let dirs = [PathBuf::from("/m"), PathBuf::from("/other/music")];
+        assert!(under_any("/m/a/b.flac", &dirs));
+        assert!(under_any("/other/music/x.mp3", &dirs));
+        assert!(!under_any("/nas/m/a.flac", &dirs));
+        assert!(!under_any("/mm/a.flac", &dirs));
+    }
+
+    #[test]
+    fn test_reprobe_sees_every_tag() {
+        let old = Track {
+            path: "/m/a.flac".to_string(),
+            title: "Adagio".to_string(),
+            ..Default::default()
+        };
+        let changes = [
+            Track {
+                movement: Some("II. Adagio".to_string()),
+                ..old.clone()
+            },
+            Track {
+                movement_no: Some(2),
+                ..old.clone()
+            },
+            Track {
+                grouping: Some("Op. 27".to_string()),
+                ..old.clone()
+            },
+            Track {
+                artists: vec!["A".to_string(), "B".to_string()],
+                ..old.clone()
+            },
+            Track {
+                original_year: Some(1801),
+                ..old.clone()
+            },
+        ];
+        assert!(!tags_differ(&old, &old.clone()));
+        assert!(changes.iter().all(|fresh| tags_differ(fresh, &old)));
+    }
+
+    #[test]
+    fn test_index_is_streamed_and_keeps_outside_entries() {
+        let tmp = tempfile::tempdir().unwrap();
+        let target = tmp.path().join("music_index.jsonl");
+        let line = |path: &str| {
+            serde_json::to_string(&Track {
+                path: path.to_string(),
+                media_type: "audio".to_string(),
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        std::fs::write(
+            &target,
+            format!(
+                "{}\nnot json\n{}\n",
+                line("/m/old.flac"),
+                line("/nas/merged.flac")
+            ),
+        )
+        .unwrap();
+
+        // tracks go in one at a time, nothing collects them first
+        let mut writer = IndexWriter::create(&target).unwrap();
+        (0..3000)
+            .map(|n| Track {
+                path: format!("/m/{:04}.flac", n),
+                ..Default::default()
+            })
+            .for_each(|track| writer.push(&track));
+        let kept = copy_outside(&target, &[PathBuf::from("/m")], &mut writer);
+        assert_eq!(kept, 1);
+        assert_eq!(writer.finish().unwrap(), 3001);
+
+        assert!(!target.with_extension("jsonl.tmp").exists());
+        let (tracks, corrupt) = read_index(&target).unwrap();
+        assert_eq!(corrupt, 0);
+        assert_eq!(tracks[0].path, "/m/0000.flac");
+        assert_eq!(tracks[2999].path, "/m/2999.flac");
+        assert_eq!(tracks[3000].path, "/nas/merged.flac");
+    }
+
+    #[test]
+    fn test_walk_order_is_sorted() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        for name in ["b/2.mp3", "b/1.mp3", "a/x.mp3", "c.mp3", "B.mp3"] {
+            let path = root.join(name);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, b"").unwrap();
+        }
+        let (indexed, fresh, reused) = (
+            HashMap::new(),
+            Mutex::new(HashMap::new()),
+            AtomicUsize::new(0),
+        );
+        let walker = DirWalker {
+            dirs: vec![root.to_path_buf()],
+            files: Vec::new(),
+            old: None,
+            indexed: &indexed,
+            fresh: &fresh,
+            reused: &reused,
+            scan_hidden: false,
+        };
+        let walked: Vec<String> = walker
+            .map(|p| {
+                p.strip_prefix(root)
+                    .unwrap()
+                    .to_string_lossy()
+                    .replace('\\', "/")
+            })
+            .collect();
+        assert_eq!(walked, ["B.mp3", "c.mp3", "a/x.mp3", "b/1.mp3", "b/2.mp3"]);
+    }
 }
@@ -0,0 +1,208 @@
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// Per-process socket (or named pipe on Windows) handed to mpv via `--input-ipc-server`.
+pub fn socket_path() -> String {
+    let pid = std::process::id();
+    if cfg!(windows) {
+        format!(r"\\.\pipe\mpv-music-ipc-{}", pid)
+    } else {
+        format!("/tmp/mpv-music-ipc-{}.sock", pid)
+    }
+}
+
+/// A fresh "<pid>-d<n>" id per detached player, those outlive us and can't
+/// share a socket. It names the player's socket and its queue file.
+pub fn detached_id() -> String {
+    static LAUNCHES: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+    let n = LAUNCHES.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    format!("{}-d{}", std::process::id(), n)
+}
+
+/// The socket of the detached player `id`. Same prefix as `socket_path`, so
+/// `running_sockets` finds them too.
+pub fn detached_socket_path(id: &str) -> String {
+    if cfg!(windows) {
+        format!(r"\\.\pipe\mpv-music-ipc-{}", id)
+    } else {
+        format!("/tmp/mpv-music-ipc-{}.sock", id)
+    }
+}
+
+// mpv creates the socket a moment after launch, poll up to 5s (50 * 100ms)
+fn wait_for_socket(socket: &str) -> bool {
+    let p = std::path::Path::new(socket);
+    let mut attempts = 0;
+    while !p.exists() && attempts < 50 {
+        std::thread::sleep(Duration::from_millis(100));
+        attempts += 1;
+    }
+    p.exists()
+}
+
+type Connection = (Box<dyn Read + Send>, Box<dyn Write + Send>);
+
+fn connect(socket: &str) -> std::io::Result<Connection> {
+    #[cfg(unix)]
+    {
+        let stream = std::os::unix::net::UnixStream::connect(socket)?;
+        let writer = stream.try_clone()?;
+        Ok((Box::new(stream), Box::new(writer)))
+    }
+
+    #[cfg(windows)]
+    {
+        let pipe = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(socket)?;
+        let writer = pipe.try_clone()?;
+        Ok((Box::new(pipe), Box::new(writer)))
+    }
+}
+
+/// Fires a single command at mpv over a fresh connection, e.g. `&["quit"]`.
+pub fn send_command(socket: &str, command: &[&str]) -> std::io::Result<()> {
+    let (_, mut writer) = connect(socket)?;
+    let payload = serde_json::json!({ "command": command });
+    writeln!(writer, "{}", payload)
+}
+
+/// Sends a command and waits (up to 5s) for mpv's reply, handing back its `data`.
+/// An mpv side error (e.g. "property unavailable") comes back as an `Err`.
+pub fn request(socket: &str, command: &[&str]) -> std::io::Result<Value> {
+    let (reader, mut writer) = connect(socket)?;
+    let payload = serde_json::json!({ "command": command, "request_id": 1 });
+    writeln!(writer, "{}", payload)?;
+
+    // events can arrive before the reply, and a wedged mpv never answers
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        let reply = BufReader::new(reader)
+            .lines()
+            .map_while(Result::ok)
+            .filter_map(|line| serde_json::from_str::<Value>(&line).ok())
+            .find(|msg| msg["request_id"] == 1);
+        let _ = tx.send(reply);
+    });
+
+    let reply = rx
+        .recv_timeout(Duration::from_secs(5))
+        .ok()
+        .flatten()
+        .ok_or_else(|| std::io::Error::new(std::io::ErrorKind::TimedOut, "mpv did not reply"))?;
+    match reply["error"].as_str() {
+        Some("success") => Ok(reply["data"].clone()),
+        other => Err(std::io::Error::other(
+            other.unwrap_or("unknown error").to_string(),
+        )),
+    }
+}
+
+/// Sockets of mpv players started by mpv-music, most recently started first.
+/// Stale sockets (crashed players) are listed too, `request` weeds them out.
+pub fn running_sockets() -> Vec<String> {
+    let (dir, prefix) = if cfg!(windows) {
+        (r"\\.\pipe\", "mpv-music-ipc-")
+    } else {
+        ("/tmp", "mpv-music-ipc-")
+    };
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut sockets: Vec<(std::time::SystemTime, String)> = entries
+        .flatten()
+        .filter(|e| e.file_name().to_string_lossy().starts_with(prefix))
+        .map(|e| {
+            let started = e
+                .metadata()
+                .and_then(|m| m.modified())
+                .unwrap_or(std::time::UNIX_EPOCH);
+            (started, e.path().to_string_lossy().to_string())
+        })
+        .collect();
+    sockets.sort_by_key(|(started, _)| std::cmp::Reverse(*started));
+    sockets.into_iter().map(|(_, path)| path).collect()
+}
+
+// blocks until mpv closes the socket, handing every message to `on_message`
+fn listen(socket: &str, properties: &[String], on_message: &mut dyn FnMut(&Value)) {
+    if !wait_for_socket(socket) {
+        log::warn!("IPC socket never appeared at '{}'", socket);
+        return;
+    }
+
+    let (reader, mut writer) = match connect(socket) {
+        Ok(conn) => conn,
+        Err(e) => {
+            log::warn!("IPC observer could not connect to '{}': {}", socket, e);
+            return;
+        }
+    };
+
+    for (id, name) in properties.iter().enumerate() {
+        let payload = serde_json::json!({ "command": ["observe_property", id + 1, name] });
+        if writeln!(writer, "{}", payload).is_err() {
+            log::warn!("IPC observer failed to subscribe to '{}'", name);
+            return;
+        }
+    }
+    log::debug!("IPC observer attached: {:?}", properties);
+
+    for line in BufReader::new(reader).lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if let Ok(msg) = serde_json::from_str::<Value>(&line) {
+            on_message(&msg);
+        }
+    }
+
+    log::debug!("IPC observer detached (mpv closed the socket)");
+}
+
+/// Watches mpv properties on a background thread and calls `on_change` for every
+/// `property-change` event. The thread ends on its own once mpv closes the socket.
+pub fn observe<F>(socket: String, properties: &[&str], mut on_change: F) -> JoinHandle<()>
+where
+    F: FnMut(&str, &Value) + Send + 'static,
+{
+    let properties: Vec<String> = properties.iter().map(|p| p.to_string()).collect();
+
+    std::thread::spawn(move || {
+        listen(&socket, &properties, &mut |msg| {
+            if msg["event"].as_str() == Some("property-change")
+                && let Some(name) = msg["name"].as_str()
+            {
+                on_change(name, &msg["data"]);
+            }
+        });
+    })
+}
+
+/// Like `observe`, but hands over every message mpv sends (events such as
+/// `client-message` included), not just the property changes.
+pub fn watch<F>(socket: String, properties: &[&str], mut on_message: F) -> JoinHandle<()>
+where
+    F: FnMut(&Value) + Send + 'static,
+{
+    let properties: Vec<String> = properties.iter().map(|p| p.to_string()).collect();
+    std::thread::spawn(move || listen(&socket, &properties, &mut on_message))
+}
+
+/// Resolves to the reason of the last `end-file` event once mpv exits.
+/// `"eof"` means the queue ran out by itself, `"quit"` means the user quit.
+pub fn last_end_reason(socket: String) -> JoinHandle<Option<String>> {
+    std::thread::spawn(move || {
+        let mut last = None;
+        listen(&socket, &[], &mut |msg| {
+            if msg["event"].as_str() == Some("end-file") {
+                last = msg["reason"].as_str().map(String::from);
+            }
+        });
+        last
+    })
+}
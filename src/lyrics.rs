@@ -0,0 +1,206 @@
+use crate::config::Config;
+use crate::indexer::{self, Track};
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const API: &str = "https://lrclib.net/api";
+const USER_AGENT: &str = concat!(
+    "mpv-music/",
+    env!("CARGO_PKG_VERSION"),
+    " (https://github.com/FurqanHun/mpv-music)"
+);
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Record {
+    #[serde(default)]
+    instrumental: bool,
+    plain_lyrics: Option<String>,
+    synced_lyrics: Option<String>,
+}
+
+enum Lyrics {
+    Local(String),
+    Fetched(Record),
+    Instrumental,
+}
+
+/// "Song.flac" -> "Song.lrc", where mpv picks the lyrics up as subtitles.
+pub fn sidecar_path(track_path: &str) -> PathBuf {
+    Path::new(track_path).with_extension("lrc")
+}
+
+// "[01:02.34]Line" -> "Line", for printing synced lyrics as plain text
+fn strip_timestamps(lrc: &str) -> String {
+    lrc.lines()
+        .filter_map(|line| {
+            let mut rest = line.trim_start();
+            while rest.starts_with('[')
+                && let Some(end) = rest.find(']')
+            {
+                // [ar:...], [ti:...] and friends are header tags, not lyrics
+                if !rest[1..end].starts_with(|c: char| c.is_ascii_digit()) {
+                    return None;
+                }
+                rest = rest[end + 1..].trim_start();
+            }
+            Some(rest)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn get(agent: &ureq::Agent, endpoint: &str, query: &[(&str, String)]) -> Result<Option<String>> {
+    let mut request = agent
+        .get(format!("{}/{}", API, endpoint))
+        .header("User-Agent", USER_AGENT);
+    for (key, value) in query {
+        request = request.query(*key, value);
+    }
+    let mut response = request
+        .call()
+        .map_err(|e| anyhow::anyhow!("lrclib.net is unreachable: {}", e))?;
+
+    match response.status().as_u16() {
+        200 => Ok(Some(response.body_mut().read_to_string()?)),
+        404 => Ok(None),
+        code => bail!("lrclib.net answered HTTP {}", code),
+    }
+}
+
+// the exact lookup wants the duration within a couple of seconds, tracks
+// indexed before durations were read (or with odd tags) fall back to a search
+fn fetch(track: &Track) -> Result<Option<Record>> {
+    let agent = ureq::Agent::new_with_config(
+        ureq::Agent::config_builder()
+            .timeout_global(Some(Duration::from_secs(10)))
+            .http_status_as_error(false)
+            .build(),
+    );
+
+    let query = vec![
+        ("track_name", track.title.clone()),
+        ("artist_name", track.artist.clone()),
+    ];
+    if let Some(secs) = track.duration {
+        let mut exact = query.clone();
+        exact.push(("album_name", track.album.clone()));
+        exact.push(("duration", secs.to_string()));
+        if let Some(body) = get(&agent, "get", &exact)? {
+            return Ok(Some(serde_json::from_str(&body)?));
+        }
+        log::debug!("No exact lrclib match for {}, searching", track.path);
+    }
+
+    let Some(body) = get(&agent, "search", &query)? else {
+        return Ok(None);
+    };
+    let mut results: Vec<Record> = serde_json::from_str(&body)?;
+    // synced lyrics first, they double as the sidecar
+    results.sort_by_key(|r| r.synced_lyrics.is_none());
+    Ok(results.into_iter().next())
+}
+
+fn lookup(track: &Track, cfg: &Config) -> Result<Lyrics> {
+    let sidecar = sidecar_path(&track.path);
+    if let Ok(local) = std::fs::read_to_string(&sidecar) {
+        log::info!("Using local lyrics: {:?}", sidecar);
+        return Ok(Lyrics::Local(local));
+    }
+    if !cfg.fetch_lyrics {
+        bail!("No .lrc next to this track, and fetch_lyrics is off in the config");
+    }
+
+    log::info!(
+        "Fetching lyrics for {} - {} ({:?}s)",
+        track.artist,
+        track.title,
+        track.duration
+    );
+    match fetch(track)? {
+        Some(record) if record.instrumental => Ok(Lyrics::Instrumental),
+        Some(record) => Ok(Lyrics::Fetched(record)),
+        None => bail!("No lyrics found for {} - {}", track.artist, track.title),
+    }
+}
+
+/// Prints the track's lyrics: its `.lrc` sidecar when there is one, otherwise
+/// whatever lrclib.net has (saved as the sidecar with `save_lyrics`).
+pub fn show(track: &Track, cfg: &Config) -> Result<()> {
+    let text = match lookup(track, cfg)? {
+        Lyrics::Instrumental => {
+            println!("🎼 {} - {} is an instrumental.", track.artist, track.title);
+            return Ok(());
+        }
+        Lyrics::Local(lrc) => strip_timestamps(&lrc),
+        Lyrics::Fetched(record) => {
+            if cfg.save_lyrics
+                && let Some(lrc) = record
+                    .synced_lyrics
+                    .as_deref()
+                    .or(record.plain_lyrics.as_deref())
+            {
+                let sidecar = sidecar_path(&track.path);
+                match std::fs::write(&sidecar, lrc) {
+                    Ok(()) => log::info!("Saved lyrics to {:?}", sidecar),
+                    Err(e) => log::warn!("Couldn't save lyrics to {:?}: {}", sidecar, e),
+                }
+            }
+            record
+                .plain_lyrics
+                .or_else(|| record.synced_lyrics.as_deref().map(strip_timestamps))
+                .unwrap_or_default()
+        }
+    };
+
+    println!(
+        "\n  \x1b[1;36m{}\x1b[0m - \x1b[1;33m{}\x1b[0m\n",
+        track.title, track.artist
+    );
+    for line in text.lines() {
+        println!("  {}", line);
+    }
+    println!();
+    Ok(())
+}
+
+/// `--lyrics <PATH>`: the index entry for the file, or a fresh probe of it.
+pub fn run(path: &str, cfg: &Config) -> Result<()> {
+    let canonical = dunce::canonicalize(path)
+        .with_context(|| format!("Can't find '{}'", path))?
+        .to_string_lossy()
+        .to_string();
+
+    let (tracks, _) = indexer::load_index(&cfg.index_format)?;
+    let track = match tracks.into_iter().find(|t| t.path == canonical) {
+        Some(track) => track,
+        None => {
+            let mut probed = [Track {
+                path: canonical,
+                media_type: "audio".to_string(),
+                ..Default::default()
+            }];
+            indexer::reprobe_where(&mut probed, cfg, |_| true);
+            let [track] = probed;
+            track
+        }
+    };
+    show(&track, cfg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_lrc_timestamps() {
+        let lrc = "[ar:Artist]\n[00:12.34]First line\n[00:15.00][01:15.00] Chorus\n\nLast";
+        assert_eq!(strip_timestamps(lrc), "First line\nChorus\n\nLast");
+        assert_eq!(
+            sidecar_path("/m/01 - Song.flac"),
+            Path::new("/m/01 - Song.lrc")
+        );
+    }
+}
@@ -1,12 +1,35 @@
+mod audiobook;
 mod cli;
 mod config;
+mod cover;
 mod dep_check;
+mod enrich;
+mod glyphs;
+mod history;
+#[cfg(feature = "http-status")]
+mod http_status;
+mod index_bin;
 mod indexer;
+mod ipc;
+#[cfg(feature = "lyrics")]
+mod lyrics;
 mod player;
+mod playlist;
+mod queue;
 mod radio;
+mod ratings;
+mod remote;
+mod rename;
 mod search;
+mod stats;
+mod tagfix;
+mod term_title;
+mod tool_cache;
+mod transcode;
+mod trash;
 mod tui;
 mod update;
+mod verify;
 
 use anyhow::{Context, Result};
 use clap::Parser;
@@ -21,7 +44,15 @@ fn main() -> Result<()> {
     let _ = rustls::crypto::ring::default_provider().install_default();
     let args = Cli::parse();
 
-    let extra_mpv_args = args.mpv_args.as_deref().unwrap_or(&[]);
+    // `--` args come after --mpv-args, so they have the last word
+    let extra_mpv_args: Vec<String> = args
+        .mpv_args
+        .iter()
+        .flatten()
+        .chain(&args.passthrough)
+        .cloned()
+        .collect();
+    let extra_mpv_args = extra_mpv_args.as_slice();
 
     // deterministic paths
     let dirs = ProjectDirs::from("com", "furqanhun", "mpv-music")
@@ -36,10 +67,19 @@ fn main() -> Result<()> {
         None
     };
 
+    // first, so nothing below can touch the index
+    if args.no_index {
+        indexer::disable();
+    }
+
     // utility flags
     let log_file_path = log_dir.join("mpv-music.log");
     if args.remove_log {
         if log_file_path.exists() {
+            if !confirmed(&args, "Delete the log file?")? {
+                println!("Cancelled.");
+                return Ok(());
+            }
             std::fs::remove_file(&log_file_path)?;
             println!("Log file nuked.");
         } else {
@@ -48,15 +88,8 @@ fn main() -> Result<()> {
         return Ok(());
     }
     if let Some(viewer_opt) = args.log {
-        let viewer = viewer_opt.unwrap_or_else(|| {
-            std::env::var("PAGER").unwrap_or_else(|_| {
-                if cfg!(windows) {
-                    "notepad.exe".to_string()
-                } else {
-                    "less".to_string()
-                }
-            })
-        });
+        let peeked = peek_config(args.safe_mode, config_path_override.clone());
+        let viewer = config::resolve_pager(viewer_opt.as_deref(), peeked.as_ref());
         if log_file_path.exists() {
             std::process::Command::new(viewer)
                 .arg(&log_file_path)
@@ -68,6 +101,10 @@ fn main() -> Result<()> {
     }
     if args.remove_config {
         if config_file.exists() {
+            if !confirmed(&args, "Delete the config file (reset to defaults)?")? {
+                println!("Cancelled.");
+                return Ok(());
+            }
             std::fs::remove_file(&config_file)?;
             println!("Config removed.");
         } else {
@@ -76,6 +113,99 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
+    if args.clear_search_cache {
+        if search::clear_cache()? {
+            println!("Search cache cleared.");
+        } else {
+            println!("No search cache found.");
+        }
+        return Ok(());
+    }
+
+    if args.clear_index {
+        if !confirmed(&args, "Delete the music index?")? {
+            println!("Cancelled.");
+            return Ok(());
+        }
+        if indexer::clear_index()? {
+            println!("Index removed. The next run rebuilds it.");
+        } else {
+            println!("No index found.");
+        }
+        if args.all {
+            if search::clear_cache()? {
+                println!("Search cache cleared.");
+            }
+            if tool_cache::clear()? {
+                println!("Tool cache cleared.");
+            }
+        }
+        return Ok(());
+    }
+
+    // completion helpers stay silent and offline, the index is all they read
+    let completion = [
+        ("artist", &args.complete_artist),
+        ("album", &args.complete_album),
+        ("genre", &args.complete_genre),
+    ]
+    .into_iter()
+    .find_map(|(key, prefix)| prefix.as_deref().map(|p| (key, p)));
+    if let Some((key, prefix)) = completion {
+        let (tracks, _) = indexer::load_index("json")?;
+        for value in tui::complete_tag(&tracks, key, prefix) {
+            println!("{}", value);
+        }
+        return Ok(());
+    }
+
+    // before the config and dep_check, so it works even without mpv
+    if args.versions {
+        dep_check::print_versions();
+        return Ok(());
+    }
+
+    // stdout stays pure JSON, this runs before the logger could print anything
+    if args.dump_index_stats {
+        println!("{}", serde_json::to_string(&indexer::index_stats()?)?);
+        return Ok(());
+    }
+
+    if let Some(other) = &args.merge_index {
+        let report = indexer::merge_index(other)?;
+        println!(
+            "Merged {}: {} added, {} updated, {} skipped.",
+            other.display(),
+            report.added,
+            report.updated,
+            report.skipped
+        );
+        return Ok(());
+    }
+
+    if let Some(file) = &args.export_history {
+        let mut entries = history::load();
+        if let Some(age) = args.since {
+            let cutoff = history::now().saturating_sub(age);
+            entries.retain(|e| e.timestamp >= cutoff);
+        }
+        let (tracks, _) = indexer::load_index("json")?;
+        let csv = file
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("csv"));
+        let export = history::export(&entries, &tracks, csv);
+        std::fs::write(file, &export.text)
+            .with_context(|| format!("Could not write {:?}", file))?;
+        println!("Exported {} plays to {}.", export.plays, file.display());
+        if export.skipped > 0 {
+            println!(
+                "Skipped {} plays of tracks that left the index or have no artist/title tags.",
+                export.skipped
+            );
+        }
+        return Ok(());
+    }
+
     // handle editor
     if let Some(editor_opt) = args.config {
         if !config_file.exists() {
@@ -86,15 +216,8 @@ fn main() -> Result<()> {
             let _ = config::load(None)?;
         }
 
-        let editor = editor_opt.unwrap_or_else(|| {
-            std::env::var("EDITOR").unwrap_or_else(|_| {
-                if cfg!(windows) {
-                    "notepad.exe".to_string()
-                } else {
-                    "nano".to_string()
-                }
-            })
-        });
+        let peeked = peek_config(args.safe_mode, config_path_override.clone());
+        let editor = config::resolve_editor(editor_opt.as_deref(), peeked.as_ref());
 
         log::info!("Opening config with editor: {}", editor);
 
@@ -112,6 +235,9 @@ fn main() -> Result<()> {
     }
 
     let mut cfg = config::load(config_path_override.clone())?;
+    if let Some(format) = &args.index_format {
+        cfg.index_format = format.clone();
+    }
 
     // init logger
     let log_filter = if cfg.enable_file_logging {
@@ -171,11 +297,23 @@ fn main() -> Result<()> {
     log::debug!("CLI Args: {:?}", args);
     log::debug!("Config loaded from: {:?}", config_file);
 
+    // runs after the logger so every removal ends up in the log
+    if let Some(path) = &args.delete_track {
+        let deleted = trash::delete_tracks(std::slice::from_ref(path), args.purge, &cfg)?;
+        if deleted.is_empty() {
+            anyhow::bail!("Nothing was deleted");
+        }
+        return Ok(());
+    }
+
     dep_check::check(&mut cfg)?;
 
     if args.serial {
         cfg.serial_mode = true;
     }
+    if args.quiet {
+        cfg.no_scan_progress = true;
+    }
 
     if cfg.serial_mode {
         if let Err(e) = rayon::ThreadPoolBuilder::new()
@@ -191,8 +329,24 @@ fn main() -> Result<()> {
         }
     }
 
+    // a full rebuild so every file gets probed, -r profiles just the changed ones
+    if args.profile_scan {
+        cfg.profile_scan = true;
+        indexer::scan_to_index(&cfg, !args.refresh_index)?;
+        return Ok(());
+    }
+
+    // read-only, the exit code is what cron/monitoring looks at
+    if args.verify {
+        if !verify::run(&cfg)? {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     if let Some(v) = args.volume {
         cfg.volume = v;
+        cfg.volume_locked = true;
     }
     if args.shuffle {
         cfg.shuffle = true;
@@ -200,23 +354,54 @@ fn main() -> Result<()> {
     if args.no_shuffle {
         cfg.shuffle = false;
     }
+    // always settle on a seed, so a shuffle you liked can be replayed with --seed
+    cfg.shuffle_seed = args.seed.unwrap_or_else(rand::random);
+
+    if let Some(command) = &args.ipc_command {
+        return remote::run(command, &cfg);
+    }
+    log::debug!("Shuffle seed: {}", cfg.shuffle_seed);
     if args.video_ok {
         cfg.video_ok = true;
     }
     if args.no_video {
         cfg.video_ok = false;
     }
+    if args.mono {
+        cfg.force_mono = true;
+    }
+    if let Some(secs) = args.idle_timeout {
+        // a timeout only means something if mpv is allowed to sit idle
+        cfg.idle_timeout = secs;
+        cfg.idle_quit = secs == 0;
+    }
     if args.watch {
         cfg.watch = true;
+        // the flag means "show me everything", it outranks the finer config knobs
+        cfg.show_window = None;
+        cfg.decode_video = None;
     }
     if args.no_watch {
         cfg.watch = false;
+        cfg.show_window = None;
+        cfg.decode_video = None;
+    }
+    if args.safe_mode {
+        cfg.disable_hooks = true;
     }
     #[cfg(feature = "update")]
     if args.update {
+        if cfg.disable_hooks {
+            log::warn!("Safe mode: skipped the self-update");
+            anyhow::bail!("--update is disabled in safe mode (--safe-mode or disable_hooks)");
+        }
         update::update_self()?;
         return Ok(());
     }
+    #[cfg(feature = "lyrics")]
+    if let Some(path) = &args.lyrics {
+        return lyrics::run(path, &cfg);
+    }
     if let Some(ref mode) = args.loop_arg {
         cfg.loop_mode = mode.clone(); // Handles "inf", "5", etc.
     }
@@ -226,6 +411,21 @@ fn main() -> Result<()> {
     if args.repeat {
         cfg.loop_mode = "track".to_string();
     }
+    if args.detach {
+        cfg.detach_player = true;
+    }
+    if let Some(times) = args.repeat_queue {
+        // a numeric loop_mode is mpv's --loop-playlist=N, which plays the queue N times in total
+        cfg.loop_mode = times.to_string();
+    }
+    if opens_picker(&args) {
+        tui::require_terminal()?;
+        // refused, not quit: wrappers need a failing status to tell the two apart
+        if !tui::term_size_ok(&cfg) {
+            std::process::exit(1);
+        }
+    }
+
     if let Some(None) = args.radio {
         log::info!("Empty radio flag. Opening Radio Picker.");
         tui::run_radio_mode(&cfg, extra_mpv_args, None)?;
@@ -264,8 +464,7 @@ fn main() -> Result<()> {
     if config_changed {
         config::save(&cfg)?;
         println!("Configuration saved. Syncing index...");
-        let tracks = indexer::scan(&cfg, false)?;
-        indexer::save(&tracks)?;
+        indexer::scan_to_index(&cfg, false)?;
         return Ok(());
     }
     if args.manage_dirs {
@@ -273,19 +472,33 @@ fn main() -> Result<()> {
             config::save(&cfg)?;
             println!("Configuration saved.");
             println!("Syncing index with new directories...");
-            let tracks = indexer::scan(&cfg, false)?;
-            indexer::save(&tracks)?;
+            indexer::scan_to_index(&cfg, false)?;
         }
         return Ok(());
     }
 
+    if let Some(path) = &args.play_after {
+        // the album's order is the point
+        if !args.shuffle {
+            cfg.shuffle = false;
+        }
+        return tui::play_after(path, &cfg, extra_mpv_args);
+    }
+
     // tracks population (session vs persistence)
     let mut tracks: Vec<indexer::Track>;
+    // straight from the index file, no scan this run
+    let mut loaded_from_index = false;
 
     if let Some(target) = args.target.clone() {
         let path = PathBuf::from(&target);
 
         if path.is_dir() {
+            if args.video_once {
+                anyhow::bail!(
+                    "--video-once plays a single file or URL, not a folder. --video-ok lets a folder's videos in"
+                );
+            }
             log::info!("Session started for directory: {:?}", path);
             let target_canonical = dunce::canonicalize(&path).unwrap_or(path.clone());
             let target_str = target_canonical.to_string_lossy();
@@ -299,32 +512,67 @@ fn main() -> Result<()> {
                 eprintln!("No music files found in: {}", target_str);
                 return Ok(());
             }
+        } else if args.expand && target.contains("://") {
+            // picked order is the point, don't shuffle it away unless asked
+            if !args.shuffle {
+                cfg.shuffle = false;
+            }
+            tui::run_expand_mode(&target, &cfg, extra_mpv_args)?;
+            return Ok(());
+        } else if args.yt_formats && target.contains("://") {
+            tui::run_format_mode(&target, &cfg, extra_mpv_args)?;
+            return Ok(());
         } else {
-            player::play(&target, &cfg, extra_mpv_args)?;
+            player::check_target(&target, &cfg)?;
+            if args.video_once {
+                player::play_with_video(&target, &cfg, extra_mpv_args)?;
+            } else {
+                player::play(&target, &cfg, extra_mpv_args)?;
+            }
             return Ok(());
         }
     } else {
-        let (mut loaded_tracks, was_repaired) = indexer::load_index()?;
-
-        if args.reindex {
+        if args.no_index {
+            anyhow::bail!(
+                "--no-index needs a TARGET (a folder, file or URL), the library modes browse the index"
+            );
+        }
+        tracks = if args.reindex {
             log::info!("Rebuilding index (Full)...");
-            loaded_tracks = indexer::scan(&cfg, true)?;
-            indexer::save(&loaded_tracks)?;
-        } else if args.refresh_index || was_repaired {
-            if was_repaired {
-                log::info!("Index corruption healed. Syncing...");
+            indexer::rescan(&cfg, true)?
+        } else {
+            let (loaded_tracks, was_repaired) = indexer::load_index(&cfg.index_format)?;
+
+            if args.refresh_index || was_repaired {
+                if was_repaired {
+                    log::info!("Index corruption healed. Syncing...");
+                } else {
+                    log::info!("Refreshing index...");
+                }
+                // release the stale copy before the scan streams a fresh one
+                drop(loaded_tracks);
+                indexer::rescan(&cfg, false)?
+            } else if loaded_tracks.is_empty() {
+                log::info!("Index empty. First scan...");
+                indexer::rescan(&cfg, true)?
             } else {
-                log::info!("Refreshing index...");
+                loaded_from_index = true;
+                loaded_tracks
             }
-            loaded_tracks = indexer::scan(&cfg, false)?;
-            indexer::save(&loaded_tracks)?;
-        } else if loaded_tracks.is_empty() {
-            log::info!("Index empty. First scan...");
-            loaded_tracks = indexer::scan(&cfg, true)?;
-            indexer::save(&loaded_tracks)?;
-        }
+        };
+    }
 
-        tracks = loaded_tracks;
+    if args.reindex_missing_tags {
+        let candidates = tracks.iter().filter(|t| tagfix::needs_fix(t)).count();
+        log::info!("Re-probing {} tracks with UNKNOWN tags...", candidates);
+        let changed = indexer::reprobe_where(&mut tracks, &cfg, tagfix::needs_fix);
+        println!(
+            "Re-read {} tracks with UNKNOWN tags, {} picked up new tags.",
+            candidates, changed
+        );
+        if changed > 0 {
+            indexer::save(&tracks)?;
+        }
     }
 
     if tracks.is_empty() {
@@ -336,34 +584,86 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
+    if args.fix_tags {
+        let broken: Vec<&indexer::Track> = tracks.iter().filter(|t| tagfix::needs_fix(t)).collect();
+        if broken.is_empty() {
+            println!("No tracks with UNKNOWN tags. Nothing to fix.");
+            return Ok(());
+        }
+
+        let fixed = if args.from_path {
+            tagfix::run_from_path(&broken, &cfg.music_dirs)?
+        } else {
+            tagfix::run_interactive(&broken, &cfg.music_dirs)?
+        };
+
+        if fixed > 0 {
+            // rewritten files have a new mtime, so an incremental scan re-probes them
+            println!("Syncing index...");
+            indexer::scan_to_index(&cfg, false)?;
+        }
+        return Ok(());
+    }
+
+    let stats_key = if args.genre_stats {
+        Some("genre")
+    } else if args.artist_stats {
+        Some("artist")
+    } else if args.album_stats {
+        Some("album")
+    } else {
+        None
+    };
+    if let Some(key) = stats_key {
+        let filtered;
+        let counted: &[indexer::Track] = if has_filter_values(&args) {
+            filtered = tui::apply_cli_filters(&tracks, &args, &cfg, false);
+            &filtered
+        } else {
+            &tracks
+        };
+        return stats::run(counted, &cfg, key, args.top, args.json);
+    }
+
+    // what the pickers share from here on (remembered queries, the staged queue)
+    let mut session = tui::Session::default();
+
     // enry point shortcuts
     if let Some(None) = args.genre {
         log::info!("Empty genre flag. Opening Genre Picker.");
-        tui::run_tag_mode(&tracks, &cfg, Some("genre"), extra_mpv_args)?;
+        tui::run_tag_mode(&tracks, &cfg, &mut session, Some("genre"), extra_mpv_args)?;
         return Ok(());
     }
     if let Some(None) = args.artist {
         log::info!("Empty artist flag. Opening Artist Picker.");
-        tui::run_tag_mode(&tracks, &cfg, Some("artist"), extra_mpv_args)?;
+        tui::run_tag_mode(&tracks, &cfg, &mut session, Some("artist"), extra_mpv_args)?;
         return Ok(());
     }
     if let Some(None) = args.album {
         log::info!("Empty album flag. Opening Album Picker.");
-        tui::run_tag_mode(&tracks, &cfg, Some("album"), extra_mpv_args)?;
+        tui::run_tag_mode(&tracks, &cfg, &mut session, Some("album"), extra_mpv_args)?;
         return Ok(());
     }
     if let Some(None) = args.title {
         log::info!("Empty title flag. Opening Track Mode.");
-        tui::run_track_mode(&tracks, &cfg, extra_mpv_args)?;
+        tui::run_track_mode(&tracks, &cfg, &mut session, extra_mpv_args)?;
         return Ok(());
     }
     if let Some(search_input) = args.search {
         if let Some(query) = search_input {
-            tui::run_search_mode(&cfg, Some(query), extra_mpv_args)?;
+            tui::run_search_mode(&cfg, &mut session, Some(query), extra_mpv_args)?;
         } else {
             log::info!("Empty search flag. Opening YouTube Search.");
-            tui::run_search_mode(&cfg, None, extra_mpv_args)?;
+            tui::run_search_mode(&cfg, &mut session, None, extra_mpv_args)?;
         }
+        if cfg.search_adds_to_queue {
+            tui::play_staged(&cfg, &mut session, extra_mpv_args)?;
+        }
+        return Ok(());
+    }
+    if args.work {
+        log::info!("Opening Composer / Work browser.");
+        tui::run_work_mode(&tracks, &cfg, &mut session, extra_mpv_args)?;
         return Ok(());
     }
     if let Some(None) = args.playlist {
@@ -372,8 +672,188 @@ fn main() -> Result<()> {
         return Ok(());
     }
 
+    if args.random.is_some()
+        || args.random_album
+        || args.album_of_the_day
+        || args.play_newest
+        || args.play_newest_track
+        || args.surprise
+        || args.mood.is_some()
+        || args.most_played.is_some()
+        || args.balanced_shuffle
+    {
+        let filtered;
+        let candidates: &[indexer::Track] = if has_filter_values(&args) {
+            filtered = tui::apply_cli_filters(&tracks, &args, &cfg, false);
+            &filtered
+        } else {
+            &tracks
+        };
+        // audiobooks have their own order, they stay out of shuffles
+        let pool: Vec<&indexer::Track> = candidates
+            .iter()
+            .filter(|t| t.media_type != "playlist" && !audiobook::is_audiobook(&cfg, &t.path))
+            .collect();
+
+        if pool.is_empty() {
+            eprintln!("No match.");
+            return Ok(());
+        }
+
+        if args.play_newest || args.play_newest_track {
+            let newest = queue::newest_tracks(&pool, args.by_mtime);
+            let picked = if args.play_newest {
+                queue::albums_of(&pool, &newest)
+            } else {
+                newest
+            };
+            println!(
+                "🆕 {} - {}{}",
+                picked[0].artist,
+                picked[0].title,
+                if picked.len() > 1 {
+                    format!(" (+{} more)", picked.len() - 1)
+                } else {
+                    String::new()
+                }
+            );
+            // newest-first hunting is about one specific thing, keep its order
+            if !args.shuffle {
+                cfg.shuffle = false;
+            }
+            player::play_tracks(&picked, &cfg, extra_mpv_args)?;
+            return Ok(());
+        }
+
+        if args.surprise {
+            if !args.shuffle {
+                cfg.shuffle = false;
+            }
+            tui::play_surprise(&pool, &cfg, extra_mpv_args)?;
+            return Ok(());
+        }
+
+        if let Some(count) = args.most_played {
+            let scores = history::compute_decayed_scores(
+                &history::load(),
+                cfg.play_decay_half_life_days,
+                history::now(),
+            );
+            let top = queue::most_played(&pool, &scores, count);
+            if top.is_empty() {
+                eprintln!("Nothing played yet. Plays are logged with record_history on.");
+                return Ok(());
+            }
+
+            println!("🔥 Most played lately: {} tracks", top.len());
+            // best first, unless asked to shuffle
+            if !args.shuffle {
+                cfg.shuffle = false;
+            }
+            player::play_tracks(&top, &cfg, extra_mpv_args)?;
+            return Ok(());
+        }
+
+        if args.balanced_shuffle {
+            let mixed =
+                queue::balanced_shuffle(&pool, &cfg.music_dirs, args.limit, cfg.shuffle_seed);
+            println!(
+                "🔀 Balanced shuffle: {} of {} tracks",
+                mixed.len(),
+                pool.len()
+            );
+            // already shuffled, mpv's own shuffle would undo the turn-taking
+            cfg.shuffle = false;
+            player::play_tracks(&mixed, &cfg, extra_mpv_args)?;
+            return Ok(());
+        }
+
+        if let Some(mood) = &args.mood {
+            let Some(&(min, max)) = cfg.mood_presets.get(&mood.to_lowercase()) else {
+                let mut known: Vec<&str> = cfg.mood_presets.keys().map(|k| k.as_str()).collect();
+                known.sort_unstable();
+                anyhow::bail!(
+                    "No mood '{}' in mood_presets (have: {})",
+                    mood,
+                    known.join(", ")
+                );
+            };
+            let picked = queue::in_bpm_range(&pool, min, max);
+            if picked.is_empty() {
+                eprintln!(
+                    "No tracks with a BPM tag between {} and {}. Tracks without one are skipped, a --reindex picks up newly added tags.",
+                    min, max
+                );
+                return Ok(());
+            }
+
+            println!("🎚  {} ({}-{} BPM): {} tracks", mood, min, max, picked.len());
+            cfg.shuffle = true;
+            player::play_tracks(&picked, &cfg, extra_mpv_args)?;
+            return Ok(());
+        }
+
+        if args.random_album || args.album_of_the_day {
+            let picked = if args.album_of_the_day {
+                queue::album_of_the_day(&pool, history::now() / 86_400)
+            } else {
+                queue::pick_random_album(
+                    &pool,
+                    args.include_singles,
+                    &mut queue::seeded_rng(cfg.shuffle_seed),
+                )
+            };
+            let Some(album) = picked else {
+                eprintln!(
+                    "No albums to pick from (tracks with an UNKNOWN album{} don't count).",
+                    if args.include_singles {
+                        ""
+                    } else {
+                        " or single-track albums"
+                    }
+                );
+                return Ok(());
+            };
+
+            println!(
+                "💿 {} - {} ({} tracks)",
+                queue::album_artist_of(album[0]),
+                album[0].album,
+                album.len()
+            );
+            // the whole point is hearing it in order
+            if !args.shuffle {
+                cfg.shuffle = false;
+            }
+            player::play_tracks(&album, &cfg, extra_mpv_args)?;
+            return Ok(());
+        }
+
+        let count = args.random.unwrap_or_default();
+        let avoid = if cfg.avoid_recent_hours > 0 {
+            history::recent_paths(&history::load(), cfg.avoid_recent_hours, history::now())
+        } else {
+            HashSet::new()
+        };
+
+        let picked = queue::pick_random(
+            &pool,
+            count,
+            &avoid,
+            &mut queue::seeded_rng(cfg.shuffle_seed),
+        );
+        log::info!("Random pick: {} of {} tracks", picked.len(), pool.len());
+
+        player::play_tracks(&picked, &cfg, extra_mpv_args)?;
+        return Ok(());
+    }
+
     // main search and filter logic
-    if args.genre.is_some() || args.artist.is_some() || args.album.is_some() || args.title.is_some()
+    if args.genre.is_some()
+        || args.artist.is_some()
+        || args.album.is_some()
+        || args.title.is_some()
+        || has_filter_values(&args)
     {
         let is_multi_value_search = args
             .artist
@@ -398,7 +878,7 @@ fn main() -> Result<()> {
 
         // stage 1: exact match
         let mut filtered = if !is_multi_value_search {
-            tui::apply_cli_filters(&tracks, &args, true)
+            tui::apply_cli_filters(&tracks, &args, &cfg, true)
         } else {
             Vec::new()
         };
@@ -406,7 +886,7 @@ fn main() -> Result<()> {
         // stage 2: partial match / ambiguity handling
         if filtered.is_empty() {
             log::debug!("Exact match skipped or failed, trying partial...");
-            let partials = tui::apply_cli_filters(&tracks, &args, false);
+            let partials = tui::apply_cli_filters(&tracks, &args, &cfg, false);
 
             if partials.is_empty() {
                 eprintln!("No match.");
@@ -429,7 +909,18 @@ fn main() -> Result<()> {
             for t in &partials {
                 match active_key {
                     "artist" => {
-                        unique_options.insert(t.artist.clone());
+                        unique_options.extend(
+                            queue::artists_of(t)
+                                .into_iter()
+                                .filter(|a| {
+                                    args.artist.iter().flatten().any(|q| {
+                                        q.to_lowercase()
+                                            .split(',')
+                                            .any(|term| a.to_lowercase().contains(term.trim()))
+                                    })
+                                })
+                                .map(String::from),
+                        );
                     }
                     "genre" => {
                         unique_options.insert(t.genre.clone());
@@ -457,6 +948,11 @@ fn main() -> Result<()> {
                     filtered = partials
                         .into_iter()
                         .filter(|t| {
+                            if active_key == "artist" {
+                                return queue::artists_of(t)
+                                    .into_iter()
+                                    .any(|a| selected_set.contains(a));
+                            }
                             let val = match active_key {
                                 "artist" => &t.artist,
                                 "genre" => &t.genre,
@@ -480,26 +976,98 @@ fn main() -> Result<()> {
             return Ok(());
         }
 
+        if args.year.is_some() {
+            filtered = queue::chronological_order(filtered, cfg.prefer_original_year);
+        } else if args.album.is_some() {
+            filtered = queue::album_order(filtered);
+        }
+
+        if args.transcode
+            && let Some(out_dir) = &args.out
+        {
+            let to = args.to.clone().unwrap_or_else(|| {
+                Some(cfg.preferred_audio_codec.clone())
+                    .filter(|codec| !codec.is_empty())
+                    .unwrap_or_else(|| "mp3".to_string())
+            });
+            return transcode::run(&filtered, &cfg, &to, out_dir);
+        }
+        if args.rename {
+            let pattern = args.pattern.as_deref().unwrap_or(rename::DEFAULT_PATTERN);
+            return rename::run(&filtered, &cfg, pattern, args.dry_run);
+        }
+
+        // "just play one": skips both the queue and the picker
+        let pick = if args.first {
+            filtered.first()
+        } else if args.last {
+            filtered.last()
+        } else {
+            None
+        };
+        if let Some(track) = pick {
+            log::info!(
+                "Playing the {} of {} matches: {}",
+                if args.first { "first" } else { "last" },
+                filtered.len(),
+                track.path
+            );
+            let queue_cfg = player::queue_config(std::slice::from_ref(track), &cfg);
+            player::play(&track.path, &queue_cfg, extra_mpv_args)?;
+            return Ok(());
+        }
+
+        if args.pick {
+            log::info!("Picking from {} matches in Track Mode", filtered.len());
+            tui::run_track_mode(&filtered, &cfg, &mut session, extra_mpv_args)?;
+            return Ok(());
+        }
+
         if filtered.len() == 1 {
             log::info!("Single match found. Playing directly.");
-            player::play(&filtered[0].path, &cfg, extra_mpv_args)?;
+            let queue_cfg = player::queue_config(&filtered, &cfg);
+            player::play(&filtered[0].path, &queue_cfg, extra_mpv_args)?;
             return Ok(());
         }
 
         println!("Found {} matching tracks.", filtered.len());
         if args.play_all || args.title.is_some() {
-            let paths: Vec<String> = filtered.iter().map(|t| t.path.clone()).collect();
-            player::play_files(&paths, &cfg, extra_mpv_args)?;
+            let music = audiobook::without_audiobooks(&cfg, &filtered);
+            if music.is_empty() {
+                // only chapters matched, so it's the books that were asked for
+                let chapters = filtered.iter().map(|t| t.path.clone()).collect();
+                audiobook::play(chapters, &cfg, extra_mpv_args)?;
+            } else {
+                player::play_tracks(&music, &cfg, extra_mpv_args)?;
+            }
         } else {
-            tui::run_post_filter_action(&filtered, &cfg, extra_mpv_args)?;
+            tui::run_post_filter_action(&filtered, &cfg, &mut session, extra_mpv_args)?;
         }
         return Ok(());
     }
 
+    if args.transcode || args.rename {
+        anyhow::bail!(
+            "--{} works on a filtered selection, add a filter like -g, -a, -b or --since",
+            if args.transcode {
+                "transcode"
+            } else {
+                "rename"
+            }
+        );
+    }
+    if args.first || args.last {
+        anyhow::bail!(
+            "--first/--last pick from a filtered selection, add a filter like -g, -a, -b or --since"
+        );
+    }
+
     // default modes
-    if args.play_all {
-        let paths: Vec<String> = tracks.iter().map(|t| t.path.clone()).collect();
-        player::play_files(&paths, &cfg, extra_mpv_args)?;
+    if args.pick {
+        tui::run_track_mode(&tracks, &cfg, &mut session, extra_mpv_args)?;
+    } else if args.play_all {
+        let music = audiobook::without_audiobooks(&cfg, &tracks);
+        player::play_tracks(&music, &cfg, extra_mpv_args)?;
     } else if let Some(maybe_val) = args.playlist {
         if let Some(playlist_name) = maybe_val {
             let name_lower = playlist_name.to_lowercase();
@@ -521,8 +1089,94 @@ fn main() -> Result<()> {
         }
         tui::run_playlist_mode(&tracks, &cfg, extra_mpv_args)?;
     } else {
-        tui::run_main_menu(&mut tracks, &mut cfg, extra_mpv_args)?;
+        if cfg.background_refresh && loaded_from_index {
+            tui::start_background_refresh(&cfg);
+        }
+        tui::run_main_menu(&mut tracks, &mut cfg, &mut session, extra_mpv_args)?;
     }
 
     Ok(())
 }
+
+// the config for the utility flags that run before the real load, --safe-mode included
+fn peek_config(safe_mode: bool, override_path: Option<PathBuf>) -> Option<config::Config> {
+    config::peek(override_path).map(|cfg| config::Config {
+        disable_hooks: cfg.disable_hooks || safe_mode,
+        ..cfg
+    })
+}
+
+// true when any tag filter was given an actual value (not just the picker flag)
+fn has_filter_values(args: &Cli) -> bool {
+    [&args.genre, &args.artist, &args.album, &args.title]
+        .iter()
+        .any(|f| matches!(f, Some(Some(_))))
+        || args.min_bitrate.is_some()
+        || args.max_bitrate.is_some()
+        || args.min_size.is_some()
+        || args.max_size.is_some()
+        || args.since.is_some()
+        || args.year.is_some()
+        || args.comment.is_some()
+        || args.min_rating.is_some()
+}
+
+// the deleting utility flags ask first on a terminal. -y skips the question,
+// and so does a script (no terminal to answer it)
+fn confirmed(args: &Cli, question: &str) -> Result<bool> {
+    use std::io::IsTerminal;
+    if args.yes || !std::io::stdin().is_terminal() {
+        return Ok(true);
+    }
+    tui::confirm(question)
+}
+
+// runs that end up in a skim picker; filters with values and direct plays don't,
+// unless --pick hands the matches to Track Mode
+fn opens_picker(args: &Cli) -> bool {
+    if args.pick {
+        return true;
+    }
+    if has_filter_values(args)
+        || args.play_all
+        || args.random.is_some()
+        || args.random_album
+        || args.album_of_the_day
+        || args.play_newest
+        || args.play_newest_track
+        || args.surprise
+        || args.mood.is_some()
+        || args.most_played.is_some()
+        || args.balanced_shuffle
+        || args.play_after.is_some()
+        || args.fix_tags
+        || args.transcode
+        || args.rename
+        || args.genre_stats
+        || args.artist_stats
+        || args.album_stats
+    {
+        return false;
+    }
+    match args.target {
+        Some(_) => args.expand || args.yt_formats,
+        None => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[test]
+    fn test_opens_picker() {
+        let parse = |args: &[&str]| Cli::try_parse_from([&["mpv-music"], args].concat()).unwrap();
+        assert!(opens_picker(&parse(&[])));
+        assert!(opens_picker(&parse(&["-g"])));
+        assert!(!opens_picker(&parse(&["-g", "Jazz"])));
+        assert!(!opens_picker(&parse(&["song.mp3"])));
+        assert!(opens_picker(&parse(&["--pick", "-g", "Jazz"])));
+        assert!(opens_picker(&parse(&["--pick"])));
+    }
+}
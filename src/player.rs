@@ -1,10 +1,22 @@
-use crate::config::Config;
+use crate::config::{Config, UrlResolver};
+use crate::indexer::Track;
+use crate::ipc;
+use crate::playlist;
+use crate::queue;
+use crate::term_title;
+use crate::tool_cache::{self, JsRuntime};
 use anyhow::{Context, Result};
 use directories::ProjectDirs;
+use lofty::prelude::*;
+use lofty::probe::Probe;
+use rand::seq::SliceRandom;
+use regex::Regex;
+use std::borrow::{Borrow, Cow};
+use std::collections::HashMap;
 use std::io::Write;
 use std::process::{Command, Stdio};
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
 struct TempCleaner {
     path: std::path::PathBuf,
@@ -36,13 +48,45 @@ impl Drop for IpcCleaner {
     }
 }
 
+/// Typed before a URL or file ("video:https://..."), plays that one target with
+/// a window and video whatever `video_ok`/`watch` say.
+pub const VIDEO_PREFIX: &str = "video:";
+
 pub fn play(target: &str, config: &Config, extra_args: &[String]) -> Result<()> {
+    match target.strip_prefix(VIDEO_PREFIX) {
+        Some(inner) if !inner.is_empty() => play_target(inner, config, extra_args, true),
+        _ => play_target(target, config, extra_args, false),
+    }
+}
+
+/// `play` with video for this one target (`--video-once`), the config and the
+/// rest of the session stay as they are.
+pub fn play_with_video(target: &str, config: &Config, extra_args: &[String]) -> Result<()> {
+    let target = target.strip_prefix(VIDEO_PREFIX).unwrap_or(target);
+    play_target(target, config, extra_args, true)
+}
+
+fn play_target(
+    target: &str,
+    config: &Config,
+    extra_args: &[String],
+    video_once: bool,
+) -> Result<()> {
     log::info!("Preparing playback for target: {}", target);
 
-    let cmd_name = if cfg!(windows) { "mpv.com" } else { "mpv" };
-    let mut cmd = Command::new(cmd_name);
+    if playlist::needs_expansion(target) || playlist::has_nested(target) {
+        return play_expanded_playlist(target, config, extra_args);
+    }
+
+    let mut cmd = Command::new(mpv_binary(config));
 
-    apply_common_args(&mut cmd, config, extra_args);
+    apply_common_args(&mut cmd, config, extra_args, video_once);
+
+    // a single target (dir, m3u) is expanded by mpv, so only mpv can shuffle it
+    if config.shuffle {
+        log::debug!("Shuffle enabled (mpv)");
+        cmd.arg("--shuffle");
+    }
 
     let optimization_target = if let Some(inner_url) = inspect_playlist_content(target, config) {
         log::debug!(
@@ -54,16 +98,38 @@ pub fn play(target: &str, config: &Config, extra_args: &[String]) -> Result<()>
         target.to_string()
     };
 
-    apply_url_optimizations(&mut cmd, &optimization_target, config);
+    apply_url_optimizations(
+        &mut cmd,
+        &optimization_target,
+        config,
+        extra_args,
+        video_once,
+    );
+
+    if config.allow_sidecar_args {
+        let track_args = per_track_args(target);
+        if !track_args.is_empty() {
+            log::info!("Per-track mpv args for '{}': {:?}", target, track_args);
+            cmd.args(track_args);
+        }
+    }
+
+    if config.detach_player {
+        return spawn_detached(cmd, target, &ipc::detached_id(), None);
+    }
 
-    let socket_to_clean = handle_radio_sync(&mut cmd, target);
+    let socket_to_clean = attach_ipc(&mut cmd, target, config, false);
 
     let _ipc_guard = IpcCleaner {
         path: socket_to_clean.clone(),
     };
+    let title_on = term_title::enabled(config);
     let ipc_handler = socket_to_clean.clone();
     ctrlc::set_handler(move || {
         log::info!("\nReceived Ctrl+C.");
+        if title_on {
+            term_title::restore();
+        }
         if let Some(ref path) = ipc_handler {
             let p = std::path::Path::new(path);
             if p.exists() {
@@ -79,36 +145,78 @@ pub fn play(target: &str, config: &Config, extra_args: &[String]) -> Result<()>
 
     log::debug!("Exec: {:?}", cmd);
 
+    let _title_guard = title_on.then(term_title::Guard::new);
     let status = cmd.status().context("Failed to launch mpv")?;
 
     if !status.success() && classify_target_weight(&optimization_target) > 0 {
         log::error!("MPV process exited with error status. Checking yt-dlp health...");
         check_ytdlp_status();
+        // mpv owns yt-dlp's stderr here, so the hint can't know the exact error
+        if classify_target_weight(&optimization_target) == 2
+            && crate::config::ytdlp_cookie_options(config).is_none()
+        {
+            eprintln!("If yt-dlp said \"Sign in to confirm your age\", cookies can get past it:");
+            crate::search::print_cookie_hint();
+        }
+        // a runtime may have been (un)installed since it was cached
+        tool_cache::forget_js_runtime();
     }
 
     Ok(())
 }
 
 pub fn play_files(paths: &[String], config: &Config, extra_args: &[String]) -> Result<()> {
+    let paths = paths.iter().map(String::as_str).collect();
+    run_queue(paths, config, extra_args, false).map(|_| ())
+}
+
+/// Plays `tracks` as one queue (`--play-all`). Only borrows their paths, so a
+/// huge library starts playing without copying (or shuffling) all of it first.
+pub fn play_tracks<T: Borrow<Track>>(
+    tracks: &[T],
+    config: &Config,
+    extra_args: &[String],
+) -> Result<()> {
+    let paths = tracks.iter().map(|t| t.borrow().path.as_str()).collect();
+    run_queue(paths, &queue_config(tracks, config), extra_args, false).map(|_| ())
+}
+
+/// Like `play_files`, but tells whether mpv ran off the end of the queue (true)
+/// instead of being quit by the user.
+pub fn play_files_to_end(paths: &[String], config: &Config, extra_args: &[String]) -> Result<bool> {
+    let paths = paths.iter().map(String::as_str).collect();
+    run_queue(paths, config, extra_args, true)
+}
+
+fn run_queue(
+    mut paths: Vec<&str>,
+    config: &Config,
+    extra_args: &[String],
+    watch_end: bool,
+) -> Result<bool> {
     if paths.is_empty() {
         log::debug!("play_files called with empty path list, skipping");
-        return Ok(());
+        return Ok(false);
     }
 
     log::info!("Preparing playback for {} files", paths.len());
-    let cmd_name = if cfg!(windows) { "mpv.com" } else { "mpv" };
-    let mut cmd = Command::new(cmd_name);
+    let mut cmd = Command::new(mpv_binary(config));
 
-    apply_common_args(&mut cmd, config, extra_args);
+    apply_common_args(&mut cmd, config, extra_args, false);
+
+    if config.shuffle && !config.shuffle_once {
+        log::debug!("Shuffle enabled (mpv)");
+        cmd.arg("--shuffle");
+    }
 
     // O(N) Single-Pass Scan: Find the item with the highest requirement.
     // 0 = Local (Default)
     // 1 = HTTP/FTP (Basic network opts)
     // 2 = YouTube (Needs JS runtimes & headers)
-    let mut best_target = paths.first();
+    let mut best_target = paths.first().copied();
     let mut max_weight = 0;
 
-    for path in paths {
+    for path in &paths {
         let weight = classify_target_weight(path);
         if weight > max_weight {
             max_weight = weight;
@@ -119,10 +227,45 @@ pub fn play_files(paths: &[String], config: &Config, extra_args: &[String]) -> R
         }
     }
 
+    // shuffled here with shuffle and shuffle_once on
+    let mut rng = (config.shuffle && config.shuffle_once).then(|| {
+        log::debug!("Shuffle enabled (seed {})", config.shuffle_seed);
+        queue::seeded_rng(config.shuffle_seed)
+    });
+
+    if config.detach_player {
+        if let Some(target) = best_target {
+            apply_url_optimizations(&mut cmd, target, config, extra_args, false);
+        }
+        if let Some(rng) = &mut rng {
+            paths.shuffle(rng);
+        }
+        let id = ipc::detached_id();
+        let queue_path = write_queue_file(&paths, &format!("detached_{}", id))?;
+        let playlist = format!("--playlist={}", queue_path.to_string_lossy());
+        spawn_detached(cmd, &playlist, &id, Some(queue_path))?;
+        return Ok(false);
+    }
+
+    // a huge queue starts from its head, the rest follows over IPC while it plays.
+    // mpv's own shuffle would only ever see the head, so that keeps the old way
+    let mpv_shuffles = config.shuffle && !config.shuffle_once;
+    let head_len = if mpv_shuffles {
+        paths.len()
+    } else {
+        STREAM_HEAD
+    };
+    let (head, rest) = queue::split_head(&mut paths, head_len, rng.as_mut());
+
     let socket_to_clean = if let Some(target) = best_target {
         log::debug!("Configuring mpv based on representative track: {}", target);
-        apply_url_optimizations(&mut cmd, target, config);
-        handle_radio_sync(&mut cmd, target) // No semicolon here!
+        apply_url_optimizations(&mut cmd, target, config, extra_args, false);
+        attach_ipc(&mut cmd, target, config, watch_end || !rest.is_empty()) // No semicolon here!
+    } else {
+        None
+    };
+    let end_reason = if watch_end {
+        socket_to_clean.clone().map(ipc::last_end_reason)
     } else {
         None
     };
@@ -131,31 +274,19 @@ pub fn play_files(paths: &[String], config: &Config, extra_args: &[String]) -> R
         path: socket_to_clean.clone(),
     };
 
-    let dirs = ProjectDirs::from("com", "furqanhun", "mpv-music")
-        .context("Could not determine data directory")?;
-    let data_dir = dirs.data_dir();
-    std::fs::create_dir_all(data_dir)?;
-
-    let pid = std::process::id();
-    let queue_path = data_dir.join(format!("queue_{}.m3u8", pid));
-
-    {
-        let mut file = std::fs::File::create(&queue_path)
-            .context("Failed to create temporary playlist file")?;
-
-        writeln!(file, "#EXTM3U")?;
-        for path in paths {
-            writeln!(file, "{}", path)?;
-        }
-    }
+    let queue_path = write_queue_file(head, &format!("queue_{}", std::process::id()))?;
 
     let running = Arc::new(AtomicBool::new(true));
     let r_handler = running.clone();
     let p_handler = queue_path.clone();
     let ipc_handler = socket_to_clean.clone();
+    let title_on = term_title::enabled(config);
 
     // Register signal handler
     ctrlc::set_handler(move || {
+        if title_on {
+            term_title::restore();
+        }
         if r_handler.swap(false, Ordering::SeqCst) && p_handler.exists() {
             let _ = std::fs::remove_file(&p_handler);
             log::info!("\nReceived Ctrl+C. Cleaned up queue file.");
@@ -185,12 +316,255 @@ pub fn play_files(paths: &[String], config: &Config, extra_args: &[String]) -> R
     log::info!("Launching MPV for playlist playback...");
     log::debug!("Exec: {:?}", cmd);
 
-    // blocks until mpv closes
-    cmd.status().context("Failed to launch mpv for playlist")?;
+    // blocks until mpv closes, the rest of a streamed queue is appended meanwhile
+    let _title_guard = title_on.then(term_title::Guard::new);
+    let mpv_done = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        if let Some(socket) = &socket_to_clean
+            && !rest.is_empty()
+        {
+            let rng = rng.as_mut();
+            let mpv_done = &mpv_done;
+            scope.spawn(move || append_once_loaded(socket, rest, rng, mpv_done));
+        }
+        let status = cmd.status();
+        mpv_done.store(true, Ordering::SeqCst);
+        status
+    })
+    .context("Failed to launch mpv for playlist")?;
+
+    let ran_out = end_reason
+        .and_then(|h| h.join().ok().flatten())
+        .is_some_and(|reason| reason == "eof");
+    Ok(ran_out)
+}
+
+// queues longer than this start playing before the rest is handed to mpv
+const STREAM_HEAD: usize = 500;
+// and the rest goes over in batches this big, so mpv never parses one huge list
+const APPEND_BATCH: usize = 5000;
+
+// waits for mpv to load the head of a streamed queue, then appends the rest
+// (shuffled only now when it's shuffled at all). Gives up once mpv exits
+fn append_once_loaded(
+    socket: &str,
+    rest: &mut [&str],
+    rng: Option<&mut queue::QueueRng>,
+    mpv_done: &AtomicBool,
+) {
+    let loaded = || {
+        ipc::request(socket, &["get_property", "playlist-count"])
+            .ok()
+            .and_then(|count| count.as_u64())
+            .is_some_and(|count| count > 0)
+    };
+    while !loaded() {
+        if mpv_done.load(Ordering::SeqCst) {
+            log::warn!(
+                "mpv exited before loading the queue, {} tracks were not appended",
+                rest.len()
+            );
+            return;
+        }
+        std::thread::sleep(std::time::Duration::from_millis(200));
+    }
+
+    if let Some(rng) = rng {
+        rest.shuffle(rng);
+    }
+    for batch in rest.chunks(APPEND_BATCH) {
+        if mpv_done.load(Ordering::SeqCst) {
+            return;
+        }
+        if let Err(e) = crate::remote::append_to_player(socket, batch) {
+            log::warn!("Could not queue the remaining tracks: {:#}", e);
+            return;
+        }
+    }
+}
+
+// mpv.com is the console wrapper on Windows, a detached player has no console to wrap
+fn mpv_binary(config: &Config) -> &'static str {
+    if cfg!(windows) && !config.detach_player {
+        "mpv.com"
+    } else {
+        "mpv"
+    }
+}
+
+// "<name>.m3u8" in the data dir
+fn write_queue_file(order: &[&str], name: &str) -> Result<std::path::PathBuf> {
+    let dirs = ProjectDirs::from("com", "furqanhun", "mpv-music")
+        .context("Could not determine data directory")?;
+    let data_dir = dirs.data_dir();
+    std::fs::create_dir_all(data_dir)?;
+    let queue_path = data_dir.join(format!("{}.m3u8", name));
+
+    let mut file =
+        std::fs::File::create(&queue_path).context("Failed to create temporary playlist file")?;
+
+    writeln!(file, "#EXTM3U")?;
+    for path in order {
+        writeln!(file, "{}", path)?;
+    }
+    Ok(queue_path)
+}
+
+/// Launches mpv in the background and returns right away (`detach_player`).
+/// Its output goes to mpv-detached.log in the data dir, and it's controlled over
+/// its IPC socket (`--ipc-command`, or any mpv IPC client).
+fn spawn_detached(
+    mut cmd: Command,
+    target: &str,
+    id: &str,
+    queue_file: Option<std::path::PathBuf>,
+) -> Result<()> {
+    let dirs = ProjectDirs::from("com", "furqanhun", "mpv-music")
+        .context("Could not determine data directory")?;
+    let data_dir = dirs.data_dir();
+    std::fs::create_dir_all(data_dir)?;
+    sweep_detached_queues(data_dir);
+
+    let log_path = data_dir.join("mpv-detached.log");
+    let log = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .with_context(|| format!("Could not open {:?}", log_path))?;
+
+    let socket = ipc::detached_socket_path(id);
+    cmd.arg(format!("--input-ipc-server={}", socket))
+        .arg("--no-input-terminal")
+        .arg(target)
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+
+    // out of our process group, so Ctrl+C in this terminal doesn't reach it
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        cmd.creation_flags(0x00000008 | 0x00000200); // DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP
+    }
 
+    log::debug!("Exec (detached): {:?}", cmd);
+    let mut child = cmd.spawn().context("Failed to launch mpv")?;
+    log::info!("Detached mpv running as pid {}", child.id());
+    println!(
+        "▶ mpv is playing in the background (pid {}). Control it over IPC at {}",
+        child.id(),
+        socket
+    );
+
+    // the queue file stays for as long as its mpv runs. If we exit first, a
+    // later detached launch sweeps it once that player is gone
+    std::thread::spawn(move || {
+        // reaped, so a finished player doesn't linger as a zombie while the TUI runs
+        let _ = child.wait();
+        if let Some(path) = queue_file {
+            let _ = std::fs::remove_file(&path);
+            log::debug!("Removed detached queue file {:?}", path);
+        }
+    });
     Ok(())
 }
 
+// queue files of detached players that no longer answer on their socket. A
+// minute's grace, a file that new may belong to a player still starting up
+fn sweep_detached_queues(data_dir: &std::path::Path) {
+    let Ok(entries) = std::fs::read_dir(data_dir) else {
+        return;
+    };
+    let minute_ago = std::time::SystemTime::now() - std::time::Duration::from_secs(60);
+    for entry in entries.flatten() {
+        let recent = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .is_ok_and(|modified| modified >= minute_ago);
+        if recent {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(id) = name
+            .strip_prefix("detached_")
+            .and_then(|rest| rest.strip_suffix(".m3u8"))
+        else {
+            continue;
+        };
+        let socket = ipc::detached_socket_path(id);
+        if ipc::request(&socket, &["get_property", "pid"]).is_err() {
+            let _ = std::fs::remove_file(entry.path());
+            log::debug!("Swept leftover detached queue {}", name);
+        }
+    }
+}
+
+/// `config` for playing `tracks`: a queue that is all one genre or all one
+/// artist gets its volume_overrides entry (artist wins when both match), never
+/// over an explicit --volume. Works on the tracks the caller already has.
+pub fn queue_config<'a, T: Borrow<Track>>(tracks: &[T], config: &'a Config) -> Cow<'a, Config> {
+    if config.volume_overrides.is_empty() || config.volume_locked {
+        return Cow::Borrowed(config);
+    }
+    let tracks: Vec<&Track> = tracks.iter().map(Borrow::borrow).collect();
+    match override_for(&tracks, &config.volume_overrides) {
+        Some(volume) => {
+            log::info!("Queue shares a tag in volume_overrides, volume {}", volume);
+            Cow::Owned(Config {
+                volume,
+                ..config.clone()
+            })
+        }
+        None => Cow::Borrowed(config),
+    }
+}
+
+fn override_for(tracks: &[&Track], overrides: &HashMap<String, u8>) -> Option<u8> {
+    let shared = |field: fn(&Track) -> &str| -> Option<u8> {
+        let first = field(tracks.first()?).trim();
+        if !tracks
+            .iter()
+            .all(|t| field(t).trim().eq_ignore_ascii_case(first))
+        {
+            return None;
+        }
+        overrides
+            .iter()
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case(first))
+            .map(|(_, v)| *v)
+    };
+    shared(|t| &t.artist).or_else(|| shared(|t| &t.genre))
+}
+
+// mpv can't read XSPF/ASX or follow nested playlists, so hand it a normalized queue instead
+fn play_expanded_playlist(target: &str, config: &Config, extra_args: &[String]) -> Result<()> {
+    match playlist::entries(std::path::Path::new(target)) {
+        Ok(entries) if !entries.is_empty() => {
+            log::info!(
+                "Expanded playlist {} into {} entries",
+                target,
+                entries.len()
+            );
+            play_files(&entries, config, extra_args)
+        }
+        Ok(_) => {
+            log::warn!("Playlist has no playable entries: {}", target);
+            eprintln!("No playable entries in: {}", target);
+            Ok(())
+        }
+        Err(e) => {
+            log::warn!("Skipping unparseable playlist '{}': {:#}", target, e);
+            eprintln!("Could not parse playlist: {}", target);
+            Ok(())
+        }
+    }
+}
+
 // helpers
 
 // 0 = Local File
@@ -206,6 +580,86 @@ fn classify_target_weight(s: &str) -> u8 {
     }
 }
 
+// container/codec signatures at the start of the file: (offset, magic)
+const MEDIA_MAGIC: &[(usize, &[u8])] = &[
+    (0, b"ID3"),
+    (0, b"fLaC"),
+    (0, b"OggS"),
+    (0, b"RIFF"),
+    (0, b"FORM"), // aiff
+    (0, b"wvpk"),
+    (0, b"MAC "),
+    (0, b"#!AMR"),
+    (0, b"MThd"),
+    (0, &[0x1A, 0x45, 0xDF, 0xA3]), // matroska / webm
+    (0, &[0x30, 0x26, 0xB2, 0x75]), // asf / wma
+    (0, b"FLV"),
+    (0, &[0x00, 0x00, 0x01, 0xBA]), // mpeg program stream
+    (4, b"ftyp"),                   // mp4 / m4a / mov
+];
+
+fn looks_like_media(head: &[u8]) -> bool {
+    // a bare mp3/aac frame sync
+    if head.len() >= 2 && head[0] == 0xFF && head[1] & 0xE0 == 0xE0 {
+        return true;
+    }
+    MEDIA_MAGIC
+        .iter()
+        .any(|(offset, magic)| head.get(*offset..offset + magic.len()) == Some(*magic))
+}
+
+/// Pre-check for a direct file target. Known extensions and files that sniff as
+/// media pass quietly, anything else gets a warning (or an error with
+/// `strict_target_ext`) instead of mpv's cryptic failure.
+pub fn check_target(target: &str, config: &Config) -> Result<()> {
+    let path = std::path::Path::new(target);
+    if target.contains("://") || !path.is_file() {
+        return Ok(());
+    }
+
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let known = [
+        &config.audio_exts,
+        &config.video_exts,
+        &config.playlist_exts,
+    ]
+    .iter()
+    .any(|list| list.iter().any(|e| e.trim().eq_ignore_ascii_case(&ext)));
+    if known {
+        return Ok(());
+    }
+
+    let mut head = [0u8; 12];
+    let read = std::fs::File::open(path)
+        .and_then(|mut f| std::io::Read::read(&mut f, &mut head))
+        .unwrap_or(0);
+    if looks_like_media(&head[..read]) {
+        log::debug!(
+            "Unlisted extension '{}' but {} looks like media",
+            ext,
+            target
+        );
+        return Ok(());
+    }
+
+    if config.strict_target_ext {
+        anyhow::bail!(
+            "Refusing to play {}: unrecognized extension '{}' and it doesn't look like media (strict_target_ext)",
+            target,
+            ext
+        );
+    }
+    log::warn!("Unrecognized extension '{}' for {}", ext, target);
+    eprintln!(
+        "⚠️  {}: unrecognized extension, attempting playback anyway",
+        target
+    );
+    Ok(())
+}
+
 // Scans a playlist file to find the "heaviest" URL inside
 fn inspect_playlist_content(path_str: &str, config: &Config) -> Option<String> {
     let path = std::path::Path::new(path_str);
@@ -235,7 +689,25 @@ fn inspect_playlist_content(path_str: &str, config: &Config) -> Option<String> {
     best_match
 }
 
-fn apply_url_optimizations(cmd: &mut Command, target: &str, config: &Config) {
+// yt-dlp's `acodec` is the codec's own name ("opus", "mp4a.40.2", "vorbis"),
+// without it (or a stream in that codec) any best audio will do
+fn audio_format(preferred: &str) -> String {
+    let acodec = match preferred.trim().to_ascii_lowercase().as_str() {
+        "" => return "bestaudio/best".to_string(),
+        "aac" | "m4a" => "mp4a".to_string(),
+        "ogg" => "vorbis".to_string(),
+        other => other.to_string(),
+    };
+    format!("bestaudio[acodec^={}]/bestaudio/best", acodec)
+}
+
+fn apply_url_optimizations(
+    cmd: &mut Command,
+    target: &str,
+    config: &Config,
+    extra_args: &[String],
+    video_once: bool,
+) {
     let weight = classify_target_weight(target);
     let is_youtube = weight == 2;
     let is_url = weight >= 1;
@@ -252,10 +724,19 @@ fn apply_url_optimizations(cmd: &mut Command, target: &str, config: &Config) {
 
         cmd.arg("--msg-level=ytdl_hook=info");
 
+        let user_format = config
+            .mpv_default_args
+            .iter()
+            .chain(extra_args)
+            .any(|a| a.starts_with("--ytdl-format"));
+
         if is_youtube {
-            if !config.video_ok && !config.watch {
-                log::debug!("YouTube detected & Audio Mode: forcing bestaudio format");
-                cmd.arg("--ytdl-format=bestaudio/best");
+            if user_format {
+                log::debug!("YouTube detected & format set by user args, leaving it alone");
+            } else if !video_once && !config.video_ok && !config.wants_video() {
+                let format = audio_format(&config.preferred_audio_codec);
+                log::debug!("YouTube detected & Audio Mode: forcing {}", format);
+                cmd.arg(format!("--ytdl-format={}", format));
             } else {
                 log::debug!("YouTube detected & Video/Watch Mode: allowing default formats");
             }
@@ -263,9 +744,23 @@ fn apply_url_optimizations(cmd: &mut Command, target: &str, config: &Config) {
 
         let mut ytdl_opts = String::new();
 
-        if target.contains("list=") {
-            log::debug!("Playlist detected in URL, forcing yes-playlist");
-            ytdl_opts.push_str("yes-playlist=,");
+        if let Some((opt, value)) = crate::config::ytdlp_cookie_options(config) {
+            // the raw options list is comma separated, a comma in the value would split it
+            if value.contains(',') {
+                log::warn!(
+                    "Ignoring {} '{}': commas aren't supported there",
+                    opt,
+                    value
+                );
+            } else {
+                log::debug!("Passing {} to yt-dlp", opt);
+                ytdl_opts.push_str(&format!("{}={},", opt, value));
+            }
+        }
+
+        for opt in resolver_opts(&config.url_resolvers, target) {
+            ytdl_opts.push_str(&opt);
+            ytdl_opts.push(',');
         }
 
         if is_youtube {
@@ -282,19 +777,16 @@ fn apply_url_optimizations(cmd: &mut Command, target: &str, config: &Config) {
                 ytdl_opts.push_str("remote-components=ejs:github,");
             }
 
-            if check_deno_availability() {
-                log::debug!("JS runtime check: Deno found (skipping fallbacks)");
-            } else if has_command("node") {
-                log::debug!("JS runtime check: node found");
-                ytdl_opts.push_str("js-runtimes=node,");
-            } else if has_command("qjs") || has_command("quickjs") {
-                log::debug!("JS runtime check: quickjs found");
-                ytdl_opts.push_str("js-runtimes=quickjs,");
-            } else if has_command("bun") {
-                log::debug!("JS runtime check: bun found");
-                ytdl_opts.push_str("js-runtimes=bun,");
-            } else {
-                log::warn!("No JS runtime found (Deno/Node). YouTube playback may fail with 403.");
+            match tool_cache::js_runtime(detect_js_runtime) {
+                JsRuntime::Missing => log::warn!(
+                    "No JS runtime found (Deno/Node). YouTube playback may fail with 403."
+                ),
+                runtime => {
+                    log::debug!("JS runtime: {:?}", runtime);
+                    if let Some(name) = runtime.ytdl_name() {
+                        ytdl_opts.push_str(&format!("js-runtimes={},", name));
+                    }
+                }
             }
         }
 
@@ -306,29 +798,134 @@ fn apply_url_optimizations(cmd: &mut Command, target: &str, config: &Config) {
     }
 }
 
-fn apply_common_args(cmd: &mut Command, config: &Config, extra_args: &[String]) {
+// "opt=value" for every url_resolvers entry whose pattern matches the URL
+fn resolver_opts(resolvers: &[UrlResolver], target: &str) -> Vec<String> {
+    let mut opts = Vec::new();
+    for resolver in resolvers {
+        // patterns were checked when the config loaded
+        let Ok(pattern) = Regex::new(&resolver.pattern) else {
+            continue;
+        };
+        if !pattern.is_match(target) {
+            continue;
+        }
+        log::debug!("url_resolvers: '{}' matches", resolver.pattern);
+        opts.extend(
+            resolver
+                .ytdl_opts
+                .iter()
+                .map(|(opt, value)| format!("{}={}", opt, value)),
+        );
+    }
+    opts
+}
+
+// only flags are let through, a bare word would turn into another file to play
+fn keep_flags<'a>(tokens: impl Iterator<Item = &'a str>) -> Vec<String> {
+    tokens
+        .map(str::trim)
+        .filter(|t| !t.is_empty() && !t.starts_with('#'))
+        .filter(|t| {
+            let ok = t.starts_with("--");
+            if !ok {
+                log::warn!("Ignoring per-track arg '{}' (not a --flag)", t);
+            }
+            ok
+        })
+        .map(String::from)
+        .collect()
+}
+
+/// Extra mpv args for one local file: `<file>.mpvargs` next to it (one arg per line),
+/// or else a `mpv-args: --flag ...` line in its comment tag.
+fn per_track_args(target: &str) -> Vec<String> {
+    let path = std::path::Path::new(target);
+    if !path.is_file() {
+        return Vec::new();
+    }
+
+    let mut sidecar = path.as_os_str().to_owned();
+    sidecar.push(".mpvargs");
+    if let Ok(content) = std::fs::read_to_string(&sidecar) {
+        log::debug!("Using sidecar args from {:?}", sidecar);
+        return keep_flags(content.lines());
+    }
+
+    let Ok(tagged) = Probe::open(path).and_then(|p| p.read()) else {
+        return Vec::new();
+    };
+    tagged
+        .tags()
+        .iter()
+        .flat_map(|tag| tag.get_strings(ItemKey::Comment))
+        .flat_map(str::lines)
+        .find_map(|line| line.trim().strip_prefix("mpv-args:"))
+        .map(|args| keep_flags(args.split_whitespace()))
+        .unwrap_or_default()
+}
+
+// `video_once` opens the window and decodes video for this playback only
+fn apply_common_args(cmd: &mut Command, config: &Config, extra_args: &[String], video_once: bool) {
     log::debug!("Applying common MPV arguments from config");
 
-    if config.watch {
-        log::debug!("Visual mode enabled (--watch)");
-        cmd.arg("--force-window=immediate");
-        cmd.arg("--video=auto");
+    let (window, video) = if video_once {
+        log::debug!("Video requested for this playback only");
+        (true, true)
     } else {
-        log::debug!("Audio-only mode (forcing video=no)");
-        cmd.arg("--force-window=no");
-        cmd.arg("--video=no");
-        cmd.arg("--audio-display=no");
+        (config.wants_window(), config.wants_video())
+    };
+    match (window, video) {
+        (true, true) => {
+            log::debug!("Visual mode enabled (window + video)");
+            cmd.arg("--force-window=immediate");
+            cmd.arg("--video=auto");
+        }
+        (true, false) => {
+            log::debug!("Window without video (OSD/visualizers only)");
+            cmd.arg("--force-window=immediate");
+            cmd.arg("--video=no");
+        }
+        (false, true) => {
+            log::debug!("Decoding video without a window (vo=null)");
+            cmd.arg("--force-window=no");
+            cmd.arg("--video=auto");
+            cmd.arg("--vo=null");
+            cmd.arg("--audio-display=no");
+        }
+        (false, false) => {
+            log::debug!("Audio-only mode (forcing video=no)");
+            cmd.arg("--force-window=no");
+            cmd.arg("--video=no");
+            cmd.arg("--audio-display=no");
+        }
     }
 
+    let status_override = crate::config::status_msg(config);
+    let quiet_cap = crate::config::quiet_hours_cap(config, local_minute());
+
     for arg in &config.mpv_default_args {
-        if config.watch
-            && (arg == "--no-video" || arg == "--video=no" || arg == "--audio-display=no")
+        if status_override.is_some()
+            && (arg.starts_with("--term-status-msg=") || arg == "--no-term-osd-bar")
+        {
+            log::debug!("Status line overridden in config, dropping '{}'", arg);
+            continue;
+        }
+        if (video && (arg == "--no-video" || arg == "--video=no"))
+            || (window && arg == "--audio-display=no")
         {
-            log::debug!("Skipping '{}' because visual mode is active", arg);
+            log::debug!(
+                "Skipping '{}' because it fights the window/video setting",
+                arg
+            );
             continue;
         }
 
         if arg.contains("--term-playing-msg=") {
+            if !config.show_banner {
+                log::debug!("Banner disabled in config, dropping '{}'", arg);
+                continue;
+            }
+
             let parts: Vec<&str> = arg.splitn(2, '=').collect();
             if parts.len() == 2 {
                 let banner_text = parts[1];
@@ -336,6 +933,9 @@ fn apply_common_args(cmd: &mut Command, config: &Config, extra_args: &[String])
                 if is_debug {
                     log::debug!("Skipping screen clear to preserve logs");
                     cmd.arg(format!("--term-playing-msg=\n{}\n", banner_text.trim()));
+                } else if !config.clear_screen_on_play {
+                    log::debug!("Screen clear disabled in config, keeping scrollback");
+                    cmd.arg(format!("--term-playing-msg=\n{}\n", banner_text.trim()));
                 } else {
                     log::debug!("Injecting ANSI clear codes into banner");
                     cmd.arg(format!(
@@ -346,15 +946,53 @@ fn apply_common_args(cmd: &mut Command, config: &Config, extra_args: &[String])
                 continue; // skip the default cmd.arg(arg) below
             }
         }
-        cmd.arg(arg);
+        cmd.arg(quiet_volume_arg(arg, quiet_cap).as_ref());
+    }
+
+    if let Some((msg, osd_bar)) = status_override {
+        cmd.arg(format!("--term-status-msg={}", msg));
+        cmd.arg(if osd_bar {
+            "--term-osd-bar"
+        } else {
+            "--no-term-osd-bar"
+        });
     }
 
-    log::debug!("Setting volume: {}", config.volume);
-    cmd.arg(format!("--volume={}", config.volume));
+    let volume = match quiet_cap {
+        Some(cap) if config.volume > cap => {
+            log::info!("Quiet hours: volume {} capped at {}", config.volume, cap);
+            cap
+        }
+        _ => config.volume,
+    };
+    log::debug!("Setting volume: {}", volume);
+    cmd.arg(format!("--volume={}", volume));
+
+    if config.force_mono {
+        let user_args = config.mpv_default_args.iter().chain(extra_args);
+        if let Some(conflict) = find_channel_conflict(user_args) {
+            log::warn!(
+                "Mono downmix skipped: '{}' already controls the channel layout",
+                conflict
+            );
+        } else {
+            log::debug!("Mono downmix enabled");
+            cmd.arg("--audio-channels=mono");
+        }
+    }
 
-    if config.shuffle {
-        log::debug!("Shuffle enabled");
-        cmd.arg("--shuffle");
+    if config
+        .mpv_default_args
+        .iter()
+        .chain(extra_args)
+        .any(|a| a.starts_with("--idle"))
+    {
+        log::debug!("Idle behaviour set by user args, leaving it alone");
+    } else if config.idle_quit {
+        cmd.arg("--idle=no");
+    } else {
+        log::debug!("Keeping mpv idle after the queue ends");
+        cmd.arg("--idle=yes");
     }
 
     log::debug!("Setting loop mode: {}", config.loop_mode);
@@ -380,11 +1018,53 @@ fn apply_common_args(cmd: &mut Command, config: &Config, extra_args: &[String])
     if !extra_args.is_empty() {
         log::debug!("Injecting manual CLI overrides: {:?}", extra_args);
         for arg in extra_args {
-            cmd.arg(arg);
+            cmd.arg(quiet_volume_arg(arg, quiet_cap).as_ref());
         }
     }
 }
 
+// a --volume=N of the user's own, held to the quiet_hours cap like ours is
+fn quiet_volume_arg(arg: &str, cap: Option<u8>) -> Cow<'_, str> {
+    let volume = arg
+        .strip_prefix("--volume=")
+        .and_then(|v| v.trim().parse::<f64>().ok());
+    match volume.zip(cap) {
+        Some((volume, cap)) if volume > f64::from(cap) => {
+            log::info!("Quiet hours: '{}' capped at {}", arg, cap);
+            Cow::Owned(format!("--volume={}", cap))
+        }
+        _ => Cow::Borrowed(arg),
+    }
+}
+
+// minutes into the local day, for quiet_hours
+fn local_minute() -> u16 {
+    use chrono::Timelike;
+    let now = chrono::Local::now();
+    (now.hour() * 60 + now.minute()) as u16
+}
+
+// user-set args that already decide the channel layout, mono must not fight them
+fn find_channel_conflict<'a>(args: impl IntoIterator<Item = &'a String>) -> Option<&'a str> {
+    args.into_iter()
+        .map(|a| a.as_str())
+        .find(|a| a.starts_with("--audio-channels") || (a.starts_with("--af") && a.contains("pan")))
+}
+
+pub fn detect_js_runtime() -> JsRuntime {
+    if check_deno_availability() {
+        JsRuntime::Deno
+    } else if has_command("node") {
+        JsRuntime::Node
+    } else if has_command("qjs") || has_command("quickjs") {
+        JsRuntime::QuickJs
+    } else if has_command("bun") {
+        JsRuntime::Bun
+    } else {
+        JsRuntime::Missing
+    }
+}
+
 fn check_deno_availability() -> bool {
     let check_cmd = if cfg!(windows) { "where" } else { "which" };
     let Ok(output) = Command::new(check_cmd).arg("yt-dlp").output() else {
@@ -449,24 +1129,199 @@ pub fn play_radio(name: &str, url: &str, config: &Config, extra_args: &[String])
     play(url, config, extra_args)
 }
 
-fn handle_radio_sync(cmd: &mut Command, target: &str) -> Option<String> {
+// Hands mpv an IPC socket when something needs to talk to it (radio metadata, play history)
+fn attach_ipc(cmd: &mut Command, target: &str, config: &Config, force: bool) -> Option<String> {
     let is_radio = crate::radio::RADIO_STATIONS
         .iter()
         .any(|(_, url, _)| *url == target);
 
-    if !is_radio {
+    if !force
+        && !is_radio
+        && !config.record_history
+        && config.idle_timeout == 0
+        && config.http_status_port == 0
+        && !term_title::enabled(config)
+        && !radio_continues(config)
+    {
         return None;
     }
 
-    let pid = std::process::id();
-    let ipc_socket = if cfg!(windows) {
-        format!(r"\\.\pipe\mpv-music-ipc-{}", pid)
-    } else {
-        format!("/tmp/mpv-music-ipc-{}.sock", pid)
-    };
-
+    let ipc_socket = ipc::socket_path();
     cmd.arg(format!("--input-ipc-server={}", ipc_socket));
 
+    if is_radio {
+        handle_radio_sync(cmd, target, &ipc_socket);
+    } else if config.record_history {
+        start_history_observer(
+            ipc_socket.clone(),
+            config.scrobble_threshold_percent,
+            config.scrobble_threshold_secs,
+        );
+    }
+
+    if term_title::enabled(config) {
+        start_title_observer(ipc_socket.clone());
+    }
+
+    if config.idle_timeout > 0 {
+        start_idle_watchdog(ipc_socket.clone(), config.idle_timeout);
+    }
+
+    if !is_radio && radio_continues(config) {
+        start_radio_continuation(ipc_socket.clone(), config.clone());
+    }
+
+    if config.http_status_port > 0 {
+        #[cfg(feature = "http-status")]
+        crate::http_status::attach(&ipc_socket, config);
+        #[cfg(not(feature = "http-status"))]
+        log::warn!("http_status_port is set, but this build lacks the 'http-status' feature");
+    }
+
+    Some(ipc_socket)
+}
+
+// every idle-active flip bumps the generation, a pending quit only fires if
+// nothing happened while it slept
+fn start_idle_watchdog(ipc_socket: String, timeout: u64) {
+    let generation = Arc::new(AtomicU64::new(0));
+    let socket = ipc_socket.clone();
+
+    ipc::observe(ipc_socket, &["idle-active"], move |_, data| {
+        let current = generation.fetch_add(1, Ordering::SeqCst) + 1;
+        if data.as_bool() != Some(true) {
+            return;
+        }
+
+        log::debug!(
+            "mpv went idle, quitting in {}s unless playback resumes",
+            timeout
+        );
+        let generation = Arc::clone(&generation);
+        let socket = socket.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_secs(timeout));
+            if generation.load(Ordering::SeqCst) != current {
+                return;
+            }
+            log::info!("mpv idle for {}s, sending quit", timeout);
+            if let Err(e) = ipc::send_command(&socket, &["quit"]) {
+                log::warn!("Idle timeout could not quit mpv: {}", e);
+            }
+        });
+    });
+}
+
+// mpv key that switches radio_after_queue off (and back on) for this player
+const RADIO_TOGGLE_KEY: &str = "Ctrl+r";
+
+// a looping queue never runs out, there's nothing to continue
+fn radio_continues(config: &Config) -> bool {
+    config.radio_after_queue
+        && !matches!(
+            config.loop_mode.as_str(),
+            "playlist" | "inf" | "track" | "file"
+        )
+}
+
+// extends the queue once its last entry starts, so mpv rolls straight into
+// the radio batch instead of stopping. Each batch ends in a new last entry,
+// which extends it again
+fn start_radio_continuation(ipc_socket: String, config: Config) {
+    let socket = ipc_socket.clone();
+    let mut enabled = true;
+    let mut bound = false;
+    let (mut pos, mut count) = (-1, 0);
+    let mut extended_at = None;
+
+    ipc::watch(
+        ipc_socket,
+        &["playlist-pos", "playlist-count"],
+        move |msg| {
+            match msg["event"].as_str() {
+                Some("client-message") => {
+                    if msg["args"] == serde_json::json!(["mpv-music", "toggle-radio-after"]) {
+                        enabled = !enabled;
+                        log::info!("radio_after_queue toggled for this session: {}", enabled);
+                        let state = if enabled { "on" } else { "off" };
+                        let _ = ipc::send_command(
+                            &socket,
+                            &["show-text", &format!("Radio after queue: {}", state)],
+                        );
+                    }
+                    return;
+                }
+                Some("property-change") => match msg["name"].as_str() {
+                    Some("playlist-pos") => pos = msg["data"].as_i64().unwrap_or(-1),
+                    Some("playlist-count") => count = msg["data"].as_i64().unwrap_or(0),
+                    _ => return,
+                },
+                _ => return,
+            }
+
+            if !bound {
+                bound = true;
+                let toggle = "script-message mpv-music toggle-radio-after";
+                if let Err(e) = ipc::request(&socket, &["keybind", RADIO_TOGGLE_KEY, toggle]) {
+                    log::warn!(
+                        "Couldn't bind {} to toggle the radio: {}",
+                        RADIO_TOGGLE_KEY,
+                        e
+                    );
+                }
+            }
+
+            if !enabled || pos < 0 || pos + 1 != count || extended_at == Some(count) {
+                return;
+            }
+            extended_at = Some(count);
+            match crate::remote::queue_radio(&socket, &config) {
+                Ok(crate::remote::Radio::Added(seed, added)) => log::info!(
+                    "Queue ending, added {} radio tracks like {} - {}",
+                    added,
+                    seed.artist,
+                    seed.title
+                ),
+                Ok(_) => log::info!("Queue ending, nothing to continue it with"),
+                Err(e) => log::warn!("Couldn't continue the queue with radio: {:#}", e),
+            }
+        },
+    );
+}
+
+fn start_title_observer(ipc_socket: String) {
+    let mut now_playing = term_title::NowPlaying::default();
+    ipc::observe(
+        ipc_socket,
+        &["path", "metadata", "media-title"],
+        move |name, data| {
+            if let Some(title) = now_playing.update(name, data) {
+                log::debug!("Terminal title: {}", title);
+                term_title::set(&title);
+            }
+        },
+    );
+}
+
+// a track goes into the history once it played past the scrobble threshold
+fn start_history_observer(ipc_socket: String, percent: u8, secs: u32) {
+    let mut listen = crate::history::Listen::new(percent, secs);
+    ipc::observe(
+        ipc_socket,
+        &["path", "duration", "time-pos"],
+        move |name, data| {
+            // streams and URLs have nothing in the index to avoid or rank later
+            if let Some(path) = listen.update(name, data)
+                && std::path::Path::new(&path).is_file()
+                && let Err(e) = crate::history::record(&path)
+            {
+                log::warn!("Failed to record history for '{}': {}", path, e);
+            }
+        },
+    );
+}
+
+fn handle_radio_sync(cmd: &mut Command, target: &str, ipc_socket: &str) {
     let (station_name, is_listen_moe) = crate::radio::RADIO_STATIONS
         .iter()
         .find(|(_, url, _)| *url == target)
@@ -500,14 +1355,25 @@ fn handle_radio_sync(cmd: &mut Command, target: &str) -> Option<String> {
             });
         });
     }
-
-    Some(ipc_socket)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_audio_format_prefers_codec() {
+        assert_eq!(audio_format(""), "bestaudio/best");
+        assert_eq!(
+            audio_format("Opus"),
+            "bestaudio[acodec^=opus]/bestaudio/best"
+        );
+        assert_eq!(
+            audio_format("aac"),
+            "bestaudio[acodec^=mp4a]/bestaudio/best"
+        );
+    }
+
     #[test]
     fn test_classify_youtube() {
         assert_eq!(
@@ -548,6 +1414,159 @@ mod tests {
         assert!(http > local);
     }
 
+    #[test]
+    fn test_channel_conflict_detection() {
+        let args = vec![
+            "--no-video".to_string(),
+            "--af=lavfi=[pan=mono|c0=FL]".to_string(),
+        ];
+        assert_eq!(
+            find_channel_conflict(&args),
+            Some("--af=lavfi=[pan=mono|c0=FL]")
+        );
+
+        let args = vec!["--audio-channels=stereo".to_string()];
+        assert!(find_channel_conflict(&args).is_some());
+
+        let args = vec!["--af=loudnorm".to_string(), "--volume=50".to_string()];
+        assert!(find_channel_conflict(&args).is_none());
+    }
+
+    #[test]
+    fn test_media_sniffing() {
+        assert!(looks_like_media(b"ID3\x04\x00"));
+        assert!(looks_like_media(&[0x1A, 0x45, 0xDF, 0xA3, 0x01]));
+        assert!(looks_like_media(b"\x00\x00\x00\x20ftypM4A "));
+        assert!(looks_like_media(&[0xFF, 0xFB, 0x90]));
+        assert!(!looks_like_media(b"#!/bin/sh\n"));
+        assert!(!looks_like_media(b"%PDF-1.7"));
+        assert!(!looks_like_media(b""));
+    }
+
+    #[test]
+    fn test_sidecar_args_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        let track = dir.join("quad.flac");
+        std::fs::write(&track, b"not really flac").unwrap();
+        std::fs::write(
+            dir.join("quad.flac.mpvargs"),
+            "# quad recording\n--audio-channels=quad\nrm-rf\n\n--af=lavfi=[pan=stereo|c0=FL|c1=FR]\n",
+        )
+        .unwrap();
+
+        let args = per_track_args(&track.to_string_lossy());
+
+        assert_eq!(
+            args,
+            vec![
+                "--audio-channels=quad",
+                "--af=lavfi=[pan=stereo|c0=FL|c1=FR]"
+            ]
+        );
+    }
+
+    fn idle_args(config: &Config, extra_args: &[String]) -> Vec<String> {
+        let mut cmd = Command::new("mpv");
+        apply_common_args(&mut cmd, config, extra_args, false);
+        cmd.get_args()
+            .map(|a| a.to_string_lossy().to_string())
+            .filter(|a| a.starts_with("--idle"))
+            .collect()
+    }
+
+    #[test]
+    fn test_radio_continuation_skips_looping_queues() {
+        let mut config = Config {
+            radio_after_queue: true,
+            ..Default::default()
+        };
+        config.loop_mode = "no".to_string();
+        assert!(radio_continues(&config));
+        config.loop_mode = "3".to_string();
+        assert!(radio_continues(&config));
+        config.loop_mode = "playlist".to_string();
+        assert!(!radio_continues(&config));
+        config.radio_after_queue = false;
+        config.loop_mode = "no".to_string();
+        assert!(!radio_continues(&config));
+    }
+
+    #[test]
+    fn test_idle_flag_follows_config() {
+        let mut config = Config::default();
+        assert_eq!(idle_args(&config, &[]), vec!["--idle=no"]);
+
+        config.idle_quit = false;
+        assert_eq!(idle_args(&config, &[]), vec!["--idle=yes"]);
+
+        // user's own --idle wins
+        let extra = vec!["--idle=once".to_string()];
+        assert_eq!(idle_args(&config, &extra), vec!["--idle=once"]);
+    }
+
+    fn display_args(show_window: bool, decode_video: bool) -> Vec<String> {
+        let config = Config {
+            show_window: Some(show_window),
+            decode_video: Some(decode_video),
+            ..Config::default()
+        };
+        let mut cmd = Command::new("mpv");
+        apply_common_args(&mut cmd, &config, &[], false);
+        cmd.get_args()
+            .map(|a| a.to_string_lossy().to_string())
+            .filter(|a| {
+                a.starts_with("--force-window")
+                    || a.starts_with("--video")
+                    || a.starts_with("--no-video")
+                    || a.starts_with("--vo=")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_window_and_video_are_independent() {
+        assert_eq!(
+            display_args(true, true),
+            ["--force-window=immediate", "--video=auto"]
+        );
+        assert_eq!(
+            display_args(true, false),
+            ["--force-window=immediate", "--video=no", "--no-video"]
+        );
+        // default mpv_default_args carry --no-video, which must not undo decoding
+        assert_eq!(
+            display_args(false, true),
+            ["--force-window=no", "--video=auto", "--vo=null"]
+        );
+        assert_eq!(
+            display_args(false, false),
+            ["--force-window=no", "--video=no", "--no-video"]
+        );
+
+        // unset follows --watch, like before
+        let watch = Config {
+            watch: true,
+            ..Config::default()
+        };
+        assert!(watch.wants_window() && watch.wants_video());
+    }
+
+    #[test]
+    fn test_video_once_leaves_config_alone() {
+        let config = Config::default();
+        let mut cmd = Command::new("mpv");
+        apply_common_args(&mut cmd, &config, &[], true);
+        let args: Vec<String> = cmd
+            .get_args()
+            .map(|a| a.to_string_lossy().to_string())
+            .collect();
+        assert!(args.contains(&"--force-window=immediate".to_string()));
+        assert!(args.contains(&"--video=auto".to_string()));
+        assert!(!args.contains(&"--no-video".to_string()));
+        assert!(!config.wants_video() && !config.video_ok);
+    }
+
     #[test]
     fn test_has_command_invalid() {
         // These commands should NOT exist
@@ -556,4 +1575,61 @@ mod tests {
 
     // Note: We can't reliably test has_command for real commands
     // because they might not be installed in CI environment
+
+    #[test]
+    fn test_quiet_hours_cap_user_volume_args() {
+        assert_eq!(quiet_volume_arg("--volume=100", Some(30)), "--volume=30");
+        assert_eq!(quiet_volume_arg("--volume=20", Some(30)), "--volume=20");
+        assert_eq!(quiet_volume_arg("--volume=100", None), "--volume=100");
+        assert_eq!(
+            quiet_volume_arg("--volume-max=150", Some(30)),
+            "--volume-max=150"
+        );
+    }
+
+    #[test]
+    fn test_volume_override_needs_a_shared_tag() {
+        let track = |artist: &str, genre: &str| Track {
+            artist: artist.to_string(),
+            genre: genre.to_string(),
+            ..Default::default()
+        };
+        let overrides = HashMap::from([("Ambient".to_string(), 60), ("Eno".to_string(), 50)]);
+
+        let (a, b) = (track("Stars", "ambient"), track("Hammock", "Ambient"));
+        assert_eq!(override_for(&[&a, &b], &overrides), Some(60));
+
+        // mixed genres are ambiguous, no override
+        let c = track("Hammock", "Post-Rock");
+        assert_eq!(override_for(&[&a, &c], &overrides), None);
+
+        // artist is more specific than genre
+        let e = track("Eno", "Ambient");
+        assert_eq!(override_for(&[&e], &overrides), Some(50));
+    }
+
+    #[test]
+    fn test_url_resolvers_match_by_pattern() {
+        let mut resolvers = Config::default().url_resolvers;
+        resolvers.push(UrlResolver {
+            pattern: r"^https?://(www\.)?niche\.example/".to_string(),
+            ytdl_opts: [
+                ("referer", "https://niche.example/"),
+                ("no-check-certificates", ""),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+        });
+
+        assert_eq!(
+            resolver_opts(&resolvers, "https://youtube.com/watch?v=x&list=PL1"),
+            ["yes-playlist="]
+        );
+        assert_eq!(
+            resolver_opts(&resolvers, "https://niche.example/track/1"),
+            ["no-check-certificates=", "referer=https://niche.example/"]
+        );
+        assert!(resolver_opts(&resolvers, "https://other.example/niche.example/").is_empty());
+    }
 }
@@ -0,0 +1,543 @@
+use anyhow::{Context, Result};
+use quick_xml::Reader;
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::{BytesStart, Event};
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, PartialEq)]
+enum XmlFormat {
+    Xspf,
+    Asx,
+}
+
+#[derive(Default, Debug)]
+pub struct Parsed {
+    pub title: Option<String>,
+    pub entries: Vec<String>,
+}
+
+fn xml_format(path: &Path) -> Option<XmlFormat> {
+    let ext = path.extension()?.to_str()?.to_lowercase();
+    match ext.as_str() {
+        "xspf" => Some(XmlFormat::Xspf),
+        "asx" => Some(XmlFormat::Asx),
+        _ => None,
+    }
+}
+
+/// mpv can't read XSPF/ASX on its own, these get expanded into a plain queue first.
+pub fn needs_expansion(target: &str) -> bool {
+    xml_format(Path::new(target)).is_some()
+}
+
+/// Reads the raw (unresolved) entries of a playlist file.
+pub fn read(path: &Path) -> Result<Parsed> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Could not read playlist {:?}", path))?;
+
+    match xml_format(path) {
+        Some(format) => parse_xml(&content, format),
+        None => Ok(parse_lines(&content)),
+    }
+}
+
+// entries counted for a playlist's preview, past this it just says "5000+"
+pub const PREVIEW_COUNT_CAP: usize = 5000;
+
+/// What the Playlist Mode preview shows: the entry count (`capped` once it
+/// passed `PREVIEW_COUNT_CAP`) and the first few resolved entries.
+#[derive(Debug, Default)]
+pub struct Preview {
+    pub count: usize,
+    pub capped: bool,
+    pub samples: Vec<String>,
+}
+
+/// Streams a line based playlist instead of reading it whole, only the sampled
+/// entries get resolved. XSPF/ASX and playlists listing other playlists are
+/// expanded like for playback.
+pub fn preview(path: &Path, samples: usize) -> Result<Preview> {
+    if xml_format(path).is_some() {
+        return Ok(preview_of(entries(path)?, samples));
+    }
+
+    let file =
+        std::fs::File::open(path).with_context(|| format!("Could not read playlist {:?}", path))?;
+    let playlist_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    let mut preview = Preview::default();
+    for line in BufReader::new(file).lines() {
+        let line = line.with_context(|| format!("Could not read playlist {:?}", path))?;
+        let raw = line.trim();
+        if raw.starts_with('#') || raw.is_empty() {
+            continue;
+        }
+        if is_nested_playlist(raw) {
+            return Ok(preview_of(entries(path)?, samples));
+        }
+        if preview.count == PREVIEW_COUNT_CAP {
+            preview.capped = true;
+            break;
+        }
+        preview.count += 1;
+        if preview.samples.len() < samples
+            && let Some(entry) = resolve_entry(raw, playlist_dir)
+        {
+            preview.samples.push(entry);
+        }
+    }
+    Ok(preview)
+}
+
+fn preview_of(entries: Vec<String>, samples: usize) -> Preview {
+    Preview {
+        count: entries.len().min(PREVIEW_COUNT_CAP),
+        capped: entries.len() > PREVIEW_COUNT_CAP,
+        samples: entries.into_iter().take(samples).collect(),
+    }
+}
+
+// playlists pointing at playlists pointing at... deeper than this is a mistake
+const MAX_DEPTH: usize = 8;
+
+// formats we parse ourselves, so entries naming one can be flattened in place
+fn is_nested_playlist(entry: &str) -> bool {
+    let Some(ext) = Path::new(entry).extension().and_then(|e| e.to_str()) else {
+        return false;
+    };
+    !entry.contains("://") && matches!(ext.to_lowercase().as_str(), "m3u" | "m3u8" | "xspf" | "asx")
+}
+
+/// Whether a line based playlist lists other playlists. mpv would pass those on
+/// unresolved, so such a playlist gets expanded like XSPF/ASX.
+pub fn has_nested(target: &str) -> bool {
+    let path = Path::new(target);
+    if xml_format(path).is_some() || !is_nested_playlist(target) {
+        return false;
+    }
+    let playlist_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    read(path).is_ok_and(|parsed| {
+        parsed
+            .entries
+            .iter()
+            .filter_map(|raw| resolve_entry(raw, playlist_dir))
+            .any(|entry| is_nested_playlist(&entry))
+    })
+}
+
+/// Reads a playlist and resolves every entry to a playable path or URL.
+/// Relative paths resolve against the playlist's own directory, nested playlists
+/// are flattened in place, up to `MAX_DEPTH` levels. One listed twice plays
+/// twice, one that lists itself (further down or directly) is cut off there.
+pub fn entries(path: &Path) -> Result<Vec<String>> {
+    let mut open = HashSet::new();
+    let mut out = Vec::new();
+    expand(path, 0, &mut open, &mut out)?;
+    Ok(out)
+}
+
+// `open` holds the playlists being expanded right now, the ones above this one
+fn expand(
+    path: &Path,
+    depth: usize,
+    open: &mut HashSet<PathBuf>,
+    out: &mut Vec<String>,
+) -> Result<()> {
+    let key = dunce::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    if open.contains(&key) {
+        log::warn!("Playlist {:?} includes itself, skipping the loop", path);
+        return Ok(());
+    }
+    open.insert(key.clone());
+    let result = expand_entries(path, depth, open, out);
+    open.remove(&key);
+    result
+}
+
+fn expand_entries(
+    path: &Path,
+    depth: usize,
+    open: &mut HashSet<PathBuf>,
+    out: &mut Vec<String>,
+) -> Result<()> {
+    let playlist_dir = path.parent().unwrap_or_else(|| Path::new("."));
+    for raw in read(path)?.entries {
+        let Some(entry) = resolve_entry(&raw, playlist_dir) else {
+            continue;
+        };
+        if !is_nested_playlist(&entry) {
+            out.push(entry);
+            continue;
+        }
+
+        if depth + 1 > MAX_DEPTH {
+            log::warn!(
+                "Not expanding {} from {:?}: playlists nested deeper than {} levels",
+                entry,
+                path,
+                MAX_DEPTH
+            );
+            continue;
+        }
+        // a broken nested playlist loses its own entries, not the whole queue
+        if let Err(e) = expand(Path::new(&entry), depth + 1, open, out) {
+            log::warn!("Skipping nested playlist: {:#}", e);
+        }
+    }
+    Ok(())
+}
+
+// m3u / m3u8 / anything line based
+fn parse_lines(content: &str) -> Parsed {
+    Parsed {
+        title: None,
+        entries: content
+            .lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.starts_with('#') && !l.is_empty())
+            .map(String::from)
+            .collect(),
+    }
+}
+
+fn lower_name(e: &BytesStart) -> String {
+    String::from_utf8_lossy(e.local_name().as_ref()).to_lowercase()
+}
+
+// <Ref href="..."/> inside an ASX <Entry>
+fn asx_href(e: &BytesStart, stack: &[String]) -> Option<String> {
+    if stack.last().is_none_or(|p| p != "entry") {
+        return None;
+    }
+    e.attributes().flatten().find_map(|attr| {
+        let key = String::from_utf8_lossy(attr.key.local_name().as_ref()).to_lowercase();
+        if key == "href" {
+            attr.unescape_value().ok().map(|v| v.trim().to_string())
+        } else {
+            None
+        }
+    })
+}
+
+fn parse_xml(content: &str, format: XmlFormat) -> Result<Parsed> {
+    let mut reader = Reader::from_str(content);
+    let mut parsed = Parsed::default();
+    let mut stack: Vec<String> = Vec::new();
+    let mut text = String::new();
+
+    let root = match format {
+        XmlFormat::Xspf => "playlist",
+        XmlFormat::Asx => "asx",
+    };
+
+    loop {
+        match reader.read_event().context("Malformed playlist XML")? {
+            Event::Start(e) => {
+                let name = lower_name(&e);
+                if format == XmlFormat::Asx
+                    && name == "ref"
+                    && let Some(href) = asx_href(&e, &stack)
+                {
+                    parsed.entries.push(href);
+                }
+                stack.push(name);
+                text.clear();
+            }
+            Event::Empty(e) => {
+                if format == XmlFormat::Asx
+                    && lower_name(&e) == "ref"
+                    && let Some(href) = asx_href(&e, &stack)
+                {
+                    parsed.entries.push(href);
+                }
+            }
+            Event::Text(t) => text.push_str(&t.decode()?),
+            Event::CData(t) => text.push_str(&t.decode()?),
+            Event::GeneralRef(r) => {
+                if let Some(c) = r.resolve_char_ref()? {
+                    text.push(c);
+                } else if let Some(s) = resolve_predefined_entity(&r.decode()?) {
+                    text.push_str(s);
+                }
+            }
+            Event::End(_) => {
+                let name = stack.pop().unwrap_or_default();
+                let parent = stack.last().map(String::as_str).unwrap_or_default();
+                let value = text.trim();
+
+                if format == XmlFormat::Xspf
+                    && name == "location"
+                    && parent == "track"
+                    && !value.is_empty()
+                {
+                    parsed.entries.push(value.to_string());
+                } else if name == "title" && parent == root && !value.is_empty() {
+                    parsed.title = Some(value.to_string());
+                }
+                text.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(parsed)
+}
+
+fn percent_decode(s: &str) -> String {
+    let hex = |b: u8| (b as char).to_digit(16).map(|d| d as u8);
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && i + 2 < bytes.len()
+            && let (Some(hi), Some(lo)) = (hex(bytes[i + 1]), hex(bytes[i + 2]))
+        {
+            out.push(hi << 4 | lo);
+            i += 3;
+            continue;
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+// file:///home/me/a%20b.mp3 -> /home/me/a b.mp3 (and file:///C:/x -> C:/x)
+fn file_uri_to_path(uri: &str) -> PathBuf {
+    let rest = uri.strip_prefix("file://").unwrap_or(uri);
+    let rest = rest.strip_prefix("localhost").unwrap_or(rest);
+    let decoded = percent_decode(rest);
+
+    let bytes = decoded.as_bytes();
+    if bytes.len() >= 3 && bytes[0] == b'/' && bytes[2] == b':' && bytes[1].is_ascii_alphabetic() {
+        return PathBuf::from(&decoded[1..]);
+    }
+    PathBuf::from(decoded)
+}
+
+/// Resolves a single playlist entry. URLs pass through, `file://` URIs are
+/// decoded, relative paths are joined onto `playlist_dir`. Missing files yield `None`.
+pub fn resolve_entry(raw: &str, playlist_dir: &Path) -> Option<String> {
+    let raw = raw.trim();
+
+    let path = if raw.starts_with("file://") {
+        file_uri_to_path(raw)
+    } else if raw.contains("://") {
+        return Some(raw.to_string());
+    } else {
+        PathBuf::from(raw)
+    };
+
+    if path.is_absolute() {
+        if path.exists() {
+            Some(path.to_string_lossy().to_string())
+        } else {
+            log::debug!("Skipping non-existent path in playlist: {}", raw);
+            None
+        }
+    } else {
+        match dunce::canonicalize(playlist_dir.join(&path)) {
+            Ok(canonical) => Some(canonical.to_string_lossy().to_string()),
+            Err(_) => {
+                log::debug!("Could not resolve relative path in playlist: {}", raw);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_xspf() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<playlist version="1" xmlns="http://xspf.org/ns/0/">
+  <title>Road &amp; Trip</title>
+  <trackList>
+    <track>
+      <title>Ignored Track Title</title>
+      <location>file:///music/a%20b.mp3</location>
+    </track>
+    <track>
+      <location>https://example.com/stream?x=1&amp;y=2</location>
+    </track>
+  </trackList>
+</playlist>"#;
+
+        let parsed = parse_xml(xml, XmlFormat::Xspf).unwrap();
+        assert_eq!(parsed.title.as_deref(), Some("Road & Trip"));
+        assert_eq!(
+            parsed.entries,
+            vec![
+                "file:///music/a%20b.mp3",
+                "https://example.com/stream?x=1&y=2"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_asx_case_insensitive() {
+        let xml = r#"<ASX version="3.0">
+  <TITLE>Station Mix</TITLE>
+  <ENTRY>
+    <TITLE>First</TITLE>
+    <REF HREF="http://radio.example.com/live" />
+  </ENTRY>
+  <Entry><Ref href="songs/local.mp3"/></Entry>
+</ASX>"#;
+
+        let parsed = parse_xml(xml, XmlFormat::Asx).unwrap();
+        assert_eq!(parsed.title.as_deref(), Some("Station Mix"));
+        assert_eq!(
+            parsed.entries,
+            vec!["http://radio.example.com/live", "songs/local.mp3"]
+        );
+    }
+
+    #[test]
+    fn test_parse_malformed_xml_errors() {
+        let xml = "<playlist><trackList><track><location>a</track></playlist>";
+        assert!(parse_xml(xml, XmlFormat::Xspf).is_err());
+    }
+
+    #[test]
+    fn test_file_uri_decoding() {
+        assert_eq!(
+            file_uri_to_path("file:///home/me/a%20b.mp3"),
+            PathBuf::from("/home/me/a b.mp3")
+        );
+        assert_eq!(
+            file_uri_to_path("file:///C:/Music/x.mp3"),
+            PathBuf::from("C:/Music/x.mp3")
+        );
+    }
+
+    #[test]
+    fn test_resolve_entry_url_passthrough() {
+        let dir = Path::new("/nonexistent");
+        assert_eq!(
+            resolve_entry("mms://example.com/live", dir).as_deref(),
+            Some("mms://example.com/live")
+        );
+        assert!(resolve_entry("/definitely/not/here.mp3", dir).is_none());
+    }
+
+    #[test]
+    fn test_preview_stops_at_the_cap() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::write(dir.join("a.mp3"), b"").unwrap();
+        let mut big = String::from("#EXTM3U\na.mp3\n");
+        for i in 0..PREVIEW_COUNT_CAP + 10 {
+            big.push_str(&format!(
+                "#EXTINF:1,Song {}\nhttps://example.com/{}.mp3\n",
+                i, i
+            ));
+        }
+        std::fs::write(dir.join("big.m3u8"), big).unwrap();
+        std::fs::write(dir.join("small.m3u"), "a.mp3\n\nmissing.mp3\n").unwrap();
+
+        let big = preview(&dir.join("big.m3u8"), 3).unwrap();
+        let small = preview(&dir.join("small.m3u"), 3).unwrap();
+
+        assert!(big.capped);
+        assert_eq!(big.count, PREVIEW_COUNT_CAP);
+        assert_eq!(big.samples.len(), 3);
+        assert!(big.samples[0].ends_with("a.mp3"));
+        assert_eq!(big.samples[1], "https://example.com/0.mp3");
+
+        assert!(!small.capped);
+        assert_eq!(small.count, 2);
+        assert_eq!(small.samples.len(), 1);
+    }
+
+    #[test]
+    fn test_nested_playlists_flatten() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::create_dir_all(dir.join("sub")).unwrap();
+        for song in ["a.mp3", "sub/b.mp3", "sub/c.mp3"] {
+            std::fs::write(dir.join(song), b"").unwrap();
+        }
+        // top -> sub/inner (relative to sub/) -> back to top, which must not loop
+        std::fs::write(
+            dir.join("top.m3u"),
+            "a.mp3
+sub/inner.m3u8
+",
+        )
+        .unwrap();
+        std::fs::write(
+            dir.join("sub/inner.m3u8"),
+            "#EXTM3U
+b.mp3
+../top.m3u
+c.mp3
+",
+        )
+        .unwrap();
+
+        let top = dir.join("top.m3u");
+        let nested = has_nested(&top.to_string_lossy());
+        let flat = entries(&top);
+        let canonical = dunce::canonicalize(dir).unwrap();
+
+        assert!(nested);
+        let expected: Vec<String> = ["a.mp3", "sub/b.mp3", "sub/c.mp3"]
+            .iter()
+            .map(|s| canonical.join(s).to_string_lossy().to_string())
+            .collect();
+        assert_eq!(flat.unwrap(), expected);
+    }
+
+    #[test]
+    fn test_diamond_plays_both_branches() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::write(dir.join("x.mp3"), b"").unwrap();
+        std::fs::write(dir.join("shared.m3u"), "x.mp3\n").unwrap();
+        std::fs::write(dir.join("left.m3u"), "shared.m3u\n").unwrap();
+        std::fs::write(dir.join("right.m3u"), "shared.m3u\n").unwrap();
+        std::fs::write(dir.join("top.m3u"), "left.m3u\nright.m3u\n").unwrap();
+
+        // reached twice but never inside itself, so not a loop
+        let flat = entries(&dir.join("top.m3u")).unwrap();
+        assert_eq!(flat.len(), 2);
+        assert!(flat.iter().all(|e| e.ends_with("x.mp3")));
+    }
+
+    #[test]
+    fn test_nesting_stops_at_max_depth() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        // level n holds song n and the playlist of level n + 1
+        for n in 0..MAX_DEPTH + 3 {
+            std::fs::write(dir.join(format!("{}.mp3", n)), b"").unwrap();
+            std::fs::write(
+                dir.join(format!("{}.m3u", n)),
+                format!("{}.mp3\n{}.m3u\n", n, n + 1),
+            )
+            .unwrap();
+        }
+
+        let flat = entries(&dir.join("0.m3u")).unwrap();
+        assert_eq!(flat.len(), MAX_DEPTH + 1);
+        assert!(
+            flat.last()
+                .unwrap()
+                .ends_with(&format!("{}.mp3", MAX_DEPTH))
+        );
+    }
+
+    #[test]
+    fn test_needs_expansion() {
+        assert!(needs_expansion("/music/list.XSPF"));
+        assert!(needs_expansion("/music/list.asx"));
+        assert!(!needs_expansion("/music/list.m3u"));
+    }
+}
@@ -0,0 +1,961 @@
+use crate::indexer::Track;
+use rand::seq::{IndexedRandom, SliceRandom};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+// StdRng may change algorithm between rand releases, ChaCha8 is fixed, so a
+// --seed replays the same order after an upgrade too
+pub type QueueRng = ChaCha8Rng;
+
+pub fn seeded_rng(seed: u64) -> QueueRng {
+    ChaCha8Rng::seed_from_u64(seed)
+}
+
+/// Splits a queue into the first `head` items to play and the rest. With `rng`
+/// the head is a shuffled random pick of the whole queue, the rest is left
+/// unshuffled for whoever queues it later. Costs O(head) either way.
+pub fn split_head<'a, T>(
+    items: &'a mut [T],
+    head: usize,
+    rng: Option<&mut QueueRng>,
+) -> (&'a mut [T], &'a mut [T]) {
+    match rng {
+        Some(rng) => items.partial_shuffle(rng, head),
+        None => items.split_at_mut(head.min(items.len())),
+    }
+}
+
+/// Same seed + same input = same order.
+pub fn shuffle_seeded<T>(items: &mut [T], seed: u64) {
+    items.shuffle(&mut seeded_rng(seed));
+}
+
+/// Picks up to `count` random tracks, preferring ones not in `avoid`.
+/// If the fresh pool runs dry, the constraint is relaxed with avoided tracks
+/// instead of returning a short (or empty) queue.
+pub fn pick_random<'a, R: Rng + ?Sized>(
+    pool: &[&'a Track],
+    count: usize,
+    avoid: &HashSet<String>,
+    rng: &mut R,
+) -> Vec<&'a Track> {
+    let (fresh, stale): (Vec<&Track>, Vec<&Track>) =
+        pool.iter().partition(|t| !avoid.contains(&t.path));
+
+    let mut picked: Vec<&Track> = fresh.choose_multiple(rng, count).copied().collect();
+
+    if picked.len() < count && !stale.is_empty() {
+        log::info!(
+            "Only {} fresh tracks available, relaxing recent-play filter",
+            picked.len()
+        );
+        picked.extend(stale.choose_multiple(rng, count - picked.len()).copied());
+    }
+
+    picked.shuffle(rng);
+    picked
+}
+
+// "CD2", "Disc 2", "disk02" -> 2, for rips that never tagged the disc number
+pub fn disc_from_dir(path: &str) -> Option<u32> {
+    let dir = Path::new(path)
+        .parent()?
+        .file_name()?
+        .to_str()?
+        .to_lowercase();
+    let rest = ["disc", "disk", "cd"]
+        .iter()
+        .find_map(|p| dir.strip_prefix(p))?
+        .trim_start_matches([' ', '_', '-', '.']);
+    rest.parse().ok()
+}
+
+fn disc_of(track: &Track) -> u32 {
+    track
+        .disc_no
+        .or_else(|| disc_from_dir(&track.path))
+        .unwrap_or(0)
+}
+
+// albums are identified by name + album artist (falling back to the track artist),
+// never by folder, so Album/CD1 and Album/CD2 end up in the same group
+fn album_key(track: &Track) -> (String, String) {
+    (
+        track.album.to_lowercase(),
+        album_artist_of(track).to_lowercase(),
+    )
+}
+
+pub fn album_artist_of(track: &Track) -> &str {
+    if track.album_artist.is_empty() {
+        &track.artist
+    } else {
+        &track.album_artist
+    }
+}
+
+/// Every artist credited on the track, for matching. Most tracks have just the one.
+pub fn artists_of(track: &Track) -> Vec<&str> {
+    if track.artists.is_empty() {
+        vec![track.artist.as_str()]
+    } else {
+        track.artists.iter().map(String::as_str).collect()
+    }
+}
+
+/// The year to sort and filter by. The other year fills in when the preferred one
+/// isn't tagged.
+pub fn year_of(track: &Track, prefer_original: bool) -> Option<u16> {
+    if prefer_original {
+        track.original_year.or(track.year)
+    } else {
+        track.year.or(track.original_year)
+    }
+}
+
+/// Albums in chronological order (undated ones last), each in disc/track order.
+/// An album's year is the earliest among its tracks.
+pub fn chronological_order<T: Borrow<Track>>(tracks: Vec<T>, prefer_original: bool) -> Vec<T> {
+    let mut years: HashMap<(String, String), u16> = HashMap::new();
+    for t in &tracks {
+        let t = t.borrow();
+        let year = year_of(t, prefer_original).unwrap_or(u16::MAX);
+        years
+            .entry(album_key(t))
+            .and_modify(|y| *y = (*y).min(year))
+            .or_insert(year);
+    }
+
+    // album_order is stable within the same year, so ties keep their album grouping
+    let mut tracks = album_order(tracks);
+    tracks.sort_by_key(|t| years[&album_key(t.borrow())]);
+    tracks
+}
+
+/// Picks one whole album out of `pool`, already in disc/track order.
+/// UNKNOWN albums and one-track "albums" only qualify with `include_singles`.
+pub fn pick_random_album<'a, R: Rng + ?Sized>(
+    pool: &[&'a Track],
+    include_singles: bool,
+    rng: &mut R,
+) -> Option<Vec<&'a Track>> {
+    let candidates = album_candidates(pool, include_singles);
+    log::debug!("Random album: {} candidate albums", candidates.len());
+
+    let album = candidates.choose(rng)?.clone();
+    Some(album_order(album))
+}
+
+/// A random track from the (path sorted) library, then the rest of its album from
+/// there on. Tracks in `avoid` are only picked when nothing else is left.
+pub fn surprise<'a, R: Rng + ?Sized>(
+    pool: &[&'a Track],
+    avoid: &HashSet<String>,
+    rng: &mut R,
+) -> Vec<&'a Track> {
+    // sorted, so a seed lands on the same track however the index was scanned
+    let mut sorted = pool.to_vec();
+    sorted.sort_by(|a, b| a.path.cmp(&b.path));
+    let Some(start) = pick_random(&sorted, 1, avoid, rng).pop() else {
+        return Vec::new();
+    };
+    rest_of_album(&sorted, start)
+}
+
+/// "3/12", or "3" when the tags don't say how many there are. Multi-disc
+/// releases add the disc: "3/12 (disc 2/2)".
+pub fn track_position(track: &Track) -> Option<String> {
+    let n = track.track_no?;
+    let position = match track.track_total {
+        Some(total) => format!("{}/{}", n, total),
+        None => n.to_string(),
+    };
+    match (track.disc_no, track.disc_total) {
+        (Some(disc), Some(discs)) if discs > 1 => {
+            Some(format!("{} (disc {}/{})", position, disc, discs))
+        }
+        _ => Some(position),
+    }
+}
+
+/// How many tracks an album's tags say it has: each disc's TRACKTOTAL added
+/// up. None unless every track carries a total and every disc is there.
+pub fn expected_tracks(album: &[&Track]) -> Option<usize> {
+    let mut per_disc: BTreeMap<u32, u32> = BTreeMap::new();
+    let mut discs = 1;
+    for t in album {
+        let total = t.track_total?;
+        let slot = per_disc.entry(t.disc_no.unwrap_or(1)).or_default();
+        *slot = (*slot).max(total);
+        discs = discs.max(t.disc_total.unwrap_or(1));
+    }
+    if album.is_empty() || !(1..=discs).all(|d| per_disc.contains_key(&d)) {
+        return None;
+    }
+    Some(per_disc.values().map(|&n| n as usize).sum())
+}
+
+/// `start` and what follows it on its album, in disc/track order. An UNKNOWN
+/// album is no album, that's just `start`.
+pub fn rest_of_album<'a>(pool: &[&'a Track], start: &'a Track) -> Vec<&'a Track> {
+    if start.album == "UNKNOWN" {
+        return vec![start];
+    }
+
+    let key = album_key(start);
+    let album = album_order(
+        pool.iter()
+            .copied()
+            .filter(|t| album_key(t) == key)
+            .collect(),
+    );
+    match album.iter().position(|t| t.path == start.path) {
+        Some(from) => album[from..].to_vec(),
+        None => vec![start],
+    }
+}
+
+// albums sorted by key, so a pick by index doesn't depend on hash or scan order
+fn album_candidates<'a>(pool: &[&'a Track], include_singles: bool) -> Vec<Vec<&'a Track>> {
+    let mut albums: BTreeMap<(String, String), Vec<&Track>> = BTreeMap::new();
+    for t in pool {
+        albums.entry(album_key(t)).or_default().push(t);
+    }
+
+    albums
+        .into_values()
+        .filter(|tracks| include_singles || (tracks.len() > 1 && tracks[0].album != "UNKNOWN"))
+        .collect()
+}
+
+// splitmix64 finalizer: fixed across builds and platforms, unlike the std hasher,
+// and neighbouring days land far apart
+fn mix(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    x ^ (x >> 31)
+}
+
+/// The album for a given day (days since the Unix epoch, UTC). Same library and
+/// same day always give the same album, in disc/track order.
+pub fn album_of_the_day<'a>(pool: &[&'a Track], day: u64) -> Option<Vec<&'a Track>> {
+    let candidates = album_candidates(pool, false);
+    if candidates.is_empty() {
+        return None;
+    }
+    let index = (mix(day) % candidates.len() as u64) as usize;
+    log::debug!(
+        "Album of the day {}: #{} of {} albums",
+        day,
+        index,
+        candidates.len()
+    );
+    Some(album_order(candidates[index].clone()))
+}
+
+/// When a track joined the library: the scan that first saw it, or its mtime
+/// with `by_mtime` (or for indexes that predate first_indexed).
+pub fn added_at(track: &Track, by_mtime: bool) -> u64 {
+    if by_mtime || track.first_indexed == 0 {
+        track.mtime
+    } else {
+        track.first_indexed
+    }
+}
+
+/// Every track added last (a bulk copy or one scan lands all at once), in album order.
+pub fn newest_tracks<'a>(pool: &[&'a Track], by_mtime: bool) -> Vec<&'a Track> {
+    let Some(newest) = pool.iter().map(|t| added_at(t, by_mtime)).max() else {
+        return Vec::new();
+    };
+    album_order(
+        pool.iter()
+            .copied()
+            .filter(|t| added_at(t, by_mtime) == newest)
+            .collect(),
+    )
+}
+
+/// The whole albums `seeds` belong to. Seeds without a real album come along as they are.
+pub fn albums_of<'a>(pool: &[&'a Track], seeds: &[&'a Track]) -> Vec<&'a Track> {
+    let keys: HashSet<(String, String)> = seeds
+        .iter()
+        .filter(|t| t.album != "UNKNOWN")
+        .map(|t| album_key(t))
+        .collect();
+
+    let mut tracks: Vec<&Track> = pool
+        .iter()
+        .copied()
+        .filter(|t| keys.contains(&album_key(t)))
+        .collect();
+    tracks.extend(seeds.iter().copied().filter(|t| t.album == "UNKNOWN"));
+    album_order(tracks)
+}
+
+/// Orders tracks album by album (in order of first appearance), and within an
+/// album by `(disc_no, track_no)` regardless of which directory each disc lives in.
+pub fn album_order<T: Borrow<Track>>(mut tracks: Vec<T>) -> Vec<T> {
+    let mut groups: HashMap<(String, String), usize> = HashMap::new();
+    for t in &tracks {
+        let next = groups.len();
+        groups.entry(album_key(t.borrow())).or_insert(next);
+    }
+
+    tracks.sort_by_cached_key(|t| {
+        let t = t.borrow();
+        (
+            groups[&album_key(t)],
+            disc_of(t),
+            t.track_no.unwrap_or(u32::MAX),
+            t.path.clone(),
+        )
+    });
+    tracks
+}
+
+/// Orders a whole folder tree: folder by folder in path order ("Disc 1" before
+/// "Disc 2"), and within a folder by `track_no`, then file name.
+pub fn folder_order<T: Borrow<Track>>(mut tracks: Vec<T>) -> Vec<T> {
+    tracks.sort_by_cached_key(|t| {
+        let t = t.borrow();
+        let path = Path::new(&t.path);
+        (
+            path.parent().map(Path::to_path_buf),
+            t.track_no.unwrap_or(u32::MAX),
+            t.path.clone(),
+        )
+    });
+    tracks
+}
+
+/// Up to `size` tracks to keep a live queue going after `seed`: about half by the
+/// same artist, the rest from the same genre, topped up from whichever has more.
+/// `pool` should already leave out what is queued.
+pub fn build_radio_batch<'a>(
+    pool: &[&'a Track],
+    seed: &Track,
+    size: usize,
+    rng_seed: u64,
+) -> Vec<&'a Track> {
+    let same = |a: &str, b: &str| a != "UNKNOWN" && !a.is_empty() && a.eq_ignore_ascii_case(b);
+
+    let (mut by_artist, mut by_genre): (Vec<&Track>, Vec<&Track>) = pool
+        .iter()
+        .copied()
+        .filter(|t| t.path != seed.path)
+        .filter(|t| same(&t.artist, &seed.artist) || same(&t.genre, &seed.genre))
+        .partition(|t| same(&t.artist, &seed.artist));
+    shuffle_seeded(&mut by_artist, rng_seed);
+    shuffle_seeded(&mut by_genre, rng_seed);
+
+    let from_artist = by_artist
+        .len()
+        .min((size / 2).max(size.saturating_sub(by_genre.len())));
+    let from_genre = by_genre.len().min(size - from_artist);
+
+    let mut batch: Vec<&Track> = by_artist.into_iter().take(from_artist).collect();
+    batch.extend(by_genre.into_iter().take(from_genre));
+    shuffle_seeded(&mut batch, rng_seed);
+    batch
+}
+
+/// Up to `count` tracks from `pool` with the highest `scores`, best first.
+/// Tracks without a score (never played) are left out.
+pub fn most_played<'a>(
+    pool: &[&'a Track],
+    scores: &HashMap<String, f64>,
+    count: usize,
+) -> Vec<&'a Track> {
+    let mut ranked: Vec<(&Track, f64)> = pool
+        .iter()
+        .filter_map(|t| scores.get(&t.path).map(|score| (*t, *score)))
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.path.cmp(&b.0.path)));
+    ranked.into_iter().take(count).map(|(t, _)| t).collect()
+}
+
+/// Tracks whose BPM tag falls in `min..=max`. Untagged tracks never match.
+pub fn in_bpm_range<'a>(pool: &[&'a Track], min: u32, max: u32) -> Vec<&'a Track> {
+    pool.iter()
+        .copied()
+        .filter(|t| t.bpm.is_some_and(|bpm| (min..=max).contains(&bpm)))
+        .collect()
+}
+
+/// Composer for classical browsing, falling back to the (album) artist.
+pub fn composer_of(track: &Track) -> &str {
+    track
+        .composer
+        .as_deref()
+        .unwrap_or_else(|| album_artist_of(track))
+}
+
+/// Work for classical browsing: the work tag, then grouping (where Apple puts it),
+/// then the album.
+pub fn work_of(track: &Track) -> &str {
+    track
+        .work
+        .as_deref()
+        .or(track.grouping.as_deref())
+        .unwrap_or(&track.album)
+}
+
+/// Orders tracks work by work (in order of first appearance), and within a work
+/// by movement number, falling back to disc/track order for untagged movements.
+pub fn movement_order<T: Borrow<Track>>(mut tracks: Vec<T>) -> Vec<T> {
+    let mut works: HashMap<(String, String), usize> = HashMap::new();
+    let key = |t: &Track| (composer_of(t).to_lowercase(), work_of(t).to_lowercase());
+    for t in &tracks {
+        let next = works.len();
+        works.entry(key(t.borrow())).or_insert(next);
+    }
+
+    tracks.sort_by_cached_key(|t| {
+        let t = t.borrow();
+        (
+            works[&key(t)],
+            t.movement_no.unwrap_or(u32::MAX),
+            disc_of(t),
+            t.track_no.unwrap_or(u32::MAX),
+            t.path.clone(),
+        )
+    });
+    tracks
+}
+
+/// Shuffles each music dir's tracks on its own and deals them out a dir at a
+/// time, so a small dir gets as many turns as a big one until it runs dry.
+/// Nested dirs count toward the outermost, tracks outside all of them are a
+/// group of their own. Stops at `limit` tracks.
+pub fn balanced_shuffle<'a>(
+    pool: &[&'a Track],
+    music_dirs: &[PathBuf],
+    limit: Option<usize>,
+    seed: u64,
+) -> Vec<&'a Track> {
+    let mut groups: BTreeMap<usize, Vec<&Track>> = BTreeMap::new();
+    for t in pool {
+        let source = Path::new(&t.path);
+        let dir = music_dirs
+            .iter()
+            .enumerate()
+            .filter(|(_, dir)| source.starts_with(dir))
+            .min_by_key(|(_, dir)| dir.components().count())
+            .map_or(music_dirs.len(), |(i, _)| i);
+        groups.entry(dir).or_default().push(t);
+    }
+
+    // sorted first, so a seed gives the same mix however the index was scanned
+    let mut rng = seeded_rng(seed);
+    let mut groups: Vec<Vec<&Track>> = groups.into_values().collect();
+    for group in &mut groups {
+        group.sort_by(|a, b| a.path.cmp(&b.path));
+        group.shuffle(&mut rng);
+        group.reverse();
+    }
+    groups.shuffle(&mut rng);
+
+    let limit = limit.unwrap_or(pool.len());
+    let mut mixed = Vec::with_capacity(limit.min(pool.len()));
+    while mixed.len() < limit && !groups.is_empty() {
+        groups.retain_mut(|group| match group.pop() {
+            Some(t) if mixed.len() < limit => {
+                mixed.push(t);
+                true
+            }
+            _ => false,
+        });
+    }
+    mixed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(path: &str) -> Track {
+        Track {
+            path: path.to_string(),
+            title: path.to_string(),
+            artist: "Artist".to_string(),
+            album: "Album".to_string(),
+            genre: "Genre".to_string(),
+            mtime: 0,
+            size: 0,
+            media_type: "audio".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_split_head_keeps_every_item_once() {
+        let mut items: Vec<u32> = (0..100).collect();
+        let (head, rest) = split_head(&mut items, 10, None);
+        assert_eq!(head, (0..10).collect::<Vec<_>>());
+        assert_eq!(rest.len(), 90);
+
+        let mut short: Vec<u32> = (0..5).collect();
+        let (head, rest) = split_head(&mut short, 10, None);
+        assert_eq!((head.len(), rest.len()), (5, 0));
+
+        let shuffled = |seed| {
+            let mut items: Vec<u32> = (0..100).collect();
+            let (head, rest) = split_head(&mut items, 10, Some(&mut seeded_rng(seed)));
+            assert_eq!((head.len(), rest.len()), (10, 90));
+            let head = head.to_vec();
+            let mut all: Vec<u32> = head.iter().chain(rest.iter()).copied().collect();
+            all.sort();
+            assert_eq!(all, (0..100).collect::<Vec<_>>());
+            head
+        };
+        assert_eq!(shuffled(7), shuffled(7));
+        assert_ne!(shuffled(7), (0..10).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_pick_random_skips_avoided() {
+        let tracks: Vec<Track> = (0..10).map(|i| track(&format!("/m/{}.mp3", i))).collect();
+        let pool: Vec<&Track> = tracks.iter().collect();
+        let avoid: HashSet<String> = (0..5).map(|i| format!("/m/{}.mp3", i)).collect();
+
+        let picked = pick_random(&pool, 5, &avoid, &mut rand::rng());
+        assert_eq!(picked.len(), 5);
+        assert!(picked.iter().all(|t| !avoid.contains(&t.path)));
+    }
+
+    #[test]
+    fn test_pick_random_relaxes_when_short() {
+        let tracks: Vec<Track> = (0..4).map(|i| track(&format!("/m/{}.mp3", i))).collect();
+        let pool: Vec<&Track> = tracks.iter().collect();
+        let avoid: HashSet<String> = (0..3).map(|i| format!("/m/{}.mp3", i)).collect();
+
+        let picked = pick_random(&pool, 3, &avoid, &mut rand::rng());
+        assert_eq!(picked.len(), 3);
+        assert!(picked.iter().any(|t| t.path == "/m/3.mp3"));
+    }
+
+    fn album_track(path: &str, disc: Option<u32>, no: u32) -> Track {
+        Track {
+            album: "Mellon Collie".to_string(),
+            album_artist: "The Smashing Pumpkins".to_string(),
+            disc_no: disc,
+            track_no: Some(no),
+            ..track(path)
+        }
+    }
+
+    #[test]
+    fn test_album_order_merges_disc_directories() {
+        // two-disc layout, discs in sibling folders, scanned out of order
+        let tracks = [
+            album_track("/m/Mellon Collie/CD2/01.flac", Some(2), 1),
+            album_track("/m/Mellon Collie/CD1/02.flac", Some(1), 2),
+            album_track("/m/Mellon Collie/CD2/02.flac", Some(2), 2),
+            album_track("/m/Mellon Collie/CD1/01.flac", Some(1), 1),
+        ];
+        let ordered: Vec<&str> = album_order(tracks.iter().collect())
+            .iter()
+            .map(|t| t.path.as_str())
+            .collect();
+
+        assert_eq!(
+            ordered,
+            vec![
+                "/m/Mellon Collie/CD1/01.flac",
+                "/m/Mellon Collie/CD1/02.flac",
+                "/m/Mellon Collie/CD2/01.flac",
+                "/m/Mellon Collie/CD2/02.flac",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_album_order_disc_from_directory_name() {
+        let tracks = [
+            album_track("/m/Album/Disc 2/01.mp3", None, 1),
+            album_track("/m/Album/Disc 1/09.mp3", None, 9),
+        ];
+        let ordered = album_order(tracks.iter().collect());
+        assert_eq!(ordered[0].path, "/m/Album/Disc 1/09.mp3");
+        assert_eq!(disc_from_dir("/m/Album/cd_03/x.mp3"), Some(3));
+        assert_eq!(disc_from_dir("/m/Album/Bonus/x.mp3"), None);
+    }
+
+    #[test]
+    fn test_random_album_skips_singles_and_unknown() {
+        let tracks = [
+            album_track("/m/Mellon Collie/CD1/02.flac", Some(1), 2),
+            album_track("/m/Mellon Collie/CD1/01.flac", Some(1), 1),
+            Track {
+                album: "Lonely Single".to_string(),
+                ..track("/m/single.mp3")
+            },
+            Track {
+                album: "UNKNOWN".to_string(),
+                ..track("/m/loose1.mp3")
+            },
+            Track {
+                album: "UNKNOWN".to_string(),
+                ..track("/m/loose2.mp3")
+            },
+        ];
+        let pool: Vec<&Track> = tracks.iter().collect();
+
+        for _ in 0..10 {
+            let album = pick_random_album(&pool, false, &mut rand::rng()).unwrap();
+            assert_eq!(album.len(), 2);
+            assert_eq!(album[0].path, "/m/Mellon Collie/CD1/01.flac");
+        }
+
+        let only_single: Vec<&Track> = tracks[2..3].iter().collect();
+        assert!(pick_random_album(&only_single, false, &mut rand::rng()).is_none());
+        assert!(pick_random_album(&only_single, true, &mut rand::rng()).is_some());
+    }
+
+    #[test]
+    fn test_album_of_the_day_is_stable() {
+        let tracks: Vec<Track> = (0..20)
+            .flat_map(|a| {
+                (1..=2).map(move |n| Track {
+                    album: format!("Album {}", a),
+                    track_no: Some(n),
+                    ..track(&format!("/m/{}/{}.mp3", a, n))
+                })
+            })
+            .collect();
+        let pool: Vec<&Track> = tracks.iter().collect();
+        let mut reversed = pool.clone();
+        reversed.reverse();
+
+        let today = album_of_the_day(&pool, 20_000).unwrap();
+        assert_eq!(today.len(), 2);
+        assert_eq!(today[0].track_no, Some(1));
+        // scan order doesn't matter, only the library and the day
+        assert_eq!(
+            album_of_the_day(&reversed, 20_000).unwrap()[0].album,
+            today[0].album
+        );
+
+        let week: HashSet<String> = (20_000..20_007)
+            .map(|d| album_of_the_day(&pool, d).unwrap()[0].album.clone())
+            .collect();
+        assert!(week.len() > 1);
+        assert!(album_of_the_day(&[], 20_000).is_none());
+    }
+
+    #[test]
+    fn test_most_played_ranks_by_score() {
+        let tracks = [track("a"), track("b"), track("c")];
+        let pool: Vec<&Track> = tracks.iter().collect();
+        let scores = HashMap::from([("a".to_string(), 0.4), ("c".to_string(), 2.5)]);
+
+        let top: Vec<&str> = most_played(&pool, &scores, 5)
+            .iter()
+            .map(|t| t.path.as_str())
+            .collect();
+        assert_eq!(top, ["c", "a"]);
+        assert_eq!(most_played(&pool, &scores, 1).len(), 1);
+    }
+
+    #[test]
+    fn test_bpm_range_skips_untagged() {
+        let tagged = |path: &str, bpm: Option<u32>| Track { bpm, ..track(path) };
+        let tracks = [
+            tagged("slow", Some(85)),
+            tagged("edge", Some(99)),
+            tagged("fast", Some(140)),
+            tagged("none", None),
+        ];
+        let pool: Vec<&Track> = tracks.iter().collect();
+
+        let chill: Vec<&str> = in_bpm_range(&pool, 0, 99)
+            .iter()
+            .map(|t| t.path.as_str())
+            .collect();
+        assert_eq!(chill, ["slow", "edge"]);
+        assert_eq!(in_bpm_range(&pool, 131, 999).len(), 1);
+        assert_eq!(in_bpm_range(&pool, 0, u32::MAX).len(), 3);
+    }
+
+    #[test]
+    fn test_newest_plays_whole_album() {
+        let tracks = [
+            Track {
+                mtime: 10,
+                ..album_track("/m/Mellon Collie/CD1/01.flac", Some(1), 1)
+            },
+            Track {
+                mtime: 50,
+                ..album_track("/m/Mellon Collie/CD1/02.flac", Some(1), 2)
+            },
+            Track {
+                mtime: 20,
+                ..track("/m/other.mp3")
+            },
+        ];
+        let pool: Vec<&Track> = tracks.iter().collect();
+
+        let newest = newest_tracks(&pool, false);
+        assert_eq!(newest.len(), 1);
+        assert_eq!(newest[0].path, "/m/Mellon Collie/CD1/02.flac");
+
+        let album: Vec<&str> = albums_of(&pool, &newest)
+            .iter()
+            .map(|t| t.path.as_str())
+            .collect();
+        assert_eq!(
+            album,
+            [
+                "/m/Mellon Collie/CD1/01.flac",
+                "/m/Mellon Collie/CD1/02.flac"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_newest_prefers_first_indexed() {
+        // an old file re-tagged yesterday vs. an album added last week
+        let retagged = Track {
+            mtime: 900,
+            first_indexed: 100,
+            ..track("/m/old.mp3")
+        };
+        let added = Track {
+            mtime: 50,
+            first_indexed: 500,
+            ..track("/m/new.mp3")
+        };
+        let pool = [&retagged, &added];
+        assert_eq!(newest_tracks(&pool, false)[0].path, "/m/new.mp3");
+        assert_eq!(newest_tracks(&pool, true)[0].path, "/m/old.mp3");
+    }
+
+    #[test]
+    fn test_seeded_shuffle_is_reproducible() {
+        let original: Vec<u32> = (0..50).collect();
+
+        let mut a = original.clone();
+        let mut b = original.clone();
+        shuffle_seeded(&mut a, 42);
+        shuffle_seeded(&mut b, 42);
+        assert_eq!(a, b);
+        assert_ne!(a, original);
+
+        let mut c = original.clone();
+        shuffle_seeded(&mut c, 43);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_pick_random_caps_at_pool_size() {
+        let tracks: Vec<Track> = (0..2).map(|i| track(&format!("/m/{}.mp3", i))).collect();
+        let pool: Vec<&Track> = tracks.iter().collect();
+
+        let picked = pick_random(&pool, 10, &HashSet::new(), &mut rand::rng());
+        assert_eq!(picked.len(), 2);
+    }
+
+    #[test]
+    fn test_movement_order_groups_works() {
+        let movement = |path: &str, work: &str, n: Option<u32>, track_no: u32| Track {
+            composer: Some("Beethoven".to_string()),
+            work: Some(work.to_string()),
+            movement_no: n,
+            track_no: Some(track_no),
+            ..track(path)
+        };
+        let tracks = [
+            movement("/m/5-2.flac", "Symphony No. 5", Some(2), 1),
+            movement("/m/7-1.flac", "Symphony No. 7", Some(1), 5),
+            movement("/m/5-1.flac", "Symphony No. 5", Some(1), 9),
+            movement("/m/5-x.flac", "Symphony No. 5", None, 2),
+        ];
+        let paths: Vec<_> = movement_order(tracks.iter().collect())
+            .into_iter()
+            .map(|t| t.path.as_str())
+            .collect();
+        assert_eq!(
+            paths,
+            ["/m/5-1.flac", "/m/5-2.flac", "/m/5-x.flac", "/m/7-1.flac"]
+        );
+
+        // untagged tracks browse as artist -> album
+        let plain = track("/m/a.mp3");
+        assert_eq!((composer_of(&plain), work_of(&plain)), ("Artist", "Album"));
+    }
+
+    #[test]
+    fn test_radio_batch_mixes_artist_and_genre() {
+        let tagged = |path: &str, artist: &str, genre: &str| Track {
+            artist: artist.to_string(),
+            genre: genre.to_string(),
+            ..track(path)
+        };
+        let mut tracks = vec![tagged("/m/seed.mp3", "Ado", "J-Pop")];
+        tracks.extend((0..10).map(|i| tagged(&format!("/m/ado{}.mp3", i), "ado", "Rock")));
+        tracks.extend((0..3).map(|i| tagged(&format!("/m/pop{}.mp3", i), "YOASOBI", "j-pop")));
+        tracks.push(tagged("/m/other.mp3", "Someone", "Jazz"));
+        let pool: Vec<&Track> = tracks.iter().collect();
+
+        let batch = build_radio_batch(&pool, &tracks[0], 8, 7);
+        assert_eq!(batch.len(), 8);
+        assert!(
+            batch
+                .iter()
+                .all(|t| t.path != "/m/seed.mp3" && t.genre != "Jazz")
+        );
+        // only 3 genre matches, the artist fills the rest
+        assert_eq!(batch.iter().filter(|t| t.artist == "YOASOBI").count(), 3);
+
+        // nothing related at all
+        let lonely = tagged("/m/x.mp3", "UNKNOWN", "UNKNOWN");
+        assert!(build_radio_batch(&pool, &lonely, 8, 7).is_empty());
+    }
+
+    #[test]
+    fn test_surprise_continues_into_album() {
+        let numbered = |path: &str, album: &str, n: u32| Track {
+            album: album.to_string(),
+            track_no: Some(n),
+            ..track(path)
+        };
+        let tracks = [
+            numbered("/m/a3.mp3", "A", 3),
+            numbered("/m/a1.mp3", "A", 1),
+            numbered("/m/a2.mp3", "A", 2),
+        ];
+        let pool: Vec<&Track> = tracks.iter().collect();
+
+        // everything but a2 was played recently, so the pick has to start there
+        let avoid: HashSet<String> = ["/m/a1.mp3", "/m/a3.mp3"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let paths: Vec<&str> = surprise(&pool, &avoid, &mut seeded_rng(1))
+            .iter()
+            .map(|t| t.path.as_str())
+            .collect();
+        assert_eq!(paths, ["/m/a2.mp3", "/m/a3.mp3"]);
+
+        // same seed, same pick
+        let first = surprise(&pool, &HashSet::new(), &mut seeded_rng(9));
+        let again = surprise(&pool, &HashSet::new(), &mut seeded_rng(9));
+        assert_eq!(first[0].path, again[0].path);
+    }
+
+    #[test]
+    fn test_chronological_order_by_original_year() {
+        let dated = |path: &str, album: &str, year: Option<u16>, original: Option<u16>| Track {
+            album: album.to_string(),
+            year,
+            original_year: original,
+            ..track(path)
+        };
+        let tracks = [
+            dated("/m/remaster.flac", "Abbey Road", Some(2019), Some(1969)),
+            dated("/m/new.flac", "Recent", Some(2001), None),
+            dated("/m/undated.flac", "Mystery", None, None),
+        ];
+
+        let order = |prefer| -> Vec<String> {
+            chronological_order(tracks.iter().collect(), prefer)
+                .into_iter()
+                .map(|t| t.album.clone())
+                .collect()
+        };
+        assert_eq!(order(false), ["Recent", "Abbey Road", "Mystery"]);
+        assert_eq!(order(true), ["Abbey Road", "Recent", "Mystery"]);
+    }
+
+    #[test]
+    fn test_balanced_shuffle_takes_turns() {
+        let dirs = [PathBuf::from("/big"), PathBuf::from("/small")];
+        let mut tracks: Vec<Track> = (0..8).map(|i| track(&format!("/big/{}.mp3", i))).collect();
+        tracks.push(track("/small/a.mp3"));
+        tracks.push(track("/small/b.mp3"));
+        tracks.push(track("/elsewhere/x.mp3"));
+        let pool: Vec<&Track> = tracks.iter().collect();
+
+        // one of each group per round until a group runs out
+        let mixed = balanced_shuffle(&pool, &dirs, Some(6), 7);
+        assert_eq!(mixed.len(), 6);
+        let from = |prefix: &str| mixed.iter().filter(|t| t.path.starts_with(prefix)).count();
+        assert_eq!(
+            (from("/big"), from("/small"), from("/elsewhere")),
+            (3, 2, 1)
+        );
+
+        let all = balanced_shuffle(&pool, &dirs, None, 7);
+        assert_eq!(all.len(), pool.len());
+        assert_eq!(
+            balanced_shuffle(&pool, &dirs, None, 7)
+                .iter()
+                .map(|t| &t.path)
+                .collect::<Vec<_>>(),
+            all.iter().map(|t| &t.path).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_positions_and_expected_tracks() {
+        let numbered = |path: &str, no: u32, total: Option<u32>, disc: Option<(u32, u32)>| Track {
+            track_no: Some(no),
+            track_total: total,
+            disc_no: disc.map(|(d, _)| d),
+            disc_total: disc.map(|(_, n)| n),
+            ..track(path)
+        };
+        assert_eq!(
+            track_position(&numbered("/m/1", 3, Some(12), None)).as_deref(),
+            Some("3/12")
+        );
+        assert_eq!(
+            track_position(&numbered("/m/1", 3, None, Some((2, 2)))).as_deref(),
+            Some("3 (disc 2/2)")
+        );
+        assert_eq!(track_position(&track("/m/1")), None);
+
+        let album = [
+            numbered("/m/1", 1, Some(10), Some((1, 2))),
+            numbered("/m/2", 2, Some(10), Some((1, 2))),
+            numbered("/m/3", 1, Some(8), Some((2, 2))),
+        ];
+        let refs: Vec<&Track> = album.iter().collect();
+        assert_eq!(expected_tracks(&refs), Some(18));
+        // disc 2 missing entirely, or a track without a total: can't tell
+        assert_eq!(expected_tracks(&refs[..2]), None);
+        let untagged = [&album[0], &track("/m/4")];
+        assert_eq!(expected_tracks(&untagged), None);
+    }
+
+    #[test]
+    fn test_folder_order_walks_the_tree() {
+        let numbered = |path: &str, no: Option<u32>| Track {
+            track_no: no,
+            ..track(path)
+        };
+        let tree = vec![
+            numbered("/m/Artist/B/01.flac", Some(1)),
+            numbered("/m/Artist/A/Disc 2/01.flac", Some(1)),
+            numbered("/m/Artist/A/Disc 1/10.flac", Some(10)),
+            numbered("/m/Artist/A/Disc 1/9.flac", Some(9)),
+            numbered("/m/Artist/A/bonus.flac", None),
+        ];
+        let paths: Vec<String> = folder_order(tree).into_iter().map(|t| t.path).collect();
+        assert_eq!(
+            paths,
+            [
+                "/m/Artist/A/bonus.flac",
+                "/m/Artist/A/Disc 1/9.flac",
+                "/m/Artist/A/Disc 1/10.flac",
+                "/m/Artist/A/Disc 2/01.flac",
+                "/m/Artist/B/01.flac",
+            ]
+        );
+    }
+}
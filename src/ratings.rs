@@ -0,0 +1,220 @@
+use anyhow::{Context, Result};
+use directories::ProjectDirs;
+use lofty::config::WriteOptions;
+use lofty::prelude::*;
+use lofty::probe::Probe;
+use lofty::tag::Tag;
+use lofty::tag::items::popularimeter::{Popularimeter, StarRating};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Track ratings, path -> 1..=5, kept in ratings.json. Loaded by whatever shows
+/// or changes them and handed on from there.
+#[derive(Debug, Clone, Default)]
+pub struct Ratings {
+    ratings: HashMap<String, u8>,
+    // where they're saved, None keeps them in memory
+    file: Option<PathBuf>,
+}
+
+fn ratings_path() -> Option<PathBuf> {
+    ProjectDirs::from("com", "furqanhun", "mpv-music")
+        .map(|dirs| dirs.data_dir().join("ratings.json"))
+}
+
+// gone from a music directory that is there, an unmounted drive keeps its ratings
+fn vanished(path: &str, music_dirs: &[PathBuf]) -> bool {
+    let path = Path::new(path);
+    !path.exists()
+        && music_dirs
+            .iter()
+            .any(|dir| path.starts_with(dir) && dir.is_dir())
+}
+
+impl Ratings {
+    /// Reads ratings.json. Ratings of files that vanished are dropped (and the
+    /// file rewritten).
+    pub fn load(music_dirs: &[PathBuf]) -> Self {
+        match ratings_path() {
+            Some(path) => Self::load_from(path, music_dirs),
+            None => Self::default(),
+        }
+    }
+
+    fn load_from(file: PathBuf, music_dirs: &[PathBuf]) -> Self {
+        let mut ratings: HashMap<String, u8> = std::fs::read_to_string(&file)
+            .ok()
+            .and_then(|s| serde_json::from_str(&s).ok())
+            .unwrap_or_default();
+
+        let before = ratings.len();
+        ratings.retain(|p, r| (1..=5).contains(r) && !vanished(p, music_dirs));
+        let ratings = Self {
+            ratings,
+            file: Some(file),
+        };
+        if ratings.ratings.len() < before {
+            log::info!(
+                "Pruned {} ratings of vanished tracks",
+                before - ratings.ratings.len()
+            );
+            ratings.save();
+        }
+        ratings
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.file else {
+            return;
+        };
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        let result = serde_json::to_string(&self.ratings)
+            .map_err(std::io::Error::other)
+            .and_then(|content| std::fs::write(path, content));
+        if let Err(e) = result {
+            log::warn!("Could not save ratings to {:?}: {}", path, e);
+        }
+    }
+
+    pub fn get(&self, path: &str) -> Option<u8> {
+        self.ratings.get(path).copied()
+    }
+
+    /// Rates the tracks 1-5, 0 clears their rating. With `to_tags` the rating is
+    /// also written to each file's tag (POPM for ID3v2).
+    pub fn set(&mut self, paths: &[String], rating: u8, to_tags: bool) {
+        for path in paths {
+            if rating == 0 {
+                self.ratings.remove(path);
+            } else {
+                self.ratings.insert(path.clone(), rating);
+            }
+        }
+        self.save();
+        log::info!("Rated {} track(s): {}", paths.len(), rating);
+
+        if to_tags {
+            for path in paths {
+                if let Err(e) = write_tag(Path::new(path), rating) {
+                    log::warn!("{:#}", e);
+                }
+            }
+        }
+    }
+
+    /// Carries ratings over to renamed files, (old path, new path) pairs. Has to
+    /// be called on ratings loaded before the renames, loading them afterwards
+    /// prunes the old paths.
+    pub fn moved(&mut self, renames: &[(String, String)]) {
+        let mut changed = false;
+        for (old, new) in renames {
+            if let Some(rating) = self.ratings.remove(old) {
+                self.ratings.insert(new.clone(), rating);
+                changed = true;
+            }
+        }
+        if changed {
+            self.save();
+        }
+    }
+}
+
+fn write_tag(path: &Path, rating: u8) -> Result<()> {
+    let mut tagged = Probe::open(path)
+        .and_then(|p| p.read())
+        .with_context(|| format!("Could not read tags from {:?}", path))?;
+
+    if tagged.primary_tag().is_none() {
+        let tag_type = tagged.primary_tag_type();
+        tagged.insert_tag(Tag::new(tag_type));
+    }
+    let tag = tagged
+        .primary_tag_mut()
+        .context("File format has no writable tag")?;
+
+    let stars = match rating {
+        1 => Some(StarRating::One),
+        2 => Some(StarRating::Two),
+        3 => Some(StarRating::Three),
+        4 => Some(StarRating::Four),
+        5 => Some(StarRating::Five),
+        _ => None,
+    };
+    match stars {
+        Some(stars) => {
+            let play_count = tag.ratings().next().map_or(0, |p| p.play_counter);
+            tag.remove_key(ItemKey::Popularimeter);
+            tag.insert_text(
+                ItemKey::Popularimeter,
+                Popularimeter::musicbee(stars, play_count).to_string(),
+            );
+        }
+        None => tag.remove_key(ItemKey::Popularimeter),
+    }
+
+    tag.save_to_path(path, WriteOptions::default())
+        .with_context(|| format!("Could not write the rating to {:?}", path))?;
+    Ok(())
+}
+
+/// 4 -> "★★★★☆"
+pub fn stars(rating: u8) -> String {
+    let rating = rating.min(5) as usize;
+    format!("{}{}", "★".repeat(rating), "☆".repeat(5 - rating))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stars() {
+        assert_eq!(stars(4), "★★★★☆");
+        assert_eq!(stars(0), "☆☆☆☆☆");
+        assert_eq!(stars(9), "★★★★★");
+    }
+
+    #[test]
+    fn test_only_vanished_under_present_dirs() {
+        let here = std::env::temp_dir();
+        let gone = here.join("mpv-music-no-such-track.flac");
+        assert!(vanished(
+            &gone.to_string_lossy(),
+            std::slice::from_ref(&here)
+        ));
+
+        // the drive isn't mounted, not the same as the track being deleted
+        let unmounted = PathBuf::from("/mpv-music-no-such-mount");
+        assert!(!vanished(
+            "/mpv-music-no-such-mount/a.flac",
+            std::slice::from_ref(&unmounted)
+        ));
+        // outside every music directory, nothing to go by
+        assert!(!vanished(&gone.to_string_lossy(), &[unmounted]));
+    }
+
+    #[test]
+    fn test_rating_follows_a_renamed_file() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dirs = [tmp.path().to_path_buf()];
+        let file = tmp.path().join("ratings.json");
+        let old = tmp.path().join("01 untitled.flac");
+        let new = tmp.path().join("01 - Hells Bells.flac");
+        std::fs::write(&old, b"").unwrap();
+        let (old, new) = (
+            old.to_string_lossy().to_string(),
+            new.to_string_lossy().to_string(),
+        );
+        std::fs::write(&file, serde_json::json!({ &old: 4 }).to_string()).unwrap();
+
+        let mut ratings = Ratings::load_from(file.clone(), &dirs);
+        std::fs::rename(&old, &new).unwrap();
+        ratings.moved(&[(old.clone(), new.clone())]);
+
+        let reloaded = Ratings::load_from(file, &dirs);
+        assert_eq!(reloaded.get(&new), Some(4));
+        assert_eq!(reloaded.get(&old), None);
+    }
+}
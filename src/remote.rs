@@ -0,0 +1,153 @@
+use crate::audiobook;
+use crate::config::Config;
+use crate::indexer::{self, Track};
+use crate::ipc;
+use crate::queue;
+use anyhow::{Context, Result, bail};
+use directories::ProjectDirs;
+use std::collections::HashSet;
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+// tracks appended per `radio` call, run it again for more
+const RADIO_BATCH: usize = 25;
+
+/// Runs an `--ipc-command` against the mpv-music player that is already playing.
+pub fn run(command: &str, cfg: &Config) -> Result<()> {
+    let socket = find_player()?;
+    log::info!("Sending '{}' to the player at {}", command, socket);
+
+    match command {
+        "radio" => radio(&socket, cfg),
+        other => bail!("Unknown IPC command '{}'", other),
+    }
+}
+
+// the newest socket that still answers, crashed players leave theirs behind
+fn find_player() -> Result<String> {
+    ipc::running_sockets()
+        .into_iter()
+        .find(|socket| ipc::request(socket, &["get_property", "pid"]).is_ok())
+        .context(
+            "No running mpv-music player found. It needs an IPC socket, which record_history, set_terminal_title or idle_timeout give it.",
+        )
+}
+
+/// What `queue_radio` did with the player's current track.
+pub enum Radio {
+    Stream(String),
+    Unindexed(String),
+    Exhausted(Track),
+    Added(Track, usize),
+}
+
+// seeds a same-artist/genre batch from the track playing right now
+fn radio(socket: &str, cfg: &Config) -> Result<()> {
+    match queue_radio(socket, cfg)? {
+        Radio::Stream(path) => println!(
+            "📻 Now playing a stream ({}), there's no index entry to seed a radio from.",
+            path
+        ),
+        Radio::Unindexed(path) => println!(
+            "📻 {} isn't in the index, so there's nothing to seed a radio from.",
+            path
+        ),
+        Radio::Exhausted(seed) => println!(
+            "📻 Nothing else by {} or in {} to add.",
+            seed.artist, seed.genre
+        ),
+        Radio::Added(seed, count) => println!(
+            "📻 Added {} tracks like {} - {} to the queue.",
+            count, seed.artist, seed.title
+        ),
+    }
+    Ok(())
+}
+
+/// Appends a radio batch seeded from what the player at `socket` is playing.
+/// Also used by `radio_after_queue`, which calls it as the last track starts.
+pub fn queue_radio(socket: &str, cfg: &Config) -> Result<Radio> {
+    let now_playing = ipc::request(socket, &["get_property", "path"])
+        .context("The player isn't playing anything right now")?;
+    let Some(path) = now_playing.as_str() else {
+        bail!("The player isn't playing anything right now");
+    };
+    if path.contains("://") {
+        return Ok(Radio::Stream(path.to_string()));
+    }
+
+    let (tracks, _) = indexer::load_index(&cfg.index_format)?;
+    let canonical = dunce::canonicalize(path)
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| path.to_string());
+    let Some(seed) = tracks
+        .iter()
+        .find(|t| t.path == path || t.path == canonical)
+    else {
+        return Ok(Radio::Unindexed(path.to_string()));
+    };
+
+    let queued = queued_paths(socket);
+    let pool: Vec<&Track> = tracks
+        .iter()
+        .filter(|t| {
+            t.media_type != "playlist"
+                && !queued.contains(&t.path)
+                && !audiobook::is_audiobook(cfg, &t.path)
+        })
+        .collect();
+    let batch = queue::build_radio_batch(&pool, seed, RADIO_BATCH, cfg.shuffle_seed);
+    if batch.is_empty() {
+        return Ok(Radio::Exhausted(seed.clone()));
+    }
+
+    let paths: Vec<String> = batch.iter().map(|t| t.path.clone()).collect();
+    append_to_player(socket, &paths)?;
+    Ok(Radio::Added(seed.clone(), paths.len()))
+}
+
+// what mpv already has queued, so the batch doesn't repeat it
+fn queued_paths(socket: &str) -> HashSet<String> {
+    ipc::request(socket, &["get_property", "playlist"])
+        .ok()
+        .and_then(|list| {
+            list.as_array().map(|entries| {
+                entries
+                    .iter()
+                    .filter_map(|e| e["filename"].as_str().map(String::from))
+                    .collect()
+            })
+        })
+        .unwrap_or_default()
+}
+
+/// Appends `paths` to the running player's queue with `loadlist <file> append`.
+pub fn append_to_player(socket: &str, paths: &[impl AsRef<str>]) -> Result<()> {
+    let dirs = ProjectDirs::from("com", "furqanhun", "mpv-music")
+        .context("Could not determine data directory")?;
+    let data_dir = dirs.data_dir();
+    std::fs::create_dir_all(data_dir)?;
+    // appends can overlap (a streamed queue's batches, a radio refill), each gets its own file
+    static APPENDS: AtomicU64 = AtomicU64::new(0);
+    let n = APPENDS.fetch_add(1, Ordering::Relaxed);
+    let list_path = data_dir.join(format!("append_{}_{}.m3u8", std::process::id(), n));
+
+    {
+        let mut file = std::fs::File::create(&list_path)
+            .context("Failed to create temporary playlist file")?;
+        writeln!(file, "#EXTM3U")?;
+        for path in paths {
+            writeln!(file, "{}", path.as_ref())?;
+        }
+    }
+
+    // mpv has read the list once it replies, so the file can go right after
+    let result = ipc::request(
+        socket,
+        &["loadlist", &list_path.to_string_lossy(), "append"],
+    );
+    let _ = std::fs::remove_file(&list_path);
+    result.context("The player refused the appended tracks")?;
+    log::info!("Appended {} tracks to the running player", paths.len());
+    Ok(())
+}
@@ -0,0 +1,362 @@
+use crate::config::Config;
+use crate::indexer::{self, Track};
+use crate::queue;
+use anyhow::{Result, bail};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_PATTERN: &str = "{track:02} - {artist} - {title}";
+
+// illegal on Windows (and '/' everywhere), replaced so one tag can't make a folder
+const ILLEGAL: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+fn sanitize(value: &str) -> String {
+    let cleaned: String = value
+        .chars()
+        .map(|c| {
+            if ILLEGAL.contains(&c) || c.is_control() {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    // Windows drops trailing dots and spaces, "." and ".." aren't names at all
+    let trimmed = cleaned.trim().trim_end_matches('.').trim_end();
+    if trimmed.is_empty() {
+        "_".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+fn field(track: &Track, name: &str) -> Option<String> {
+    let number = |n: Option<u32>| n.map(|n| n.to_string()).unwrap_or_default();
+    let value = match name {
+        "artist" => track.artist.clone(),
+        "album_artist" => queue::album_artist_of(track).to_string(),
+        "album" => track.album.clone(),
+        "title" => track.title.clone(),
+        "genre" => track.genre.clone(),
+        "year" => track.year.map(|y| y.to_string()).unwrap_or_default(),
+        "track" => number(track.track_no),
+        "disc" => number(track.disc_no),
+        _ => return None,
+    };
+    Some(value)
+}
+
+/// Fills `{artist}`, `{album_artist}`, `{album}`, `{title}`, `{genre}`, `{year}`,
+/// `{track}` and `{disc}` into the pattern, `{track:02}` zero-pads. Each value is
+/// sanitized on its own, so only the pattern's own `/` start subfolders. None
+/// when one of the pattern's fields is empty or UNKNOWN, such a track is left be.
+pub fn render(pattern: &str, track: &Track) -> Result<Option<String>> {
+    let mut out = String::new();
+    let mut rest = pattern;
+    while let Some(open) = rest.find('{') {
+        out.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('}') else {
+            bail!("Unclosed '{{' in rename pattern '{}'", pattern);
+        };
+        let spec = &rest[open + 1..open + close];
+        let (name, width) = match spec.split_once(':') {
+            Some((name, width)) => match width.parse::<usize>() {
+                Ok(width) => (name, width),
+                Err(_) => bail!("Bad width in '{{{}}}', use e.g. {{track:02}}", spec),
+            },
+            None => (spec, 0),
+        };
+        let Some(value) = field(track, name) else {
+            bail!("Unknown field '{{{}}}' in rename pattern", name);
+        };
+        if value.trim().is_empty() || value == "UNKNOWN" {
+            return Ok(None);
+        }
+        let padded = if value.chars().all(|c| c.is_ascii_digit()) {
+            format!("{:0>width$}", value, width = width)
+        } else {
+            value
+        };
+        out.push_str(&sanitize(&padded));
+        rest = &rest[open + close + 1..];
+    }
+    out.push_str(rest);
+    Ok(Some(out))
+}
+
+// a pattern with folders in it is laid out below the music dir holding the
+// track, a plain name keeps the file in its folder
+fn target_for(track: &Track, rendered: &str, music_dirs: &[PathBuf]) -> PathBuf {
+    let source = Path::new(&track.path);
+    let folder = source.parent().unwrap_or_else(|| Path::new("."));
+    let base = if rendered.contains('/') {
+        music_dirs
+            .iter()
+            .find(|dir| source.starts_with(dir))
+            .map(PathBuf::as_path)
+            .unwrap_or(folder)
+    } else {
+        folder
+    };
+
+    let mut target = base.to_path_buf();
+    for part in rendered.split('/').filter(|p| !p.trim().is_empty()) {
+        target.push(part.trim());
+    }
+    match source.extension() {
+        Some(ext) => {
+            let name = format!(
+                "{}.{}",
+                target.file_name().unwrap_or_default().to_string_lossy(),
+                ext.to_string_lossy()
+            );
+            target.with_file_name(name)
+        }
+        None => target,
+    }
+}
+
+// "Song.flac" -> "Song (2).flac", "Song (3).flac"... until nothing claims it
+fn disambiguate(target: PathBuf, taken: &HashSet<PathBuf>, source: &Path) -> PathBuf {
+    let free = |p: &Path| p == source || (!taken.contains(p) && !p.exists());
+    if free(&target) {
+        return target;
+    }
+    let stem = target
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string();
+    let ext = target
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (2..)
+        .map(|n| target.with_file_name(format!("{} ({}){}", stem, n, ext)))
+        .find(|candidate| free(candidate))
+        .unwrap_or(target)
+}
+
+// files next to a track that belong to it: "Song.lrc" goes along with "Song.flac"
+const SIDECAR_EXTS: &[&str] = &[
+    "lrc", "txt", "cue", "srt", "ass", "vtt", "jpg", "jpeg", "png",
+];
+
+fn is_sidecar(track: &Path, candidate: &Path) -> bool {
+    candidate != track
+        && candidate.parent() == track.parent()
+        && candidate.file_stem() == track.file_stem()
+        && candidate
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| SIDECAR_EXTS.contains(&e.to_lowercase().as_str()))
+}
+
+// moves the sidecars of `old` next to `new`, keeping their extensions
+fn move_sidecars(old: &Path, new: &Path) {
+    let Ok(entries) = old.parent().unwrap_or(Path::new(".")).read_dir() else {
+        return;
+    };
+    for sidecar in entries.flatten().map(|e| e.path()) {
+        if !is_sidecar(old, &sidecar) {
+            continue;
+        }
+        let target = new.with_extension(sidecar.extension().unwrap_or_default());
+        if target.exists() {
+            log::warn!("Not moving {:?}, {:?} is taken", sidecar, target);
+            continue;
+        }
+        match std::fs::rename(&sidecar, &target) {
+            Ok(()) => log::info!("Moved {:?} -> {:?}", sidecar, target),
+            Err(e) => log::warn!("Could not move {:?}: {}", sidecar, e),
+        }
+    }
+}
+
+/// Plans the renames: (old path, new path) for every track whose name changes,
+/// and how many tracks were left out for missing one of the pattern's fields.
+/// Only audio is renamed.
+fn plan(
+    tracks: &[Track],
+    pattern: &str,
+    music_dirs: &[PathBuf],
+) -> Result<(Vec<(String, PathBuf)>, usize)> {
+    let mut taken = HashSet::new();
+    let mut moves = Vec::new();
+    let mut skipped = 0;
+    for track in tracks.iter().filter(|t| t.media_type == "audio") {
+        let source = Path::new(&track.path);
+        let Some(rendered) = render(pattern, track)? else {
+            log::info!(
+                "Not renaming {}: a field of the pattern is missing",
+                track.path
+            );
+            skipped += 1;
+            continue;
+        };
+        let target = target_for(track, &rendered, music_dirs);
+        let target = disambiguate(target, &taken, source);
+        taken.insert(target.clone());
+        if target != source {
+            moves.push((track.path.clone(), target));
+        }
+    }
+    Ok((moves, skipped))
+}
+
+/// `--rename`: renames the filtered tracks after their tags and moves their
+/// sidecars, index entries and ratings along. The plan is shown first and has
+/// to be confirmed, with `dry_run` it's only shown.
+pub fn run(tracks: &[Track], cfg: &Config, pattern: &str, dry_run: bool) -> Result<()> {
+    let (moves, skipped) = plan(tracks, pattern, &cfg.music_dirs)?;
+    if skipped > 0 {
+        println!(
+            "Leaving {} tracks as they are, they have an empty or UNKNOWN field in '{}'.",
+            skipped, pattern
+        );
+    }
+    if moves.is_empty() {
+        let which = if skipped > 0 { "other" } else { "the" };
+        println!("All {} tracks already match '{}'.", which, pattern);
+        return Ok(());
+    }
+
+    for (old, new) in &moves {
+        println!("  {}\n  -> {}", old, new.display());
+    }
+    if dry_run {
+        println!(
+            "\n{} of {} tracks would be renamed. Run again without --dry-run to do it.",
+            moves.len(),
+            tracks.len()
+        );
+        return Ok(());
+    }
+    if !crate::tui::confirm(&format!("\nRename {} tracks?", moves.len()))? {
+        log::info!("Rename cancelled");
+        return Ok(());
+    }
+
+    // loaded before the renames, loading drops ratings of paths that are gone
+    let mut ratings = crate::ratings::Ratings::load(&cfg.music_dirs);
+    let mut done: Vec<(String, String)> = Vec::new();
+    for (old, new) in &moves {
+        let result = match new.parent() {
+            Some(parent) => std::fs::create_dir_all(parent),
+            None => Ok(()),
+        }
+        .and_then(|()| std::fs::rename(old, new));
+        match result {
+            Ok(()) => {
+                log::info!("Renamed {} -> {:?}", old, new);
+                move_sidecars(Path::new(old), new);
+                done.push((old.clone(), new.to_string_lossy().to_string()));
+            }
+            Err(e) => {
+                log::error!("Could not rename {}: {}", old, e);
+                eprintln!("❌ Could not rename {}: {}", old, e);
+            }
+        }
+    }
+
+    if !done.is_empty() {
+        let renamed: HashMap<&str, &str> = done
+            .iter()
+            .map(|(old, new)| (old.as_str(), new.as_str()))
+            .collect();
+        let (mut index, _) = indexer::load_index(&cfg.index_format)?;
+        for track in &mut index {
+            if let Some(new) = renamed.get(track.path.as_str()) {
+                track.path = new.to_string();
+            }
+        }
+        indexer::save(&index)?;
+        ratings.moved(&done);
+    }
+    println!("\nRenamed {} of {} tracks.", done.len(), moves.len());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track(path: &str, title: &str, track_no: Option<u32>) -> Track {
+        Track {
+            path: path.to_string(),
+            title: title.to_string(),
+            artist: "AC/DC".to_string(),
+            album: "Back in Black".to_string(),
+            track_no,
+            media_type: "audio".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_pattern() {
+        let t = track("/m/x.flac", "What: Now?", Some(3));
+        assert_eq!(
+            render(DEFAULT_PATTERN, &t).unwrap().unwrap(),
+            "03 - AC_DC - What_ Now_"
+        );
+        assert_eq!(
+            render("{artist}/{album}/{track} {title}", &t)
+                .unwrap()
+                .unwrap(),
+            "AC_DC/Back in Black/3 What_ Now_"
+        );
+        // missing fields leave the track alone instead of naming it "_"
+        assert_eq!(
+            render("{track:02}", &track("/m/y", "y", None)).unwrap(),
+            None
+        );
+        assert_eq!(
+            render("{title}", &track("/m/y", "UNKNOWN", None)).unwrap(),
+            None
+        );
+        assert!(render("{bogus}", &t).is_err());
+        assert!(render("{title", &t).is_err());
+    }
+
+    #[test]
+    fn test_plan_collisions_and_folders() {
+        let dirs = [PathBuf::from("/nonexistent/m")];
+        let tracks = [
+            track("/nonexistent/m/a/1.flac", "Song", Some(1)),
+            track("/nonexistent/m/b/2.flac", "Song", Some(1)),
+            track("/nonexistent/m/01 - Song.mp3", "Song", Some(1)),
+            track("/nonexistent/m/untagged.mp3", "Song", None),
+            Track {
+                media_type: "video".to_string(),
+                ..track("/nonexistent/m/clip.mkv", "Clip", Some(1))
+            },
+        ];
+        let (moves, skipped) = plan(&tracks, "{album}/{track:02} - {title}", &dirs).unwrap();
+        assert_eq!(skipped, 1);
+        let targets: Vec<&Path> = moves.iter().map(|(_, p)| p.as_path()).collect();
+        assert_eq!(
+            targets,
+            [
+                Path::new("/nonexistent/m/Back in Black/01 - Song.flac"),
+                Path::new("/nonexistent/m/Back in Black/01 - Song (2).flac"),
+                Path::new("/nonexistent/m/Back in Black/01 - Song.mp3"),
+            ]
+        );
+
+        // already named right: nothing to do
+        let (same, _) = plan(&tracks[2..3], "{track:02} - {title}", &dirs).unwrap();
+        assert!(same.is_empty());
+    }
+
+    #[test]
+    fn test_sidecars_share_the_stem() {
+        let song = Path::new("/m/a/Song.flac");
+        assert!(is_sidecar(song, Path::new("/m/a/Song.lrc")));
+        assert!(is_sidecar(song, Path::new("/m/a/Song.JPG")));
+        assert!(!is_sidecar(song, Path::new("/m/a/Song.flac")));
+        assert!(!is_sidecar(song, Path::new("/m/a/Song.mp3")));
+        assert!(!is_sidecar(song, Path::new("/m/a/Song 2.lrc")));
+        assert!(!is_sidecar(song, Path::new("/m/b/Song.lrc")));
+    }
+}
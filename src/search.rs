@@ -1,3 +1,4 @@
+use crate::config::{self, Config};
 use anyhow::{Context, Result};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
@@ -17,6 +18,41 @@ pub struct SearchResult {
     pub is_playlist: bool,
 }
 
+// "Sign in to confirm your age" and friends, which cookies can get past
+pub fn needs_login(stderr: &str) -> bool {
+    let lower = stderr.to_lowercase();
+    [
+        "confirm your age",
+        "sign in to confirm",
+        "login required",
+        "members-only",
+    ]
+    .iter()
+    .any(|m| lower.contains(m))
+}
+
+/// Explains how to hand yt-dlp cookies, for when a video needs a logged in account.
+pub fn print_cookie_hint() {
+    eprintln!("\n\x1b[33mThis video needs a signed in account (age or login restricted).\x1b[0m");
+    eprintln!("Set one of these in config.toml so yt-dlp can use your cookies:");
+    eprintln!("   ytdlp_cookies = \"/path/to/cookies.txt\"");
+    eprintln!("   ytdlp_cookies_from_browser = \"firefox\"");
+}
+
+// same cookies as playback, so search/listing sees what the account can see
+fn cookie_args(cfg: &Config) -> Vec<String> {
+    config::ytdlp_cookie_options(cfg)
+        .map(|(opt, value)| vec![format!("--{}", opt), value])
+        .unwrap_or_default()
+}
+
+// surfaces the login hint when yt-dlp hit a restriction and no cookies were set
+fn check_login_error(stderr: &str, cfg: &Config) {
+    if needs_login(stderr) && config::ytdlp_cookie_options(cfg).is_none() {
+        print_cookie_hint();
+    }
+}
+
 // Helper: Format seconds into MM:SS
 fn format_duration(seconds: f64) -> String {
     let m = (seconds / 60.0).floor();
@@ -35,12 +71,98 @@ fn format_views(count: u64) -> String {
     }
 }
 
+// shared by search results and expanded playlist entries
+fn build_result(v: &Value, title: String, url: String) -> SearchResult {
+    // Uploader / Channel Name
+    let uploader = v["uploader"]
+        .as_str()
+        .or_else(|| v["channel"].as_str())
+        .unwrap_or("Unknown Channel")
+        .to_string();
+
+    // Duration: Seconds -> MM:SS
+    let duration = if let Some(seconds) = v["duration"].as_f64() {
+        format_duration(seconds)
+    } else {
+        "LIVE/???".to_string()
+    };
+
+    // Views: 1200000 -> 1.2M
+    let views = if let Some(count) = v["view_count"].as_u64() {
+        format_views(count)
+    } else {
+        "N/A".to_string()
+    };
+
+    let is_playlist = url.contains("playlist?list=") || v["_type"].as_str() == Some("playlist");
+
+    SearchResult {
+        title,
+        url,
+        uploader,
+        duration,
+        view_count: views,
+        is_playlist,
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 struct CacheEntry {
     timestamp: u64,
+    #[serde(default)]
+    last_access: u64, // 0 on entries written before LRU, falls back to timestamp
     results: Vec<SearchResult>,
 }
 
+impl CacheEntry {
+    fn last_used(&self) -> u64 {
+        self.last_access.max(self.timestamp)
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// Drops least recently used queries until at most `max` remain (0 = no limit).
+/// Returns whether anything was evicted.
+fn evict_lru(cache: &mut HashMap<String, CacheEntry>, max: usize) -> bool {
+    if max == 0 || cache.len() <= max {
+        return false;
+    }
+    let mut by_age: Vec<(u64, String)> = cache
+        .iter()
+        .map(|(k, v)| (v.last_used(), k.clone()))
+        .collect();
+    by_age.sort();
+
+    let excess = cache.len() - max;
+    for (_, key) in by_age.into_iter().take(excess) {
+        cache.remove(&key);
+    }
+    log::debug!(
+        "Search cache: evicted {} least recently used queries",
+        excess
+    );
+    true
+}
+
+/// Deletes the search cache file. Returns false if there was none.
+pub fn clear_cache() -> Result<bool> {
+    let Some(path) = get_cache_path() else {
+        return Ok(false);
+    };
+    if !path.exists() {
+        return Ok(false);
+    }
+    fs::remove_file(&path).with_context(|| format!("Failed to remove {:?}", path))?;
+    log::info!("Search cache cleared: {:?}", path);
+    Ok(true)
+}
+
 fn get_cache_path() -> Option<std::path::PathBuf> {
     ProjectDirs::from("com", "furqanhun", "mpv-music")
         .map(|dirs| dirs.data_dir().join("yt_cache.json"))
@@ -56,10 +178,7 @@ fn load_cache() -> (HashMap<String, CacheEntry>, bool) {
         && let Ok(content) = fs::read_to_string(&path)
         && let Ok(map) = serde_json::from_str::<HashMap<String, CacheEntry>>(&content)
     {
-        let now = SystemTime::now()
-            .duration_since(SystemTime::UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs();
+        let now = now_secs();
         let initial_len = map.len();
         let pruned_map: HashMap<String, CacheEntry> = map
             .into_iter()
@@ -80,7 +199,9 @@ fn save_cache(cache: &HashMap<String, CacheEntry>) {
         }
         match serde_json::to_string(cache) {
             Ok(content) => {
-                let temp_path = path.with_extension("tmp");
+                // per-process temp name, so two instances saving at once can't
+                // interleave writes; the rename swaps the whole file atomically
+                let temp_path = path.with_extension(format!("{}.tmp", std::process::id()));
                 if let Err(e) = fs::write(&temp_path, content) {
                     log::error!("Failed to write cache file {:?}: {}", temp_path, e);
                 } else if let Err(e) = fs::rename(&temp_path, &path) {
@@ -97,7 +218,7 @@ fn save_cache(cache: &HashMap<String, CacheEntry>) {
 }
 
 /// Returns a list of parsed search results, ignoring channels, mixes, and shorts.
-pub fn search_youtube(query: &str, limit: usize) -> Result<Vec<SearchResult>> {
+pub fn search_youtube(query: &str, limit: usize, cfg: &Config) -> Result<Vec<SearchResult>> {
     log::info!(
         "Starting YouTube search for: '{}' (Limit: {})",
         query,
@@ -105,14 +226,16 @@ pub fn search_youtube(query: &str, limit: usize) -> Result<Vec<SearchResult>> {
     );
 
     let cache_key = format!("{}|{}", query, limit);
-    let (mut cache, was_pruned) = load_cache();
+    let (mut cache, _) = load_cache();
 
-    if let Some(entry) = cache.get(&cache_key) {
+    if let Some(entry) = cache.get_mut(&cache_key) {
         log::info!("Cache hit for YouTube search: '{}'", query);
-        if was_pruned {
-            save_cache(&cache);
-        }
-        return Ok(entry.results.clone());
+        entry.last_access = now_secs();
+        let results = entry.results.clone();
+        evict_lru(&mut cache, cfg.search_cache_max_entries);
+        // always saved, the access time is what LRU eviction goes by
+        save_cache(&cache);
+        return Ok(results);
     }
 
     let search_url = format!(
@@ -130,13 +253,16 @@ pub fn search_youtube(query: &str, limit: usize) -> Result<Vec<SearchResult>> {
     log::debug!("Exec: yt-dlp {:?}", args);
 
     let output = Command::new("yt-dlp")
+        .args(cookie_args(cfg))
         .args(args)
         .output()
         .context("Failed to execute yt-dlp search")?;
 
     if !output.status.success() {
         log::warn!("yt-dlp exited with error status");
-        log::debug!("yt-dlp stderr: {}", String::from_utf8_lossy(&output.stderr));
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        log::debug!("yt-dlp stderr: {}", stderr);
+        check_login_error(&stderr, cfg);
     }
 
     let stdout = String::from_utf8_lossy(&output.stdout);
@@ -191,38 +317,7 @@ pub fn search_youtube(query: &str, limit: usize) -> Result<Vec<SearchResult>> {
                 continue;
             }
 
-            // Uploader / Channel Name
-            let uploader = v["uploader"]
-                .as_str()
-                .or_else(|| v["channel"].as_str())
-                .unwrap_or("Unknown Channel")
-                .to_string();
-
-            // Duration: Seconds -> MM:SS
-            let duration = if let Some(seconds) = v["duration"].as_f64() {
-                format_duration(seconds)
-            } else {
-                "LIVE/???".to_string()
-            };
-
-            // Views: 1200000 -> 1.2M
-            let views = if let Some(count) = v["view_count"].as_u64() {
-                format_views(count)
-            } else {
-                "N/A".to_string()
-            };
-
-            let is_playlist =
-                url.contains("playlist?list=") || v["_type"].as_str() == Some("playlist");
-
-            results.push(SearchResult {
-                title,
-                url,
-                uploader,
-                duration,
-                view_count: views,
-                is_playlist,
-            });
+            results.push(build_result(&v, title, url));
         }
     }
     log::info!(
@@ -234,27 +329,201 @@ pub fn search_youtube(query: &str, limit: usize) -> Result<Vec<SearchResult>> {
         stats_bad_url
     );
 
-    let now = SystemTime::now()
-        .duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_secs();
+    let now = now_secs();
 
     cache.insert(
         cache_key,
         CacheEntry {
             timestamp: now,
+            last_access: now,
             results: results.clone(),
         },
     );
+    evict_lru(&mut cache, cfg.search_cache_max_entries);
     save_cache(&cache);
 
     Ok(results)
 }
 
+/// Lists the entries of an online playlist (in playlist order) without resolving them.
+pub fn list_playlist(url: &str, cfg: &Config) -> Result<Vec<SearchResult>> {
+    log::info!("Expanding playlist: {}", url);
+
+    let args = ["--flat-playlist", "--dump-json", "--ignore-errors", url];
+    log::debug!("Exec: yt-dlp {:?}", args);
+
+    let output = Command::new("yt-dlp")
+        .args(cookie_args(cfg))
+        .args(args)
+        .output()
+        .context("Failed to execute yt-dlp")?;
+
+    if !output.status.success() {
+        log::warn!("yt-dlp exited with error status while expanding playlist");
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        log::debug!("yt-dlp stderr: {}", stderr);
+        check_login_error(&stderr, cfg);
+    }
+
+    let entries = parse_playlist_entries(&String::from_utf8_lossy(&output.stdout));
+    log::info!("Playlist expanded to {} entries", entries.len());
+    Ok(entries)
+}
+
+/// One row of `yt-dlp -F`: the format id and the row as yt-dlp printed it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormatEntry {
+    pub id: String,
+    pub line: String,
+}
+
+/// Lists the formats available for a URL, plus the table header line for display.
+pub fn list_formats(url: &str, cfg: &Config) -> Result<(String, Vec<FormatEntry>)> {
+    log::info!("Listing formats: {}", url);
+
+    let args = ["-F", "--no-playlist", url];
+    log::debug!("Exec: yt-dlp {:?}", args);
+
+    let output = Command::new("yt-dlp")
+        .args(cookie_args(cfg))
+        .args(args)
+        .output()
+        .context("Failed to execute yt-dlp")?;
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        log::debug!("yt-dlp stderr: {}", stderr);
+        check_login_error(&stderr, cfg);
+        anyhow::bail!("yt-dlp could not list formats: {}", ytdlp_error(&stderr));
+    }
+
+    let (header, formats) = parse_format_table(&String::from_utf8_lossy(&output.stdout));
+    if formats.is_empty() {
+        anyhow::bail!("yt-dlp returned no formats for {}", url);
+    }
+    log::info!("Found {} formats", formats.len());
+    Ok((header, formats))
+}
+
+// the useful part of a failure ("Sign in to confirm your age", "Video unavailable"...)
+fn ytdlp_error(stderr: &str) -> String {
+    stderr
+        .lines()
+        .rev()
+        .find_map(|l| l.trim().strip_prefix("ERROR:"))
+        .map(str::trim)
+        .unwrap_or_else(|| stderr.trim().lines().last().unwrap_or("unknown error"))
+        .to_string()
+}
+
+// the table is "ID EXT RESOLUTION ..." then a rule of dashes, then one row per format
+fn parse_format_table(stdout: &str) -> (String, Vec<FormatEntry>) {
+    let lines: Vec<&str> = stdout.lines().collect();
+    let Some(rule) = lines.iter().position(|l| {
+        let l = l.trim();
+        l.len() > 10 && l.chars().all(|c| matches!(c, '-' | '─' | ' ' | '|' | '│'))
+    }) else {
+        return (String::new(), Vec::new());
+    };
+
+    let header = rule
+        .checked_sub(1)
+        .map(|i| lines[i].to_string())
+        .unwrap_or_default();
+    let formats = lines[rule + 1..]
+        .iter()
+        .filter_map(|line| {
+            let id = line.split_whitespace().next()?;
+            Some(FormatEntry {
+                id: id.to_string(),
+                line: line.to_string(),
+            })
+        })
+        .collect();
+    (header, formats)
+}
+
+fn parse_playlist_entries(stdout: &str) -> Vec<SearchResult> {
+    stdout
+        .lines()
+        .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+        .filter_map(|v| {
+            let url = v["url"]
+                .as_str()
+                .or_else(|| v["webpage_url"].as_str())
+                .filter(|u| !u.is_empty())?
+                .to_string();
+            let title = v["title"].as_str().unwrap_or("Unknown Title").to_string();
+            Some(build_result(&v, title, url))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn entry(timestamp: u64, last_access: u64) -> CacheEntry {
+        CacheEntry {
+            timestamp,
+            last_access,
+            results: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_evict_lru_keeps_recently_used() {
+        let mut cache = HashMap::new();
+        cache.insert("old".to_string(), entry(100, 100));
+        cache.insert("old-but-reused".to_string(), entry(100, 900));
+        cache.insert("new".to_string(), entry(500, 500));
+        cache.insert("legacy".to_string(), entry(300, 0));
+
+        assert!(evict_lru(&mut cache, 2));
+        let mut kept: Vec<&str> = cache.keys().map(String::as_str).collect();
+        kept.sort();
+        assert_eq!(kept, ["new", "old-but-reused"]);
+
+        assert!(!evict_lru(&mut cache, 2));
+        assert!(!evict_lru(&mut cache, 0));
+    }
+
+    #[test]
+    fn test_parse_format_table() {
+        let stdout = "[youtube] Extracting URL: https://youtu.be/x\n\
+[info] Available formats for x:\n\
+ID  EXT   RESOLUTION FPS │   FILESIZE   TBR PROTO │ VCODEC        ACODEC\n\
+─────────────────────────────────────────────────────────────────────\n\
+139 m4a   audio only     │    1.2MiB   49k https │ audio only    mp4a.40.5\n\
+251 webm  audio only     │    3.4MiB  130k https │ audio only    opus\n\
+137 mp4   1920x1080   30 │   40.1MiB 1500k https │ avc1.640028   video only\n";
+
+        let (header, formats) = parse_format_table(stdout);
+        assert!(header.starts_with("ID  EXT"));
+        let ids: Vec<&str> = formats.iter().map(|f| f.id.as_str()).collect();
+        assert_eq!(ids, ["139", "251", "137"]);
+        assert!(formats[2].line.contains("video only"));
+
+        assert!(parse_format_table("ERROR: nothing here").1.is_empty());
+    }
+
+    #[test]
+    fn test_login_errors_detected() {
+        assert!(needs_login(
+            "ERROR: [youtube] abc: Sign in to confirm your age. This video may be inappropriate"
+        ));
+        assert!(!needs_login("ERROR: [youtube] abc: Video unavailable"));
+    }
+
+    #[test]
+    fn test_ytdlp_error_message() {
+        let stderr = "WARNING: something\nERROR: [youtube] x: Sign in to confirm your age\n";
+        assert_eq!(
+            ytdlp_error(stderr),
+            "[youtube] x: Sign in to confirm your age"
+        );
+    }
+
     #[test]
     fn test_search_result_creation() {
         let result = SearchResult {
@@ -333,6 +602,20 @@ mod tests {
         assert!(url.contains("list=RD"));
     }
 
+    #[test]
+    fn test_parse_playlist_entries_keeps_order() {
+        let stdout = r#"{"title": "One", "url": "https://www.youtube.com/watch?v=1", "duration": 61}
+not json
+{"title": "Broken", "url": ""}
+{"title": "Two", "url": "https://www.youtube.com/watch?v=2", "channel": "Chan"}"#;
+
+        let entries = parse_playlist_entries(stdout);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].title, "One");
+        assert_eq!(entries[0].duration, "01:01");
+        assert_eq!(entries[1].uploader, "Chan");
+    }
+
     #[test]
     fn test_url_channel_detection() {
         assert!("https://youtube.com/channel/UC123".contains("/channel/"));
@@ -0,0 +1,140 @@
+use crate::config::Config;
+use crate::indexer::Track;
+use crate::tui;
+use anyhow::Result;
+use serde::Serialize;
+use unicode_width::UnicodeWidthStr;
+
+// eighths of a cell, so small counts still show a sliver
+const PARTIALS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+#[derive(Serialize)]
+struct Row<'a> {
+    name: &'a str,
+    count: usize,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    key: &'a str,
+    tracks: usize,
+    groups: usize,
+    rows: Vec<Row<'a>>,
+}
+
+/// A bar `width` cells long for the biggest count, the rest in proportion.
+fn bar(count: usize, max: usize, width: usize) -> String {
+    if max == 0 || width == 0 {
+        return String::new();
+    }
+    let eighths = (count * width * 8).div_ceil(max);
+    let mut bar = "█".repeat(eighths / 8);
+    if !eighths.is_multiple_of(8) {
+        bar.push(PARTIALS[eighths % 8]);
+    }
+    bar
+}
+
+/// `--genre-stats`, `--artist-stats`, `--album-stats`: tracks per tag as a
+/// bar chart, or one JSON object with `json`. `top` keeps the biggest N.
+pub fn run(
+    tracks: &[Track],
+    cfg: &Config,
+    key: &str,
+    top: Option<usize>,
+    json: bool,
+) -> Result<()> {
+    let counts = tui::tag_counts(tracks, cfg, key);
+    let groups = counts.len();
+    let shown = &counts[..top.unwrap_or(groups).min(groups)];
+    let songs = tracks.iter().filter(|t| t.media_type != "playlist").count();
+
+    if json {
+        let report = Report {
+            key,
+            tracks: songs,
+            groups,
+            rows: shown
+                .iter()
+                .map(|(name, count)| Row {
+                    name,
+                    count: *count,
+                })
+                .collect(),
+        };
+        println!("{}", serde_json::to_string(&report)?);
+        return Ok(());
+    }
+
+    if shown.is_empty() {
+        println!("No tracks to count.");
+        return Ok(());
+    }
+
+    let name_width = shown
+        .iter()
+        .map(|(name, _)| name.width())
+        .max()
+        .unwrap_or(0)
+        .min(40);
+    let max = shown[0].1;
+    let count_width = max.to_string().len();
+    let cols = crossterm::terminal::size().map_or(80, |(c, _)| c as usize);
+    let bar_width = cols
+        .saturating_sub(name_width + count_width + 4)
+        .clamp(10, 60);
+
+    for (name, count) in shown {
+        let name = fit(name, name_width);
+        let pad = name_width.saturating_sub(name.width());
+        println!(
+            "{}{}  {:>cw$} {}",
+            name,
+            " ".repeat(pad),
+            count,
+            bar(*count, max, bar_width),
+            cw = count_width
+        );
+    }
+
+    let hidden = groups - shown.len();
+    if hidden > 0 {
+        println!("… {} more", hidden);
+    }
+    println!("\n{} tracks across {} {}(s)", songs, groups, key);
+    Ok(())
+}
+
+// long names are cut with an ellipsis so the bars stay lined up
+fn fit(name: &str, width: usize) -> String {
+    if name.width() <= width {
+        return name.to_string();
+    }
+    let mut out = String::new();
+    for c in name.chars() {
+        if out.width() + c.to_string().width() + 1 > width {
+            break;
+        }
+        out.push(c);
+    }
+    out.push('…');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bars_and_names() {
+        assert_eq!(bar(10, 10, 4), "████");
+        assert_eq!(bar(5, 10, 4), "██");
+        // 1/10 of 4 cells is 3.2 eighths, rounded up to half a cell
+        assert_eq!(bar(1, 10, 4), "▌");
+        assert_eq!(bar(0, 10, 4), "");
+        assert_eq!(bar(3, 0, 4), "");
+
+        assert_eq!(fit("Jazz", 10), "Jazz");
+        assert_eq!(fit("Progressive Rock", 8), "Progres…");
+    }
+}
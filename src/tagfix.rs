@@ -0,0 +1,315 @@
+use crate::indexer::Track;
+use crate::queue;
+use anyhow::{Context, Result};
+use lofty::config::WriteOptions;
+use lofty::prelude::*;
+use lofty::probe::Probe;
+use lofty::tag::Tag;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const UNKNOWN: &str = "UNKNOWN";
+
+#[derive(Default, Debug, PartialEq)]
+pub struct TagFix {
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub genre: Option<String>,
+}
+
+impl TagFix {
+    fn is_empty(&self) -> bool {
+        self.artist.is_none() && self.album.is_none() && self.genre.is_none()
+    }
+
+    fn summary(&self) -> String {
+        [
+            ("artist", &self.artist),
+            ("album", &self.album),
+            ("genre", &self.genre),
+        ]
+        .iter()
+        .filter_map(|(k, v)| v.as_ref().map(|v| format!("{}='{}'", k, v)))
+        .collect::<Vec<_>>()
+        .join(", ")
+    }
+}
+
+pub fn needs_fix(track: &Track) -> bool {
+    track.media_type != "playlist"
+        && [&track.artist, &track.album, &track.genre]
+            .iter()
+            .any(|v| v.as_str() == UNKNOWN)
+}
+
+/// Everything we know about a track, one field per line.
+pub fn describe(track: &Track) -> String {
+    let num = |n: Option<u32>| n.map(|n| n.to_string()).unwrap_or_else(|| "-".to_string());
+    format!(
+        "  Path:         {}\n  Title:        {}\n  Artist:       {}\n  Album:        {}\n  Album Artist: {}\n  Genre:        {}\n  Disc / Track: {} / {}",
+        track.path,
+        track.title,
+        track.artist,
+        track.album,
+        if track.album_artist.is_empty() {
+            "-"
+        } else {
+            &track.album_artist
+        },
+        track.genre,
+        num(track.disc_no),
+        num(track.track_no),
+    )
+}
+
+/// Guesses artist/album from `<root>/Artist/Album[/CD1]/file`. Only folders below
+/// a music dir count, so the music dir itself never becomes an artist.
+pub fn derive_from_path(path: &Path, roots: &[PathBuf]) -> TagFix {
+    let Some(relative) = roots.iter().find_map(|r| path.strip_prefix(r).ok()) else {
+        return TagFix::default();
+    };
+
+    let mut dirs: Vec<String> = relative
+        .parent()
+        .map(|p| {
+            p.components()
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Album/CD1/track.flac -> the disc folder isn't the album
+    if dirs.len() > 1 && queue::disc_from_dir(&relative.to_string_lossy()).is_some() {
+        dirs.pop();
+    }
+
+    let mut fix = TagFix::default();
+    match dirs.as_slice() {
+        [.., artist, album] => {
+            fix.artist = Some(artist.clone());
+            fix.album = Some(album.clone());
+        }
+        [album] => fix.album = Some(album.clone()),
+        [] => {}
+    }
+    fix
+}
+
+// keep only suggestions for fields that are actually UNKNOWN
+fn unknown_only(track: &Track, fix: TagFix) -> TagFix {
+    let keep = |current: &str, v: Option<String>| v.filter(|_| current == UNKNOWN);
+    TagFix {
+        artist: keep(&track.artist, fix.artist),
+        album: keep(&track.album, fix.album),
+        genre: keep(&track.genre, fix.genre),
+    }
+}
+
+/// Writes the given fields into the file's primary tag (creating one if needed).
+pub fn write_tags(path: &Path, fix: &TagFix) -> Result<()> {
+    let mut tagged = Probe::open(path)
+        .and_then(|p| p.read())
+        .with_context(|| format!("Could not read tags from {:?}", path))?;
+
+    if tagged.primary_tag().is_none() {
+        let tag_type = tagged.primary_tag_type();
+        tagged.insert_tag(Tag::new(tag_type));
+    }
+    let tag = tagged
+        .primary_tag_mut()
+        .context("File format has no writable tag")?;
+
+    if let Some(artist) = &fix.artist {
+        tag.set_artist(artist.clone());
+    }
+    if let Some(album) = &fix.album {
+        tag.set_album(album.clone());
+    }
+    if let Some(genre) = &fix.genre {
+        tag.set_genre(genre.clone());
+    }
+
+    tag.save_to_path(path, WriteOptions::default())
+        .with_context(|| format!("Could not write tags to {:?}", path))?;
+    log::info!("Tags updated for {:?}: {}", path, fix.summary());
+    Ok(())
+}
+
+fn prompt(label: &str) -> Result<String> {
+    print!("{}", label);
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(input.trim().to_string())
+}
+
+enum Answer {
+    Value(Option<String>),
+    Skip,
+    Quit,
+}
+
+fn ask_field(name: &str, current: &str, suggestion: Option<&String>) -> Result<Answer> {
+    if current != UNKNOWN {
+        return Ok(Answer::Value(None));
+    }
+    let label = match suggestion {
+        Some(s) => format!("  {} (Enter = '{}') > ", name, s),
+        None => format!("  {} (Enter = keep) > ", name),
+    };
+    Ok(match prompt(&label)?.as_str() {
+        "s" => Answer::Skip,
+        "q" => Answer::Quit,
+        "" => Answer::Value(suggestion.cloned()),
+        v => Answer::Value(Some(v.to_string())),
+    })
+}
+
+/// Walks every track with an UNKNOWN field and asks for corrections.
+/// Returns how many files were rewritten.
+pub fn run_interactive(tracks: &[&Track], roots: &[PathBuf]) -> Result<usize> {
+    println!("\n🏷️  --- Fix Tags ({} tracks) ---", tracks.len());
+    println!("Enter a value, ENTER to accept the suggestion, 's' to skip, 'q' to stop.\n");
+
+    let mut fixed = 0;
+    'tracks: for (i, track) in tracks.iter().enumerate() {
+        println!("[{}/{}]\n{}\n", i + 1, tracks.len(), describe(track));
+        let suggested = derive_from_path(Path::new(&track.path), roots);
+
+        let mut fix = TagFix::default();
+        for (name, current, suggestion, slot) in [
+            (
+                "Artist",
+                &track.artist,
+                suggested.artist.as_ref(),
+                &mut fix.artist,
+            ),
+            (
+                "Album",
+                &track.album,
+                suggested.album.as_ref(),
+                &mut fix.album,
+            ),
+            (
+                "Genre",
+                &track.genre,
+                suggested.genre.as_ref(),
+                &mut fix.genre,
+            ),
+        ] {
+            match ask_field(name, current, suggestion)? {
+                Answer::Value(v) => *slot = v,
+                Answer::Skip => {
+                    println!();
+                    continue 'tracks;
+                }
+                Answer::Quit => break 'tracks,
+            }
+        }
+
+        if fix.is_empty() {
+            println!("  Nothing changed.\n");
+            continue;
+        }
+        match write_tags(Path::new(&track.path), &fix) {
+            Ok(()) => {
+                fixed += 1;
+                println!("  ✅ Saved.\n");
+            }
+            Err(e) => {
+                log::warn!("{:#}", e);
+                eprintln!("  ❌ {:#}\n", e);
+            }
+        }
+    }
+    Ok(fixed)
+}
+
+/// Derives artist/album from folders for every track at once, after one confirmation.
+pub fn run_from_path(tracks: &[&Track], roots: &[PathBuf]) -> Result<usize> {
+    let plan: Vec<(&Track, TagFix)> = tracks
+        .iter()
+        .map(|t| {
+            (
+                *t,
+                unknown_only(t, derive_from_path(Path::new(&t.path), roots)),
+            )
+        })
+        .filter(|(_, fix)| !fix.is_empty())
+        .collect();
+
+    if plan.is_empty() {
+        println!("Folder names didn't give anything to fill in.");
+        return Ok(0);
+    }
+
+    for (track, fix) in plan.iter().take(20) {
+        println!("  {}\n    -> {}", track.path, fix.summary());
+    }
+    if plan.len() > 20 {
+        println!("  ...and {} more", plan.len() - 20);
+    }
+
+    let answer = prompt(&format!("\nWrite tags to {} files? [y/N] > ", plan.len()))?;
+    if !answer.eq_ignore_ascii_case("y") {
+        println!("Aborted, nothing written.");
+        return Ok(0);
+    }
+
+    let mut fixed = 0;
+    for (track, fix) in &plan {
+        match write_tags(Path::new(&track.path), fix) {
+            Ok(()) => fixed += 1,
+            Err(e) => log::warn!("{:#}", e),
+        }
+    }
+    println!("Updated {} of {} files.", fixed, plan.len());
+    Ok(fixed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unknown_track(path: &str) -> Track {
+        Track {
+            path: path.to_string(),
+            title: "Song".to_string(),
+            artist: UNKNOWN.to_string(),
+            album: UNKNOWN.to_string(),
+            genre: "Rock".to_string(),
+            media_type: "audio".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_derive_artist_album_from_folders() {
+        let roots = vec![PathBuf::from("/music")];
+        let fix = derive_from_path(Path::new("/music/Ado/Kyougen/01.flac"), &roots);
+        assert_eq!(fix.artist.as_deref(), Some("Ado"));
+        assert_eq!(fix.album.as_deref(), Some("Kyougen"));
+
+        // disc folders are skipped
+        let fix = derive_from_path(Path::new("/music/Ado/Kyougen/CD2/01.flac"), &roots);
+        assert_eq!(fix.album.as_deref(), Some("Kyougen"));
+
+        // loose files and files outside music dirs give nothing
+        assert!(derive_from_path(Path::new("/music/01.flac"), &roots).is_empty());
+        assert!(derive_from_path(Path::new("/elsewhere/A/B/01.flac"), &roots).is_empty());
+    }
+
+    #[test]
+    fn test_only_unknown_fields_are_filled() {
+        let mut track = unknown_track("/music/Ado/Kyougen/01.flac");
+        track.artist = "Ado".to_string();
+
+        let fix = unknown_only(
+            &track,
+            derive_from_path(Path::new(&track.path), &[PathBuf::from("/music")]),
+        );
+        assert_eq!(fix.artist, None);
+        assert_eq!(fix.album.as_deref(), Some("Kyougen"));
+        assert!(needs_fix(&track));
+    }
+}
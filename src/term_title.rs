@@ -0,0 +1,143 @@
+use crate::config::Config;
+use serde_json::Value;
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// xterm's title stack: save before the first title, put it back afterwards.
+// Terminals without the stack ignore both, which leaves the cleared title.
+const PUSH_TITLE: &str = "\x1b[22;0t";
+const POP_TITLE: &str = "\x1b[23;0t";
+
+// set while a pushed title waits to be popped, so the Ctrl+C handler and the
+// guard's drop don't both pop it
+static SAVED: AtomicBool = AtomicBool::new(false);
+
+// TERMs known to print the escape sequence instead of setting a title
+fn term_supports(term: Option<&str>) -> bool {
+    match term {
+        Some(t) => !matches!(t, "" | "dumb" | "linux" | "cons25" | "emacs"),
+        // Windows terminals don't set TERM but handle OSC titles fine
+        None => cfg!(windows),
+    }
+}
+
+/// Whether playback should put "Artist - Title" in the terminal title.
+pub fn enabled(config: &Config) -> bool {
+    config.set_terminal_title
+        && std::io::stdout().is_terminal()
+        && term_supports(std::env::var("TERM").ok().as_deref())
+}
+
+fn emit(seq: &str) {
+    let mut out = std::io::stdout();
+    let _ = out.write_all(seq.as_bytes());
+    let _ = out.flush();
+}
+
+// control characters would end the OSC early (or start a new sequence)
+fn sanitize(title: &str) -> String {
+    title.chars().filter(|c| !c.is_control()).collect()
+}
+
+pub fn set(title: &str) {
+    emit(&format!("\x1b]0;{}\x07", sanitize(title)));
+}
+
+/// Clears the title and pops the one saved by `Guard::new`, once per save.
+pub fn restore() {
+    if !SAVED.swap(false, Ordering::SeqCst) {
+        return;
+    }
+    emit(&format!("\x1b]0;\x07{}", POP_TITLE));
+    log::debug!("Terminal title restored");
+}
+
+/// Saves the current title and restores it when dropped (playback over).
+pub struct Guard;
+
+impl Guard {
+    pub fn new() -> Self {
+        if !SAVED.swap(true, Ordering::SeqCst) {
+            emit(PUSH_TITLE);
+        }
+        Guard
+    }
+}
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        restore();
+    }
+}
+
+/// Builds the title from mpv's `path`, `metadata` and `media-title`. Streams and
+/// untagged files have no artist, so they get the media title alone.
+#[derive(Default)]
+pub struct NowPlaying {
+    artist: Option<String>,
+    title: Option<String>,
+}
+
+impl NowPlaying {
+    /// Feeds one property change, returns the new title if there is one to show.
+    pub fn update(&mut self, name: &str, data: &Value) -> Option<String> {
+        match name {
+            // a new file: the last one's artist mustn't stick to its title
+            "path" => {
+                self.artist = None;
+                self.title = None;
+                return None;
+            }
+            "metadata" => {
+                // tag keys come in whatever case the container uses
+                self.artist = data.as_object().and_then(|tags| {
+                    tags.iter()
+                        .find(|(k, _)| k.eq_ignore_ascii_case("artist"))
+                        .and_then(|(_, v)| v.as_str())
+                        .map(|s| s.trim().to_string())
+                        .filter(|s| !s.is_empty())
+                });
+            }
+            "media-title" => self.title = data.as_str().map(String::from),
+            _ => return None,
+        }
+        let title = self.title.as_deref()?;
+        Some(match &self.artist {
+            Some(artist) if !title.starts_with(artist.as_str()) => {
+                format!("{} - {}", artist, title)
+            }
+            _ => title.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_now_playing_title() {
+        let mut np = NowPlaying::default();
+        assert_eq!(np.update("metadata", &json!({"ARTIST": "Daft Punk"})), None);
+        assert_eq!(
+            np.update("media-title", &json!("One More Time")).as_deref(),
+            Some("Daft Punk - One More Time")
+        );
+
+        // a stream without tags shows mpv's media-title as is
+        assert_eq!(np.update("path", &json!("https://x/live")), None);
+        assert_eq!(
+            np.update("media-title", &json!("lofi radio")).as_deref(),
+            Some("lofi radio")
+        );
+    }
+
+    #[test]
+    fn test_title_guards() {
+        assert_eq!(sanitize("a\x07b\x1b]0;c"), "ab]0;c");
+        assert!(term_supports(Some("xterm-256color")));
+        assert!(!term_supports(Some("dumb")));
+        assert!(!term_supports(Some("linux")));
+    }
+}
@@ -0,0 +1,184 @@
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+// long enough to skip the probes across a day of launches, short enough to
+// notice a freshly installed (or removed) tool the same day
+const TTL_SECS: u64 = 6 * 3600;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub enum JsRuntime {
+    Deno,
+    Node,
+    QuickJs,
+    Bun,
+    Missing,
+}
+
+impl JsRuntime {
+    /// Value for yt-dlp's `js-runtimes=` option. Deno is yt-dlp's default, so it needs none.
+    pub fn ytdl_name(self) -> Option<&'static str> {
+        match self {
+            JsRuntime::Node => Some("node"),
+            JsRuntime::QuickJs => Some("quickjs"),
+            JsRuntime::Bun => Some("bun"),
+            JsRuntime::Deno | JsRuntime::Missing => None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Deps {
+    pub ytdlp_available: bool,
+    pub ytdlp_is_nightly: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy)]
+struct Stamped<T> {
+    timestamp: u64,
+    value: T,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct ToolCache {
+    deps: Option<Stamped<Deps>>,
+    js_runtime: Option<Stamped<JsRuntime>>,
+}
+
+// per-process answer, so a session never probes twice
+static JS_RUNTIME: Mutex<Option<JsRuntime>> = Mutex::new(None);
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+fn cache_path() -> Option<PathBuf> {
+    ProjectDirs::from("com", "furqanhun", "mpv-music")
+        .map(|dirs| dirs.data_dir().join("tool_cache.json"))
+}
+
+/// Forgets the cached tool checks, the next launch probes again. Returns false
+/// if there was nothing cached.
+pub fn clear() -> std::io::Result<bool> {
+    let Some(path) = cache_path().filter(|p| p.exists()) else {
+        return Ok(false);
+    };
+    std::fs::remove_file(path)?;
+    Ok(true)
+}
+
+fn load() -> ToolCache {
+    cache_path()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn update(edit: impl FnOnce(&mut ToolCache)) {
+    let Some(path) = cache_path() else {
+        return;
+    };
+    let mut cache = load();
+    edit(&mut cache);
+
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    match serde_json::to_string(&cache) {
+        Ok(content) => {
+            if let Err(e) = std::fs::write(&path, content) {
+                log::debug!("Could not write tool cache {:?}: {}", path, e);
+            }
+        }
+        Err(e) => log::debug!("Could not serialize tool cache: {}", e),
+    }
+}
+
+fn fresh<T: Copy>(entry: Option<Stamped<T>>, now: u64) -> Option<T> {
+    entry
+        .filter(|e| now.saturating_sub(e.timestamp) < TTL_SECS)
+        .map(|e| e.value)
+}
+
+/// Last dependency check, if it's recent enough to trust.
+pub fn cached_deps() -> Option<Deps> {
+    fresh(load().deps, now())
+}
+
+pub fn store_deps(deps: Deps) {
+    update(|c| {
+        c.deps = Some(Stamped {
+            timestamp: now(),
+            value: deps,
+        })
+    });
+}
+
+/// The JS runtime yt-dlp should use. Memory first, then the on-disk cache,
+/// and only then `detect` (whose answer is remembered in memory, and on disk
+/// when it found one).
+pub fn js_runtime(detect: impl FnOnce() -> JsRuntime) -> JsRuntime {
+    let mut memo = JS_RUNTIME.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(runtime) = *memo {
+        return runtime;
+    }
+
+    let runtime = match fresh(load().js_runtime, now()) {
+        Some(cached) => {
+            log::debug!("JS runtime (cached): {:?}", cached);
+            cached
+        }
+        None => {
+            let detected = detect();
+            // a missing runtime is probed again next launch, it may get installed any time
+            if detected != JsRuntime::Missing {
+                update(|c| {
+                    c.js_runtime = Some(Stamped {
+                        timestamp: now(),
+                        value: detected,
+                    })
+                });
+            }
+            detected
+        }
+    };
+
+    *memo = Some(runtime);
+    runtime
+}
+
+/// Drops the remembered runtime so the next stream probes again,
+/// e.g. after playback failed and the runtime may have changed.
+pub fn forget_js_runtime() {
+    *JS_RUNTIME.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    update(|c| c.js_runtime = None);
+    log::debug!("JS runtime cache cleared, will re-probe");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stale_entries_are_ignored() {
+        let entry = Some(Stamped {
+            timestamp: 1_000,
+            value: JsRuntime::Node,
+        });
+        assert_eq!(fresh(entry, 1_000 + 60), Some(JsRuntime::Node));
+        assert_eq!(fresh(entry, 1_000 + TTL_SECS), None);
+        assert_eq!(fresh::<JsRuntime>(None, 1_000), None);
+    }
+
+    #[test]
+    fn test_ytdl_runtime_names() {
+        assert_eq!(JsRuntime::Deno.ytdl_name(), None);
+        assert_eq!(JsRuntime::QuickJs.ytdl_name(), Some("quickjs"));
+        assert_eq!(JsRuntime::Missing.ytdl_name(), None);
+    }
+}
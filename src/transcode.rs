@@ -0,0 +1,309 @@
+use crate::config::Config;
+use crate::indexer::Track;
+use crate::queue;
+use anyhow::{Result, bail};
+use indicatif::{ProgressBar, ProgressStyle};
+use rayon::prelude::*;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+struct Format {
+    ext: &'static str,
+    muxer: &'static str, // ffmpeg can't guess it from the ".part" name we write to
+    codec: &'static [&'static str],
+    cover: bool, // container can carry the embedded cover art along
+}
+
+fn format_for(name: &str) -> Option<Format> {
+    let format = match name.trim_start_matches('.').to_ascii_lowercase().as_str() {
+        "mp3" => Format {
+            ext: "mp3",
+            muxer: "mp3",
+            codec: &["-c:a", "libmp3lame", "-q:a", "2", "-id3v2_version", "3"],
+            cover: true,
+        },
+        "m4a" | "aac" => Format {
+            ext: "m4a",
+            muxer: "ipod",
+            codec: &["-c:a", "aac", "-b:a", "256k"],
+            cover: true,
+        },
+        "opus" => Format {
+            ext: "opus",
+            muxer: "opus",
+            codec: &["-c:a", "libopus", "-b:a", "128k"],
+            cover: false,
+        },
+        "ogg" | "vorbis" => Format {
+            ext: "ogg",
+            muxer: "ogg",
+            codec: &["-c:a", "libvorbis", "-q:a", "6"],
+            cover: false,
+        },
+        "flac" => Format {
+            ext: "flac",
+            muxer: "flac",
+            codec: &["-c:a", "flac"],
+            cover: true,
+        },
+        _ => return None,
+    };
+    Some(format)
+}
+
+// keeps the folder layout below whichever music dir holds the track, tracks
+// outside all of them land in <album artist>/<album>/
+fn output_path(track: &Track, music_dirs: &[PathBuf], out_dir: &Path, ext: &str) -> PathBuf {
+    let source = Path::new(&track.path);
+    let relative = music_dirs
+        .iter()
+        .find_map(|dir| source.strip_prefix(dir).ok())
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| {
+            Path::new(queue::album_artist_of(track))
+                .join(&track.album)
+                .join(source.file_name().unwrap_or_default())
+        });
+    out_dir.join(relative).with_extension(ext)
+}
+
+// "Song.flac" and "Song.mp3" would both become "Song.mp3", the later ones get
+// " (2)", " (3)"... Case-insensitively, for the filesystems that are
+fn distinct_targets(targets: &mut [PathBuf]) {
+    let key = |p: &Path| p.to_string_lossy().to_lowercase();
+    let mut taken = HashSet::new();
+    for target in targets.iter_mut() {
+        if taken.insert(key(target)) {
+            continue;
+        }
+        let stem = target
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let ext = target
+            .extension()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let free = (2..)
+            .map(|n| target.with_file_name(format!("{} ({}).{}", stem, n, ext)))
+            .find(|candidate| !taken.contains(&key(candidate)))
+            .unwrap_or_else(|| target.clone());
+        log::info!("{:?} is taken by another track, writing {:?}", target, free);
+        taken.insert(key(&free));
+        *target = free;
+    }
+}
+
+enum Outcome {
+    Transcoded,
+    Copied,
+    Skipped,
+}
+
+fn convert(source: &Path, target: &Path, format: &Format) -> Result<Outcome> {
+    if target.exists() {
+        return Ok(Outcome::Skipped);
+    }
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    // written under a temp name, so an interrupted run never leaves a
+    // half-written file that the next run would skip as done
+    let part = target.with_extension(format!("{}.part", format.ext));
+
+    // already the wanted format, re-encoding would only lose quality
+    let same = source
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case(format.ext));
+    if same {
+        std::fs::copy(source, &part)?;
+        std::fs::rename(&part, target)?;
+        return Ok(Outcome::Copied);
+    }
+
+    let mut cmd = Command::new("ffmpeg");
+    cmd.args(["-nostdin", "-hide_banner", "-loglevel", "error", "-y", "-i"])
+        .arg(source)
+        .args(["-map", "0:a", "-map_metadata", "0"]);
+    if format.cover {
+        cmd.args([
+            "-map",
+            "0:v?",
+            "-c:v",
+            "copy",
+            "-disposition:v",
+            "attached_pic",
+        ]);
+    } else {
+        cmd.arg("-vn");
+    }
+    cmd.args(format.codec)
+        .args(["-f", format.muxer])
+        .arg(&part)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null());
+
+    let output = cmd.output()?;
+    if !output.status.success() {
+        let _ = std::fs::remove_file(&part);
+        bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    std::fs::rename(&part, target)?;
+    Ok(Outcome::Transcoded)
+}
+
+/// Transcodes `tracks` into `out_dir` with ffmpeg, in parallel (serial_mode
+/// limits the pool), keeping folder layout and tags. Existing outputs are skipped.
+/// Errors when any track failed, after the rest were done.
+pub fn run(tracks: &[Track], cfg: &Config, to: &str, out_dir: &Path) -> Result<()> {
+    let Some(format) = format_for(to) else {
+        bail!(
+            "Can't transcode to '{}'. Use mp3, m4a, opus, ogg or flac.",
+            to
+        );
+    };
+    let Some(version) = crate::dep_check::ffmpeg_version() else {
+        bail!("--transcode needs ffmpeg, and it isn't in your PATH.");
+    };
+    log::info!("Transcoding with {}", version);
+
+    let sources: Vec<&Track> = tracks
+        .iter()
+        .filter(|t| t.media_type != "playlist")
+        .collect();
+    let mut targets: Vec<PathBuf> = sources
+        .iter()
+        .map(|t| output_path(t, &cfg.music_dirs, out_dir, format.ext))
+        .collect();
+    distinct_targets(&mut targets);
+    let jobs: Vec<(&Track, PathBuf)> = sources.into_iter().zip(targets).collect();
+    println!(
+        "Transcoding {} tracks to {} in {:?}",
+        jobs.len(),
+        format.ext,
+        out_dir
+    );
+
+    let pb = ProgressBar::new(jobs.len() as u64);
+    pb.set_style(
+        ProgressStyle::default_bar()
+            .template("{spinner:.green} [{elapsed_precise}] [{bar:30}] {pos}/{len} {wide_msg}")
+            .unwrap(),
+    );
+
+    let (transcoded, copied, skipped) = (
+        AtomicUsize::new(0),
+        AtomicUsize::new(0),
+        AtomicUsize::new(0),
+    );
+    let failed = Mutex::new(Vec::new());
+    jobs.par_iter().for_each(|(track, target)| {
+        pb.set_message(track.title.clone());
+        match convert(Path::new(&track.path), target, &format) {
+            Ok(Outcome::Transcoded) => {
+                log::debug!("Transcoded {} -> {:?}", track.path, target);
+                transcoded.fetch_add(1, Ordering::Relaxed);
+            }
+            Ok(Outcome::Copied) => {
+                copied.fetch_add(1, Ordering::Relaxed);
+            }
+            Ok(Outcome::Skipped) => {
+                skipped.fetch_add(1, Ordering::Relaxed);
+            }
+            Err(e) => {
+                log::error!("Transcode failed for {}: {:#}", track.path, e);
+                failed
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .push(track.path.clone());
+            }
+        }
+        pb.inc(1);
+    });
+    pb.finish_and_clear();
+
+    let failed = failed.into_inner().unwrap_or_else(|e| e.into_inner());
+    println!(
+        "✅ {} transcoded, {} copied (already {}), {} skipped (exist), {} failed",
+        transcoded.into_inner(),
+        copied.into_inner(),
+        format.ext,
+        skipped.into_inner(),
+        failed.len()
+    );
+    for path in &failed {
+        eprintln!("   ❌ {}", path);
+    }
+    if !failed.is_empty() {
+        eprintln!("   See the log (--log) for ffmpeg's errors.");
+        bail!(
+            "{} of {} tracks failed to transcode",
+            failed.len(),
+            jobs.len()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_keeps_folder_layout() {
+        let track = Track {
+            path: "/music/Artist/Album/01 - Song.flac".to_string(),
+            album: "Album".to_string(),
+            artist: "Artist".to_string(),
+            ..Default::default()
+        };
+        let dirs = [PathBuf::from("/other"), PathBuf::from("/music")];
+        assert_eq!(
+            output_path(&track, &dirs, Path::new("/phone"), "mp3"),
+            Path::new("/phone/Artist/Album/01 - Song.mp3")
+        );
+
+        // outside every music dir, filed by album artist/album
+        let loose = Track {
+            path: "/tmp/x.flac".to_string(),
+            ..track
+        };
+        assert_eq!(
+            output_path(&loose, &dirs, Path::new("/phone"), "opus"),
+            Path::new("/phone/Artist/Album/x.opus")
+        );
+    }
+
+    #[test]
+    fn test_same_name_targets_get_a_number() {
+        let mut targets = vec![
+            PathBuf::from("/phone/A/Song.mp3"),
+            PathBuf::from("/phone/A/song.mp3"),
+            PathBuf::from("/phone/A/Song.mp3"),
+            PathBuf::from("/phone/B/Song.mp3"),
+        ];
+        distinct_targets(&mut targets);
+        assert_eq!(
+            targets,
+            [
+                PathBuf::from("/phone/A/Song.mp3"),
+                PathBuf::from("/phone/A/song (2).mp3"),
+                PathBuf::from("/phone/A/Song (3).mp3"),
+                PathBuf::from("/phone/B/Song.mp3"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_format_names() {
+        assert_eq!(format_for("MP3").map(|f| f.muxer), Some("mp3"));
+        assert_eq!(format_for("aac").map(|f| f.ext), Some("m4a"));
+        assert!(format_for("wma").is_none());
+    }
+}
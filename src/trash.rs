@@ -0,0 +1,100 @@
+use crate::config::Config;
+use crate::indexer;
+use anyhow::{Context, Result};
+use directories::ProjectDirs;
+use std::io::Write;
+
+/// Deletes tracks that are in the index: to the trash (or for good with `purge`),
+/// then drops them from the index. Paths not in the index are refused, so a typo
+/// can't take out some unrelated file. Returns the index paths that were deleted.
+pub fn delete_tracks(paths: &[String], purge: bool, cfg: &Config) -> Result<Vec<String>> {
+    let (mut tracks, _) = indexer::load_index(&cfg.index_format)?;
+
+    let mut deleted = Vec::new();
+    for path in paths {
+        let Some(indexed) = find_indexed(&tracks, path) else {
+            log::warn!("Refusing to delete '{}': not in the index", path);
+            eprintln!("❌ Not in the index, refusing to delete: {}", path);
+            continue;
+        };
+
+        let result = if purge {
+            std::fs::remove_file(&indexed).with_context(|| format!("Could not delete {}", indexed))
+        } else {
+            trash::delete(&indexed).with_context(|| {
+                format!(
+                    "Could not move {} to the trash, --purge deletes it for good",
+                    indexed
+                )
+            })
+        };
+        match result {
+            Ok(()) => {
+                let how = if purge { "purged" } else { "trashed" };
+                log::warn!("Deleted track ({}): {}", how, indexed);
+                record_deletion(&indexed, how);
+                println!("🗑️  {} ({})", indexed, how);
+                deleted.push(indexed);
+            }
+            Err(e) => {
+                log::error!("{:#}", e);
+                eprintln!("❌ {:#}", e);
+            }
+        }
+    }
+
+    if !deleted.is_empty() {
+        tracks.retain(|t| !deleted.contains(&t.path));
+        indexer::save(&tracks)?;
+        log::info!("Removed {} deleted tracks from the index", deleted.len());
+    }
+    Ok(deleted)
+}
+
+// the indexed spelling of `path`, matching either as given or canonicalized
+fn find_indexed(tracks: &[indexer::Track], path: &str) -> Option<String> {
+    let canonical = dunce::canonicalize(path)
+        .ok()
+        .map(|p| p.to_string_lossy().to_string());
+    tracks
+        .iter()
+        .filter(|t| t.media_type != "playlist" || t.path == path)
+        .find(|t| t.path == path || canonical.as_deref() == Some(t.path.as_str()))
+        .map(|t| t.path.clone())
+}
+
+// the app log is reset every run, this one keeps what was deleted for good
+fn record_deletion(path: &str, how: &str) {
+    let Some(dirs) = ProjectDirs::from("com", "furqanhun", "mpv-music") else {
+        return;
+    };
+    let log_path = dirs.data_dir().join("deletions.log");
+    let line = format!("{}\t{}\t{}\n", crate::history::now(), how, path);
+    let result = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .and_then(|mut f| f.write_all(line.as_bytes()));
+    if let Err(e) = result {
+        log::warn!("Could not record deletion in {:?}: {}", log_path, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_indexed_paths_are_found() {
+        let tracks = [indexer::Track {
+            path: "/music/a.mp3".to_string(),
+            media_type: "audio".to_string(),
+            ..Default::default()
+        }];
+        assert_eq!(
+            find_indexed(&tracks, "/music/a.mp3").as_deref(),
+            Some("/music/a.mp3")
+        );
+        assert_eq!(find_indexed(&tracks, "/music/b.mp3"), None);
+    }
+}
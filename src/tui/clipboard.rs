@@ -0,0 +1,66 @@
+use arboard::Clipboard;
+use skim::prelude::*;
+use std::cell::RefCell;
+
+const KEY: &str = "copy";
+
+thread_local! {
+    // on X11/Wayland the copied text lives as long as its owner, so the handle is
+    // kept for the whole session rather than dropped right after setting it
+    static CLIPBOARD: RefCell<Option<Clipboard>> = const { RefCell::new(None) };
+}
+
+/// skim binding that copies the highlighted (or selected) paths/URLs.
+pub fn bind() -> String {
+    format!("ctrl-y:accept({})", KEY)
+}
+
+pub fn is_copy(event: &Event) -> bool {
+    matches!(event, Event::Action(Action::Accept(Some(key))) if key == KEY)
+}
+
+// one entry per line, the way a shell or a text field would want them pasted
+fn joined(entries: &[String]) -> String {
+    entries.join("\n")
+}
+
+/// Copies the entries to the system clipboard and returns a short note for the
+/// picker header. Without a clipboard (headless, ssh) they go to stderr instead.
+pub fn copy(entries: &[String]) -> String {
+    if entries.is_empty() {
+        return String::new();
+    }
+    let text = joined(entries);
+
+    let result = CLIPBOARD.with(|cell| {
+        let mut slot = cell.borrow_mut();
+        if slot.is_none() {
+            *slot = Some(Clipboard::new()?);
+        }
+        slot.as_mut().map_or(Ok(()), |c| c.set_text(text.clone()))
+    });
+
+    match result {
+        Ok(()) => {
+            log::info!("Copied {} entries to the clipboard", entries.len());
+            format!("📋 copied {}", entries.len())
+        }
+        Err(e) => {
+            log::warn!("No clipboard available ({}), printing instead", e);
+            eprintln!("{}", text);
+            "📋 no clipboard, printed to stderr".to_string()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_joins_lines() {
+        let entries = ["/m/a.mp3".to_string(), "https://youtu.be/x".to_string()];
+        assert_eq!(joined(&entries), "/m/a.mp3\nhttps://youtu.be/x");
+        assert_eq!(copy(&[]), "");
+    }
+}
@@ -1,21 +1,154 @@
+use crate::config::Config;
+use crate::cover::Covers;
+use crate::glyphs::{self, Glyph};
 use crate::indexer;
+use crate::playlist;
 use crate::search;
+use ratatui::text::Line;
 use skim::prelude::*;
 use std::borrow::Cow;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+const ELLIPSIS: char = '…';
+
+// cuts `text` to `width` terminal columns, ending in an ellipsis when it had to
+fn fit(text: &str, width: usize) -> Cow<'_, str> {
+    if width == 0 || text.width() <= width {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w + 1 > width {
+            break;
+        }
+        used += w;
+        out.push(c);
+    }
+    out.push(ELLIPSIS);
+    Cow::Owned(out)
+}
+
+// like `fit`, but drops the middle, so both ends of a path stay readable
+fn fit_middle(text: &str, width: usize) -> Cow<'_, str> {
+    if width == 0 || text.width() <= width {
+        return Cow::Borrowed(text);
+    }
+    let budget = width.saturating_sub(1);
+    let head_budget = budget / 2;
+    let mut tail_budget = budget - head_budget;
+
+    let mut head = String::new();
+    let mut used = 0;
+    for c in text.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > head_budget {
+            break;
+        }
+        used += w;
+        head.push(c);
+    }
+    // a wide char that didn't fit the head leaves its room to the tail
+    tail_budget += head_budget - used;
+
+    let mut tail: Vec<char> = Vec::new();
+    let mut used = 0;
+    for c in text.chars().rev() {
+        let w = c.width().unwrap_or(0);
+        if used + w > tail_budget {
+            break;
+        }
+        used += w;
+        tail.push(c);
+    }
+
+    head.push(ELLIPSIS);
+    head.extend(tail.into_iter().rev());
+    Cow::Owned(head)
+}
+
+// numbered sample list ("  1. name"), each line cut to the preview width
+fn numbered(lines: &[String], width: usize) -> String {
+    let mut out = String::new();
+    for (i, line) in lines.iter().take(10).enumerate() {
+        let prefix = format!("  {}. ", i + 1);
+        let room = width.saturating_sub(prefix.width());
+        out.push_str(&format!("{}{}\n", prefix, fit(line, room)));
+    }
+    out
+}
+
+// 5347737 -> "5.1 MB", binary units to match what --min-size/--max-size take
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// What every TrackItem of one picker shares: the cover lookup and the icons
+/// its previews start with.
+pub struct TrackShared {
+    covers: Covers,
+    audio_icon: String,
+    video_icon: String,
+}
+
+impl TrackShared {
+    pub fn new(cfg: &Config) -> Arc<TrackShared> {
+        Arc::new(TrackShared {
+            covers: Covers::new(&cfg.cover_filenames),
+            audio_icon: glyphs::get(cfg, Glyph::Tracks),
+            video_icon: glyphs::get(cfg, Glyph::Video),
+        })
+    }
+}
 
 pub struct TrackItem {
     pub track: indexer::Track,
     pub display_text: String,
+    pub rating: Option<u8>,
+    shared: Arc<TrackShared>,
+    // display_text plus the comment on one line, what the fuzzy search matches
+    search_text: String,
+}
+
+impl TrackItem {
+    pub fn new(track: indexer::Track, rating: Option<u8>, shared: &Arc<TrackShared>) -> TrackItem {
+        let display_text = super::lazy::display_text(&track, rating);
+        let mut search_text = String::new();
+        super::lazy::write_search_text(&mut search_text, &track, rating);
+        TrackItem {
+            track,
+            display_text,
+            rating,
+            shared: Arc::clone(shared),
+            search_text,
+        }
+    }
 }
 
 impl SkimItem for TrackItem {
     fn text(&self) -> Cow<'_, str> {
-        Cow::Borrowed(&self.display_text)
+        Cow::Borrowed(&self.search_text)
+    }
+    // the comment is only searched, the row shows the track itself
+    fn display(&self, context: DisplayContext) -> Line<'_> {
+        context.to_line(Cow::Borrowed(&self.display_text))
     }
     fn output(&self) -> Cow<'_, str> {
         Cow::Borrowed(&self.track.path)
     }
-    fn preview(&self, _ctx: PreviewContext) -> ItemPreview {
+    fn preview(&self, ctx: PreviewContext) -> ItemPreview {
+        let w = ctx.width;
         let ext = std::path::Path::new(&self.track.path)
             .extension()
             .and_then(|e| e.to_str())
@@ -28,21 +161,87 @@ impl SkimItem for TrackItem {
             "Audio"
         };
         let icon = if self.track.media_type == "video" {
-            "🎬"
+            &self.shared.video_icon
+        } else {
+            &self.shared.audio_icon
+        };
+
+        // only classical-tagged tracks get the extra lines
+        let mut classical = String::new();
+        if let Some(work) = &self.track.work {
+            classical.push_str(&format!(
+                "\n  \x1b[1;33mWork:\x1b[0m   {}",
+                fit(work, w.saturating_sub(10))
+            ));
+        }
+        if let Some(n) = self.track.movement_no {
+            let name = self.track.movement.as_deref().unwrap_or(&self.track.title);
+            classical.push_str(&format!(
+                "\n  \x1b[1;33mMvt:\x1b[0m    {}. {}",
+                n,
+                fit(name, w.saturating_sub(14))
+            ));
+        }
+
+        // "1977", or "2019 (orig. 1969)" for a reissue
+        let year = match (self.track.year, self.track.original_year) {
+            (Some(y), Some(o)) if y != o => format!(" ({}, orig. {})", y, o),
+            (Some(y), _) | (None, Some(y)) => format!(" ({})", y),
+            (None, None) => String::new(),
+        };
+
+        // the folder's cover image, for tracks that don't embed one
+        let cover = if self.track.media_type == "audio" {
+            self.shared
+                .covers
+                .find(&self.track.path)
+                .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+                .map(|name| {
+                    format!(
+                        "\n  \x1b[1;34mCover:\x1b[0m  {}",
+                        fit(&name, w.saturating_sub(10))
+                    )
+                })
+                .unwrap_or_default()
+        } else {
+            String::new()
+        };
+
+        let comment = if self.track.comment.is_empty() {
+            String::new()
         } else {
-            "🎵"
+            format!(
+                "\n  \x1b[1;37mComment:\x1b[0m {}",
+                fit(&self.track.comment, w.saturating_sub(12))
+            )
         };
 
+        let position = crate::queue::track_position(&self.track)
+            .map(|p| format!("\n  \x1b[1;32mTrack:\x1b[0m  {}", p))
+            .unwrap_or_default();
+
+        let rating = self
+            .rating
+            .map(|r| format!("\n  \x1b[1;33mRating:\x1b[0m {}", crate::ratings::stars(r)))
+            .unwrap_or_default();
+
         let text = format!(
-            "\n  {} \x1b[1;36m{}\x1b[0m\n\n  \x1b[1;33mArtist:\x1b[0m {}\n  \x1b[1;32mAlbum:\x1b[0m  {}\n  \x1b[1;35mGenre:\x1b[0m  {}\n  \x1b[1;34mType:\x1b[0m   {} ({})\n\n  \x1b[90mPath: {}\x1b[0m",
+            "\n  {} \x1b[1;36m{}\x1b[0m\n\n  \x1b[1;33mArtist:\x1b[0m {}\n  \x1b[1;32mAlbum:\x1b[0m  {}{}{}{}\n  \x1b[1;35mGenre:\x1b[0m  {}\n  \x1b[1;34mType:\x1b[0m   {} ({}, {}){}{}{}\n\n  \x1b[90mPath: {}\x1b[0m",
             icon,
-            self.track.title,
-            self.track.artist,
-            self.track.album,
-            self.track.genre,
+            fit(&self.track.title, w.saturating_sub(5)),
+            fit(&self.track.artist, w.saturating_sub(10)),
+            fit(&self.track.album, w.saturating_sub(10 + year.len())),
+            year,
+            classical,
+            position,
+            fit(&self.track.genre, w.saturating_sub(10)),
             type_str,
             ext,
-            self.track.path
+            human_size(self.track.size),
+            cover,
+            comment,
+            rating,
+            fit_middle(&self.track.path, w.saturating_sub(8))
         );
         ItemPreview::AnsiText(text)
     }
@@ -53,25 +252,33 @@ pub struct TagItem {
     pub count: usize,
     pub samples: Vec<String>,
     pub icon: String,
+    pub expected: Option<usize>, // set when the tags say tracks are missing
+}
+
+impl TagItem {
+    fn total(&self) -> String {
+        match self.expected {
+            Some(expected) => format!("{} of {}", self.count, expected),
+            None => self.count.to_string(),
+        }
+    }
 }
 
 impl SkimItem for TagItem {
     fn text(&self) -> Cow<'_, str> {
-        Cow::Owned(format!("{} ({})", self.name, self.count))
+        Cow::Owned(format!("{} ({})", self.name, self.total()))
     }
 
-    fn preview(&self, _context: PreviewContext) -> ItemPreview {
-        let mut sample_text = String::new();
-        for (i, song) in self.samples.iter().enumerate() {
-            if i >= 10 {
-                break;
-            } // limit to 10
-            sample_text.push_str(&format!("  {}. {}\n", i + 1, song));
-        }
+    fn preview(&self, context: PreviewContext) -> ItemPreview {
+        let w = context.width;
+        let sample_text = numbered(&self.samples, w);
 
         let output = format!(
             "\n  {} \x1b[1;36m{}\x1b[0m\n\n  \x1b[1;33mTotal Tracks:\x1b[0m {}\n\n  \x1b[1;32mSample Tracks:\x1b[0m\n{}",
-            self.icon, self.name, self.count, sample_text
+            self.icon,
+            fit(&self.name, w.saturating_sub(5)),
+            self.total(),
+            sample_text
         );
         ItemPreview::AnsiText(output)
     }
@@ -82,28 +289,34 @@ pub struct DirItem {
     pub path: String,
     pub count: usize,
     pub samples: Vec<String>,
+    pub audiobook: bool,
+    // the folder or audiobook glyph
+    pub icon: String,
 }
 
 impl SkimItem for DirItem {
     fn text(&self) -> Cow<'_, str> {
-        Cow::Owned(format!("{} ({})", self.dirname, self.count))
+        if self.audiobook {
+            Cow::Owned(format!("{} {} ({})", self.icon, self.dirname, self.count))
+        } else {
+            Cow::Owned(format!("{} ({})", self.dirname, self.count))
+        }
     }
     fn output(&self) -> Cow<'_, str> {
         Cow::Borrowed(&self.path)
     }
 
-    fn preview(&self, _context: PreviewContext) -> ItemPreview {
-        let mut sample_text = String::new();
-        for (i, song) in self.samples.iter().enumerate() {
-            if i >= 10 {
-                break;
-            }
-            sample_text.push_str(&format!("  {}. {}\n", i + 1, song));
-        }
+    fn preview(&self, context: PreviewContext) -> ItemPreview {
+        let w = context.width;
+        let sample_text = numbered(&self.samples, w);
 
         let output = format!(
-            "\n  📁 \x1b[1;36m{}\x1b[0m\n\n  \x1b[1;33mPath:\x1b[0m {}\n  \x1b[1;33mFiles:\x1b[0m {}\n\n  \x1b[1;32mContents:\x1b[0m\n{}",
-            self.dirname, self.path, self.count, sample_text
+            "\n  {} \x1b[1;36m{}\x1b[0m\n\n  \x1b[1;33mPath:\x1b[0m {}\n  \x1b[1;33mFiles:\x1b[0m {}\n\n  \x1b[1;32mContents:\x1b[0m\n{}",
+            self.icon,
+            fit(&self.dirname, w.saturating_sub(5)),
+            fit_middle(&self.path, w.saturating_sub(8)),
+            self.count,
+            sample_text
         );
         ItemPreview::AnsiText(output)
     }
@@ -112,8 +325,9 @@ impl SkimItem for DirItem {
 pub struct PlaylistItem {
     pub name: String,
     pub path: String,
-    pub count: usize,
-    pub preview_lines: Vec<String>,
+    // read the first time the playlist is highlighted, not for every one up front
+    pub preview: std::sync::OnceLock<Option<playlist::Preview>>,
+    pub icon: String,
 }
 
 impl SkimItem for PlaylistItem {
@@ -124,29 +338,66 @@ impl SkimItem for PlaylistItem {
         Cow::Borrowed(&self.path)
     }
 
-    fn preview(&self, _context: PreviewContext) -> ItemPreview {
-        let mut content = String::new();
-        for (i, line) in self.preview_lines.iter().enumerate() {
-            if i >= 10 {
-                break;
-            }
-            content.push_str(&format!("  {}. {}\n", i + 1, line));
-        }
+    fn preview(&self, context: PreviewContext) -> ItemPreview {
+        let w = context.width;
+        let preview = self.preview.get_or_init(|| {
+            playlist::preview(std::path::Path::new(&self.path), 10)
+                .inspect_err(|e| log::warn!("Skipping unparseable playlist preview: {:#}", e))
+                .ok()
+        });
+        let (count, mut content) = match preview {
+            Some(p) if p.capped => (format!("{}+", p.count), numbered(&p.samples, w)),
+            Some(p) => (p.count.to_string(), numbered(&p.samples, w)),
+            None => ("0".to_string(), "  (Could not read file)\n".to_string()),
+        };
 
         if content.is_empty() {
             content.push_str("  (Empty or Binary Playlist)\n");
         }
 
         let output = format!(
-            "\n  📜 \x1b[1;36m{}\x1b[0m\n\n  \x1b[1;33mPath:\x1b[0m {}\n  \x1b[1;33mEntries:\x1b[0m {}\n\n  \x1b[1;32mFirst Few Tracks:\x1b[0m\n{}",
-            self.name, self.path, self.count, content
+            "\n  {} \x1b[1;36m{}\x1b[0m\n\n  \x1b[1;33mPath:\x1b[0m {}\n  \x1b[1;33mEntries:\x1b[0m {}\n\n  \x1b[1;32mFirst Few Tracks:\x1b[0m\n{}",
+            self.icon,
+            fit(&self.name, w.saturating_sub(5)),
+            fit_middle(&self.path, w.saturating_sub(8)),
+            count,
+            content
         );
         ItemPreview::AnsiText(output)
     }
 }
 
+pub struct FormatItem {
+    pub format: search::FormatEntry,
+}
+
+impl SkimItem for FormatItem {
+    fn text(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.format.line)
+    }
+    fn output(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.format.id)
+    }
+}
+
 pub struct SearchItem {
     pub result: search::SearchResult,
+    // the playlist or video glyph
+    pub icon: String,
+}
+
+impl SearchItem {
+    pub fn new(result: search::SearchResult, cfg: &Config) -> SearchItem {
+        let glyph = if result.is_playlist {
+            Glyph::Playlists
+        } else {
+            Glyph::Stream
+        };
+        SearchItem {
+            result,
+            icon: glyphs::get(cfg, glyph),
+        }
+    }
 }
 
 impl SkimItem for SearchItem {
@@ -160,15 +411,15 @@ impl SkimItem for SearchItem {
     }
 
     fn preview(&self, _ctx: PreviewContext) -> ItemPreview {
-        let (icon, type_str) = if self.result.is_playlist {
-            ("📜", "Playlist / Mix")
+        let type_str = if self.result.is_playlist {
+            "Playlist / Mix"
         } else {
-            ("📺", "Video")
+            "Video"
         };
 
         let details = format!(
             "\n  {} \x1b[1;36m{}\x1b[0m\n\n  \x1b[1;33mChannel:\x1b[0m  {}\n  \x1b[1;33mViews:\x1b[0m    {}\n  \x1b[1;33mDuration:\x1b[0m {}\n  \x1b[1;34mType:\x1b[0m      {}\n\n  \x1b[90mURL: {}\x1b[0m",
-            icon,
+            self.icon,
             self.result.title,
             self.result.uploader,
             self.result.view_count,
@@ -195,3 +446,50 @@ impl SkimItem for MenuItem {
         ItemPreview::Text(self.id.clone())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_track_comment_is_searched_on_one_line() {
+        let track = indexer::Track {
+            path: "/m/a.flac".to_string(),
+            artist: "Ado".to_string(),
+            title: "Show".to_string(),
+            comment: "workout\nwedding  mix".to_string(),
+            ..Default::default()
+        };
+        let item = TrackItem::new(track, None, &TrackShared::new(&Config::default()));
+        assert_eq!(item.display_text, "Ado - Show");
+        assert_eq!(item.text(), "Ado - Show  # workout wedding mix");
+    }
+
+    #[test]
+    fn test_fit_truncates_with_ellipsis() {
+        assert_eq!(fit("short", 10), "short");
+        assert_eq!(fit("a very long title", 8), "a very …");
+        assert_eq!(fit("anything", 0), "anything");
+        // wide chars count double
+        assert_eq!(fit("日本語のタイトル", 7), "日本語…");
+        assert!(fit("日本語のタイトル", 7).width() <= 7);
+    }
+
+    #[test]
+    fn test_fit_middle_keeps_both_ends() {
+        let path = "/home/user/Music/Artist/Album/01 - Track.flac";
+        let fitted = fit_middle(path, 21);
+        assert_eq!(fitted.width(), 21);
+        assert!(fitted.starts_with("/home/user"));
+        assert!(fitted.ends_with("Track.flac"));
+        assert!(fitted.contains('…'));
+        assert_eq!(fit_middle("/a/b", 10), "/a/b");
+    }
+
+    #[test]
+    fn test_human_size() {
+        assert_eq!(human_size(512), "512 B");
+        assert_eq!(human_size(5 * 1024 * 1024 + 100 * 1024), "5.1 MB");
+        assert_eq!(human_size(3 << 30), "3.0 GB");
+    }
+}
@@ -0,0 +1,223 @@
+use super::items::{TrackItem, TrackShared};
+use crate::indexer;
+use crate::ratings::Ratings;
+use skim::prelude::*;
+
+// matches are pushed to skim in chunks so a new keystroke can cut a scan short
+const BATCH_SIZE: usize = 1024;
+
+/// Feeds skim's interactive mode straight from the in-memory index.
+/// Every query change re-invokes this with the new query and only the
+/// matching tracks get wrapped and streamed, nothing is sent up front. An
+/// empty query shows the first BATCH_SIZE tracks, not the whole library.
+pub struct LibraryQuery {
+    tracks: Arc<Vec<indexer::Track>>,
+    ratings: Arc<Ratings>,
+    shared: Arc<TrackShared>,
+}
+
+impl LibraryQuery {
+    pub fn new(tracks: Vec<indexer::Track>, ratings: &Ratings, shared: &Arc<TrackShared>) -> Self {
+        Self {
+            tracks: Arc::new(tracks),
+            ratings: Arc::new(ratings.clone()),
+            shared: Arc::clone(shared),
+        }
+    }
+
+    /// Shows `ratings` from the next query on, after tracks were rated.
+    pub fn rerate(&mut self, ratings: &Ratings) {
+        self.ratings = Arc::new(ratings.clone());
+    }
+
+    /// Drops tracks by path, e.g. after they were deleted from the picker.
+    pub fn remove(&mut self, paths: &[String]) {
+        Arc::make_mut(&mut self.tracks).retain(|t| !paths.contains(&t.path));
+    }
+}
+
+pub fn display_text(track: &indexer::Track, rating: Option<u8>) -> String {
+    let mut text = String::new();
+    write_display(&mut text, track, rating);
+    text
+}
+
+fn write_display(out: &mut String, track: &indexer::Track, rating: Option<u8>) {
+    use std::fmt::Write;
+    let _ = match track.movement_no {
+        Some(n) => write!(out, "{} - {}. {}", track.artist, n, track.title),
+        None => write!(out, "{} - {}", track.artist, track.title),
+    };
+    if let Some(rating) = rating {
+        let _ = write!(out, "  {}", crate::ratings::stars(rating));
+    }
+}
+
+/// The display text plus the comment on one line (a multi-line comment would
+/// break the row apart), what the fuzzy search matches.
+pub fn write_search_text(out: &mut String, track: &indexer::Track, rating: Option<u8>) {
+    write_display(out, track, rating);
+    if !track.comment.trim().is_empty() {
+        out.push_str("  #");
+        for word in track.comment.split_whitespace() {
+            out.push(' ');
+            out.push_str(word);
+        }
+    }
+}
+
+// skim hands us the expanded `{q}` template, which is shell-quoted on unix
+fn unquote(cmd: &str) -> String {
+    if cfg!(windows) {
+        return cmd.to_string();
+    }
+    match cmd.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')) {
+        Some(inner) => inner.replace("'\\''", "'"),
+        None => cmd.to_string(),
+    }
+}
+
+/// Every whitespace separated term has to appear somewhere (case-insensitive).
+fn matches(haystack: &str, terms: &[String]) -> bool {
+    terms.iter().all(|t| haystack.contains(t.as_str()))
+}
+
+fn query_terms(query: &str) -> Vec<String> {
+    query.split_whitespace().map(|t| t.to_lowercase()).collect()
+}
+
+impl CommandCollector for LibraryQuery {
+    fn invoke(
+        &mut self,
+        cmd: &str,
+        components_to_stop: Arc<AtomicUsize>,
+    ) -> (SkimItemReceiver, Sender<i32>) {
+        let terms = query_terms(&unquote(cmd));
+        let tracks = Arc::clone(&self.tracks);
+        let ratings = Arc::clone(&self.ratings);
+        let shared = Arc::clone(&self.shared);
+
+        let (tx_item, rx_item) = unbounded();
+        let (tx_interrupt, rx_interrupt) = bounded::<i32>(1);
+
+        components_to_stop.fetch_add(1, Ordering::SeqCst);
+        std::thread::spawn(move || {
+            let mut batch: Vec<Arc<dyn SkimItem>> = Vec::with_capacity(BATCH_SIZE);
+            let mut sent = 0;
+
+            // nothing to narrow it down yet, a page of it is enough
+            let limit = if terms.is_empty() {
+                BATCH_SIZE
+            } else {
+                usize::MAX
+            };
+            // matched on the borrowed tracks through two reused buffers, only the
+            // matches get cloned and wrapped
+            let (mut text, mut lower) = (String::new(), String::new());
+            let found = tracks
+                .iter()
+                .filter(|track| {
+                    if terms.is_empty() {
+                        return true;
+                    }
+                    text.clear();
+                    write_search_text(&mut text, track, ratings.get(&track.path));
+                    lower.clear();
+                    lower.extend(text.chars().flat_map(char::to_lowercase));
+                    matches(&lower, &terms)
+                })
+                .take(limit)
+                .map(|track| TrackItem::new(track.clone(), ratings.get(&track.path), &shared));
+            for item in found {
+                batch.push(Arc::new(item));
+
+                if batch.len() == BATCH_SIZE {
+                    // anything but "nothing yet" means skim moved on (new query or exit)
+                    if !matches!(rx_interrupt.try_recv(), Ok(None)) {
+                        batch.clear();
+                        break;
+                    }
+                    sent += batch.len();
+                    if tx_item.send(std::mem::take(&mut batch)).is_err() {
+                        break;
+                    }
+                }
+            }
+            if !batch.is_empty() {
+                sent += batch.len();
+                let _ = tx_item.send(batch);
+            }
+
+            log::debug!("Lazy track query {:?}: streamed {} items", terms, sent);
+            components_to_stop.fetch_sub(1, Ordering::SeqCst);
+        });
+
+        (rx_item, tx_interrupt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unquote_shell_escaped_query() {
+        if cfg!(windows) {
+            return;
+        }
+        assert_eq!(unquote("'daft punk'"), "daft punk");
+        assert_eq!(unquote("'don'\\''t stop'"), "don't stop");
+        assert_eq!(unquote("''"), "");
+    }
+
+    #[test]
+    fn test_all_terms_must_match() {
+        let haystack = "daft punk - one more time";
+        assert!(matches(haystack, &query_terms("PUNK time")));
+        assert!(matches(haystack, &query_terms("")));
+        assert!(!matches(haystack, &query_terms("punk around")));
+    }
+
+    fn streamed_from(query: &mut LibraryQuery, cmd: &str) -> usize {
+        let (rx, _interrupt) = query.invoke(cmd, Arc::new(AtomicUsize::new(0)));
+        let mut count = 0;
+        while let Ok(batch) = rx.recv() {
+            count += batch.len();
+        }
+        count
+    }
+
+    #[test]
+    fn test_empty_query_streams_one_page() {
+        let tracks = (0..BATCH_SIZE * 2)
+            .map(|i| indexer::Track {
+                path: format!("/m/{}.mp3", i),
+                artist: "Artist".to_string(),
+                title: i.to_string(),
+                ..Default::default()
+            })
+            .collect();
+        let mut query = LibraryQuery::new(
+            tracks,
+            &Ratings::default(),
+            &TrackShared::new(&crate::config::Config::default()),
+        );
+        assert_eq!(streamed_from(&mut query, ""), BATCH_SIZE);
+        assert_eq!(streamed_from(&mut query, "artist"), BATCH_SIZE * 2);
+        // every term has to match, "7" is in 547 of the numbers below 2048
+        assert_eq!(streamed_from(&mut query, "artist 7"), 547);
+        // the comment is matched too, on one line
+        let mut query = LibraryQuery::new(
+            vec![indexer::Track {
+                path: "/m/a.mp3".to_string(),
+                artist: "Ado".to_string(),
+                title: "Show".to_string(),
+                comment: "wedding\nMIX".to_string(),
+                ..Default::default()
+            }],
+            &Ratings::default(),
+            &TrackShared::new(&crate::config::Config::default()),
+        );
+        assert_eq!(streamed_from(&mut query, "'ado wedding mix'"), 1);
+    }
+}
@@ -0,0 +1,173 @@
+use crate::config::Config;
+use crate::glyphs::{self, Glyph};
+use anyhow::{Context, Result};
+use skim::prelude::*;
+use std::path::Path;
+
+const LEVELS: [&str; 5] = ["ERROR", "WARN", "INFO", "DEBUG", "TRACE"];
+
+/// One record of the file log. flexi_logger's opt_format writes
+/// "[2026-01-02 10:00:00.123456 +00:00] WARN [src/x.rs:12] message", a message
+/// running over several lines keeps going without a prefix.
+#[derive(Debug, Clone, PartialEq)]
+struct LogEntry {
+    time: String,
+    level: &'static str,
+    source: String,
+    message: String,
+}
+
+fn parse_line(line: &str) -> Option<LogEntry> {
+    let (time, rest) = line.strip_prefix('[')?.split_once("] ")?;
+    let (level, rest) = rest.split_once(' ')?;
+    let level = LEVELS.into_iter().find(|l| *l == level)?;
+    let (source, message) = rest.strip_prefix('[')?.split_once("] ")?;
+    Some(LogEntry {
+        time: time.to_string(),
+        level,
+        source: source.to_string(),
+        message: message.to_string(),
+    })
+}
+
+fn parse(text: &str) -> Vec<LogEntry> {
+    let mut entries: Vec<LogEntry> = Vec::new();
+    for line in text.lines() {
+        match (parse_line(line), entries.last_mut()) {
+            (Some(entry), _) => entries.push(entry),
+            (None, Some(last)) => {
+                last.message.push('\n');
+                last.message.push_str(line);
+            }
+            // whatever precedes the first record (a truncated write) is dropped
+            (None, None) => {}
+        }
+    }
+    entries
+}
+
+fn marker(level: &str) -> (&'static str, &'static str) {
+    match level {
+        "ERROR" => ("✖", "1;31"),
+        "WARN" => ("⚠", "1;33"),
+        "INFO" => ("ℹ", "1;32"),
+        _ => ("·", "90"),
+    }
+}
+
+struct LogItem {
+    entry: LogEntry,
+}
+
+impl SkimItem for LogItem {
+    // the level is spelled out so typing "error" finds them, the source so a
+    // file name does
+    fn text(&self) -> Cow<'_, str> {
+        let e = &self.entry;
+        // "2026-01-02 10:00:00.123456 +00:00" -> "10:00:00"
+        let clock = e.time.split(' ').nth(1).unwrap_or(&e.time);
+        let clock = clock.split('.').next().unwrap_or(clock);
+        Cow::Owned(format!(
+            "{} {:<5} {}  {}  [{}]",
+            marker(e.level).0,
+            e.level,
+            clock,
+            e.message.lines().next().unwrap_or_default(),
+            e.source
+        ))
+    }
+
+    fn preview(&self, _context: PreviewContext) -> ItemPreview {
+        let e = &self.entry;
+        let message: String = e
+            .message
+            .lines()
+            .map(|line| format!("  {}\n", line))
+            .collect();
+        ItemPreview::AnsiText(format!(
+            "\n  \x1b[{}m{} {}\x1b[0m\n\n  \x1b[1;34mTime:\x1b[0m   {}\n  \x1b[1;34mSource:\x1b[0m {}\n\n{}",
+            marker(e.level).1,
+            marker(e.level).0,
+            e.level,
+            e.time,
+            e.source,
+            message
+        ))
+    }
+}
+
+/// Browses the log inside the TUI, newest first. alt-e narrows it down to
+/// errors and warnings, alt-a shows everything again. ENTER or ESC closes it.
+pub fn run(path: &Path, cfg: &Config) -> Result<()> {
+    let mut problems_only = false;
+    loop {
+        let bytes = std::fs::read(path).with_context(|| format!("Could not read {:?}", path))?;
+        let entries = parse(&String::from_utf8_lossy(&bytes));
+        let total = entries.len();
+        let items: Vec<LogItem> = entries
+            .into_iter()
+            .rev()
+            .filter(|e| !problems_only || matches!(e.level, "ERROR" | "WARN"))
+            .map(|entry| LogItem { entry })
+            .collect();
+        let shown = items.len();
+
+        let header = format!(
+            "   {} of {} entries    [alt-e errors/warnings, alt-a all]",
+            shown, total
+        );
+        let opts = SkimOptionsBuilder::default()
+            .prompt(glyphs::prompt(cfg, Glyph::Settings, "Log"))
+            .header(header)
+            .bind(vec![
+                "alt-e:accept(problems)".to_string(),
+                "alt-a:accept(everything)".to_string(),
+            ])
+            .reverse(true)
+            .inline_info(true)
+            .preview("")
+            .build()
+            .unwrap();
+
+        let output = Skim::run_items(opts, items)
+            .ok()
+            .with_context(super::skim_failed)?;
+        match &output.final_event {
+            Event::Action(Action::Accept(Some(key))) if key == "problems" => problems_only = true,
+            Event::Action(Action::Accept(Some(key))) if key == "everything" => {
+                problems_only = false
+            }
+            _ => return Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_joins_continuation_lines() {
+        let text = "stray tail of a cut write\n\
+            [2026-01-02 10:00:00.123456 +00:00] INFO [src/main.rs:270] Starting MPV-Music...\n\
+            [2026-01-02 10:00:01.000000 +00:00] ERROR [src/player.rs:88] mpv failed:\n\
+            exit status 2\n\
+            [2026-01-02 10:00:02.000000 +00:00] NOTE [x] not a level\n";
+        let entries = parse(text);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].level, "INFO");
+        assert_eq!(entries[0].source, "src/main.rs:270");
+        assert_eq!(
+            entries[1].message,
+            "mpv failed:\nexit status 2\n[2026-01-02 10:00:02.000000 +00:00] NOTE [x] not a level"
+        );
+
+        let item = LogItem {
+            entry: entries[1].clone(),
+        };
+        assert_eq!(
+            item.text(),
+            "✖ ERROR 10:00:01  mpv failed:  [src/player.rs:88]"
+        );
+    }
+}
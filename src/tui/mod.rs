@@ -1,10 +1,22 @@
+mod clipboard;
 mod items;
+mod lazy;
+mod logview;
+mod recall;
+mod refresh;
+mod staging;
 use items::*;
+use lazy::LibraryQuery;
+pub use refresh::start as start_background_refresh;
 
+use crate::audiobook;
 use crate::cli::Cli;
 use crate::config;
+use crate::glyphs::{self, Glyph};
 use crate::indexer;
 use crate::player;
+use crate::queue;
+use crate::ratings::Ratings;
 use crate::search;
 use anyhow::{Context, Result};
 use directories::ProjectDirs;
@@ -15,13 +27,84 @@ use std::path::PathBuf;
 
 // skim item wrappers
 
+/// What the pickers carry over between each other during one run.
+#[derive(Default)]
+pub struct Session {
+    recall: recall::Recall,
+    staged: staging::Staging,
+}
+
+fn fits(size: (u16, u16), min: (u16, u16)) -> bool {
+    (min.0 == 0 || size.0 >= min.0) && (min.1 == 0 || size.1 >= min.1)
+}
+
+const NO_TTY: &str = "Interactive mode requires a terminal (stdin/stdout is not a tty). \
+Use filters (-g, -a, --play-all, --random...) for scripted runs.";
+
+/// True when both stdin and stdout are a terminal, i.e. skim can actually run.
+pub fn interactive() -> bool {
+    use std::io::IsTerminal;
+    std::io::stdin().is_terminal() && std::io::stdout().is_terminal()
+}
+
+/// Fails early with a clear message instead of skim's opaque error.
+pub fn require_terminal() -> Result<()> {
+    if !interactive() {
+        log::error!("Refusing to open a picker without a terminal");
+        anyhow::bail!(NO_TTY);
+    }
+    Ok(())
+}
+
+fn skim_failed() -> &'static str {
+    if interactive() { "Skim failed" } else { NO_TTY }
+}
+
+/// Checks the terminal against `min_term_cols`/`min_term_rows` before a picker
+/// opens, and explains instead of letting skim draw a garbled UI.
+/// Not a terminal (piped, scripted) counts as fine.
+pub fn term_size_ok(cfg: &config::Config) -> bool {
+    let min = (cfg.min_term_cols, cfg.min_term_rows);
+    let Ok(size) = crossterm::terminal::size() else {
+        log::debug!("Terminal size unavailable, skipping size check");
+        return true;
+    };
+    if fits(size, min) {
+        return true;
+    }
+
+    log::warn!(
+        "Terminal too small: {}x{} (need {}x{})",
+        size.0,
+        size.1,
+        min.0,
+        min.1
+    );
+    eprintln!(
+        "\n\x1b[33mTerminal too small:\x1b[0m {}x{}, mpv-music needs at least {}x{}.",
+        size.0, size.1, min.0, min.1
+    );
+    eprintln!("Please enlarge the window (or lower min_term_cols / min_term_rows in config).");
+    false
+}
+
 pub fn run_main_menu(
     tracks: &mut Vec<indexer::Track>,
     cfg: &mut config::Config,
+    session: &mut Session,
     extra_args: &[String],
 ) -> Result<()> {
+    let mut notice = String::new();
     loop {
-        let options = vec![
+        // swapped in between menus only, a mode already open keeps what it was given
+        if let Some(fresh) = refresh::take_finished() {
+            notice = format!("🔄 Library updated ({} tracks)", fresh.len());
+            *tracks = fresh;
+        } else if refresh::running() {
+            notice = "🔄 Refreshing the library in the background...".to_string();
+        }
+
+        let mut options = vec![
             "1) Directory Mode",
             "2) Track Mode",
             "3) Playlist Mode",
@@ -30,109 +113,364 @@ pub fn run_main_menu(
             "6) Search & Stream URL",
             "7) Radio Mode",
             "8) Settings",
-            "q) Quit",
+            "9) Surprise Me",
         ];
-        let selected = run_skim_simple(options, "🎧 Pick mode > ");
-        match selected.as_deref() {
+        let staged_label = format!("s) Play Staged Queue ({})", session.staged.len());
+        if session.staged.len() > 0 {
+            options.push(&staged_label);
+        }
+        options.push("q) Quit");
+        let selected = run_skim_menu(
+            options,
+            &glyphs::prompt(cfg, Glyph::Menu, "Pick mode"),
+            &notice,
+        );
+        if !refresh::running() {
+            notice.clear();
+        }
+        // true once something played and return_to_menu_after_play is off
+        let done = match selected.as_deref() {
             Some(s) if s.starts_with("1)") => run_dir_mode(tracks, cfg, extra_args)?,
-            Some(s) if s.starts_with("2)") => run_track_mode(tracks, cfg, extra_args)?,
+            Some(s) if s.starts_with("2)") => run_track_mode(tracks, cfg, session, extra_args)?,
             Some(s) if s.starts_with("3)") => run_playlist_mode(tracks, cfg, extra_args)?,
-            Some(s) if s.starts_with("4)") => run_tag_mode(tracks, cfg, None, extra_args)?,
+            Some(s) if s.starts_with("4)") => run_tag_mode(tracks, cfg, session, None, extra_args)?,
             Some(s) if s.starts_with("5)") => {
-                let paths: Vec<String> = tracks.iter().map(|t| t.path.clone()).collect();
-                player::play_files(&paths, cfg, extra_args)?;
+                let music = audiobook::without_audiobooks(cfg, tracks);
+                player::play_tracks(&music, cfg, extra_args)?;
+                leave_after_play(cfg)
             }
             Some(s) if s.starts_with("6)") => {
-                run_search_mode(cfg, None, extra_args)?;
+                run_search_mode(cfg, session, None, extra_args)?;
+                false
             }
             Some(s) if s.starts_with("7)") => {
                 run_radio_mode(cfg, extra_args, None)?;
+                false
+            }
+            Some(s) if s.starts_with("8)") => {
+                run_settings_menu(tracks, cfg)?;
+                false
+            }
+            Some(s) if s.starts_with("9)") => {
+                let pool: Vec<&indexer::Track> = tracks
+                    .iter()
+                    .filter(|t| t.media_type != "playlist")
+                    .collect();
+                // it continues into the album, which only makes sense in order
+                let ordered = config::Config {
+                    shuffle: false,
+                    ..cfg.clone()
+                };
+                play_surprise(&pool, &ordered, extra_args)? && leave_after_play(cfg)
+            }
+            Some(s) if s.starts_with("s)") => {
+                let staged = session.staged.take();
+                log::info!("Playing staged queue of {} entries", staged.len());
+                player::play_files(&staged, cfg, extra_args)?;
+                leave_after_play(cfg)
             }
-            Some(s) if s.starts_with("8)") => run_settings_menu(tracks, cfg)?,
             Some(s) if s.starts_with("q)") => break,
             None => break,
-            _ => {}
+            _ => false,
+        };
+        if done {
+            log::info!("Playback finished, return_to_menu_after_play is off. Exiting.");
+            break;
         }
     }
     Ok(())
 }
 
+/// Plays a random forgotten track and carries on into its album (`--surprise`).
+/// Honors `avoid_recent_hours` like --random does. Returns false if nothing played.
+pub fn play_surprise(
+    pool: &[&indexer::Track],
+    cfg: &config::Config,
+    extra_args: &[String],
+) -> Result<bool> {
+    let avoid = if cfg.avoid_recent_hours > 0 {
+        crate::history::recent_paths(
+            &crate::history::load(),
+            cfg.avoid_recent_hours,
+            crate::history::now(),
+        )
+    } else {
+        HashSet::new()
+    };
+
+    let picked = queue::surprise(pool, &avoid, &mut queue::seeded_rng(cfg.shuffle_seed));
+    let Some(first) = picked.first() else {
+        eprintln!("No tracks to pick from.");
+        return Ok(false);
+    };
+    println!(
+        "🎲 {} - {}{}",
+        first.artist,
+        first.title,
+        if picked.len() > 1 {
+            format!(" (then {} more from {})", picked.len() - 1, first.album)
+        } else {
+            String::new()
+        }
+    );
+
+    player::play_tracks(&picked, cfg, extra_args)?;
+    Ok(true)
+}
+
+/// `--play-after`: plays `path` and the rest of its album after it, like a
+/// double-click in a desktop player. Files missing from the index (or on an
+/// UNKNOWN album) continue with the files next to them, by name.
+pub fn play_after(path: &str, cfg: &config::Config, extra_args: &[String]) -> Result<()> {
+    let file = dunce::canonicalize(path).with_context(|| format!("Can't find '{}'", path))?;
+    let file_str = file.to_string_lossy().to_string();
+
+    let tracks = match indexer::load_index(&cfg.index_format) {
+        Ok((tracks, _)) => tracks,
+        Err(e) => {
+            log::info!("Play after without the index: {:#}", e);
+            Vec::new()
+        }
+    };
+    let pool: Vec<&indexer::Track> = tracks
+        .iter()
+        .filter(|t| t.media_type != "playlist")
+        .collect();
+
+    match pool.iter().find(|t| t.path == file_str) {
+        Some(start) if start.album != "UNKNOWN" => {
+            let album = queue::rest_of_album(&pool, start);
+            println!(
+                "💿 {} - {} from track {} ({} tracks)",
+                queue::album_artist_of(start),
+                start.album,
+                queue::track_position(start).unwrap_or_else(|| "?".to_string()),
+                album.len()
+            );
+            player::play_tracks(&album, cfg, extra_args)
+        }
+        _ => {
+            let files = files_from(&file, cfg);
+            log::info!(
+                "{} isn't on an indexed album, playing its folder from there ({} files)",
+                file_str,
+                files.len()
+            );
+            player::play_files(&files, cfg, extra_args)
+        }
+    }
+}
+
+// `file` and the media files after it in its folder, sorted by name
+fn files_from(file: &std::path::Path, cfg: &config::Config) -> Vec<String> {
+    let mut exts: HashSet<String> = cfg.audio_exts.iter().map(|e| e.to_lowercase()).collect();
+    if cfg.video_ok {
+        exts.extend(cfg.video_exts.iter().map(|e| e.to_lowercase()));
+    }
+    let is_media = |p: &std::path::Path| {
+        p.is_file()
+            && p.extension()
+                .is_some_and(|e| exts.contains(&e.to_string_lossy().to_lowercase()))
+    };
+
+    let mut siblings: Vec<PathBuf> = file
+        .parent()
+        .and_then(|dir| std::fs::read_dir(dir).ok())
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| is_media(p))
+                .collect()
+        })
+        .unwrap_or_default();
+    siblings.sort();
+
+    match siblings.iter().position(|p| p == file) {
+        Some(from) => siblings[from..]
+            .iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect(),
+        // an extension the config doesn't list, it was asked for all the same
+        None => vec![file.to_string_lossy().to_string()],
+    }
+}
+
+/// Where a picker goes once its selection has played: back to itself when
+/// `return_to_menu_after_play` is on, otherwise out (and the program with it).
+/// Every picker returns true only in the second case, ESC always returns false.
+fn leave_after_play(cfg: &config::Config) -> bool {
+    !cfg.return_to_menu_after_play
+}
+
 pub fn run_tag_mode(
     tracks: &[indexer::Track],
     cfg: &config::Config,
+    session: &mut Session,
     force_key: Option<&str>,
     extra_args: &[String],
-) -> Result<()> {
+) -> Result<bool> {
     // if a key is forced (like from cli -g), we don't loop/menu, just run once
     if let Some(k) = force_key {
-        let _ = run_tag_picker(tracks, cfg, k, extra_args)?;
-        return Ok(());
+        return run_tag_picker(tracks, cfg, session, k, extra_args);
     }
 
     loop {
-        let choices = vec!["1) Genre", "2) Artist", "3) Album", "q) Back"];
-        let choice = run_skim_simple(choices, "🔎 Filter by > ");
+        let choices = vec![
+            "1) Genre",
+            "2) Artist",
+            "3) Album",
+            "4) Composer / Work",
+            "q) Back",
+        ];
+        let choice = run_skim_simple(choices, &glyphs::prompt(cfg, Glyph::Filter, "Filter by"));
 
         let key = match choice.as_deref() {
             Some(s) if s.contains("Genre") => "genre",
             Some(s) if s.contains("Artist") => "artist",
             Some(s) if s.contains("Album") => "album",
-            Some(s) if s.contains("Back") || s.starts_with("q)") => return Ok(()),
-            None => return Ok(()),
+            Some(s) if s.contains("Composer") => {
+                if run_work_mode(tracks, cfg, session, extra_args)? {
+                    return Ok(true);
+                }
+                continue;
+            }
+            Some(s) if s.contains("Back") || s.starts_with("q)") => return Ok(false),
+            None => return Ok(false),
             _ => continue,
         };
 
-        // true = selection was made and processed -> Exit to Main Menu.
+        // true = played and we're not returning to menus -> leave.
         // false = user pressed ESC inside the list -> Loop back.
-        if run_tag_picker(tracks, cfg, key, extra_args)? {
-            return Ok(());
+        if run_tag_picker(tracks, cfg, session, key, extra_args)? {
+            return Ok(true);
         }
     }
 }
 
-// helper to keep the logic clean, returns true if action taken, false if aborted (ESC).
+// helper to keep the logic clean, see leave_after_play for the return value
 pub fn run_tag_picker(
     tracks: &[indexer::Track],
     cfg: &config::Config,
+    session: &mut Session,
     key: &str,
     extra_args: &[String],
 ) -> Result<bool> {
-    let (icon, prompt) = match key {
-        "genre" => ("🏷️", "🏷️  Pick Genre > "),
-        "artist" => ("🎤", "🎤 Pick Artist > "),
-        "album" => ("💿", "💿 Pick Album > "),
+    let (glyph, label) = match key {
+        "genre" => (Glyph::Genre, "Pick Genre"),
+        "artist" => (Glyph::Artist, "Pick Artist"),
+        "album" => (Glyph::Album, "Pick Album"),
         _ => return Ok(false),
     };
+    let (icon, prompt) = (glyphs::get(cfg, glyph), glyphs::prompt(cfg, glyph, label));
 
-    let mut counts: HashMap<String, usize> = HashMap::new();
-    let mut samples: HashMap<String, Vec<String>> = HashMap::new();
+    let labels = tag_labels(tracks, cfg, key);
 
-    for t in tracks {
-        let val = match key {
-            "genre" => &t.genre,
-            "artist" => &t.artist,
-            "album" => &t.album,
-            _ => continue,
+    loop {
+        let mut items = tag_items(tracks.iter(), &labels, &icon);
+        if key == "album" {
+            mark_incomplete(&mut items, tracks, &labels);
+        }
+        let Some(selected_names) = pick_tags(items, &prompt, true, cfg, session, key)? else {
+            return Ok(false);
         };
 
-        let clean_key = if val.trim().is_empty() {
-            "UNKNOWN"
+        // Reference approach: just collect references, no cloning here.
+        let filtered: Vec<&indexer::Track> = tracks
+            .iter()
+            .filter(|t| labels(t).iter().any(|l| selected_names.contains(l)))
+            .collect();
+
+        // multi-disc albums can span folders, play them in disc/track order
+        let filtered = if key == "album" {
+            queue::album_order(filtered)
         } else {
-            val.as_str()
+            filtered
+        };
+
+        if run_post_filter_action(&filtered, cfg, session, extra_args)? {
+            return Ok(true);
+        }
+    }
+}
+
+// What a track is grouped under in the genre/artist/album pickers (and their
+// --*-stats reports).
+fn tag_labels<'a>(
+    tracks: &[indexer::Track],
+    cfg: &config::Config,
+    key: &'a str,
+) -> impl Fn(&indexer::Track) -> Vec<String> + 'a {
+    // one-off albums collapse into a "Singles" entry per artist
+    let singles = if key == "album" && cfg.group_singles {
+        single_albums(tracks, cfg.single_threshold)
+    } else {
+        HashSet::new()
+    };
+    // a track with several credited artists shows up under each of them
+    move |t: &indexer::Track| -> Vec<String> {
+        let val = match key {
+            "genre" => &t.genre,
+            "artist" => {
+                return queue::artists_of(t)
+                    .into_iter()
+                    .map(|a| clean_tag(a).to_string())
+                    .collect();
+            }
+            _ => &t.album,
         };
+        let clean = clean_tag(val);
+        let artist = queue::album_artist_of(t);
+        if singles.contains(&(artist.to_string(), clean.to_string())) {
+            vec![format!("{}{}", SINGLES_PREFIX, artist)]
+        } else {
+            vec![clean.to_string()]
+        }
+    }
+}
+
+/// Tracks per genre, artist or album, grouped like the pickers group them.
+/// Biggest first, ties by name. Playlists aren't counted.
+pub fn tag_counts(
+    tracks: &[indexer::Track],
+    cfg: &config::Config,
+    key: &str,
+) -> Vec<(String, usize)> {
+    let labels = tag_labels(tracks, cfg, key);
+    let songs = tracks.iter().filter(|t| t.media_type != "playlist");
+    let mut counts: Vec<(String, usize)> = tag_items(songs, &labels, "")
+        .into_iter()
+        .map(|item| (item.name, item.count))
+        .collect();
+    counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    counts
+}
+
+// one TagItem per distinct label, with a few sample titles for the preview
+fn tag_items<'a>(
+    tracks: impl Iterator<Item = &'a indexer::Track>,
+    labels: impl Fn(&indexer::Track) -> Vec<String>,
+    icon: &str,
+) -> Vec<TagItem> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut samples: HashMap<String, Vec<String>> = HashMap::new();
 
-        *counts.entry(clean_key.to_string()).or_default() += 1;
+    for t in tracks {
+        for clean_key in labels(t) {
+            *counts.entry(clean_key.clone()).or_default() += 1;
 
-        let sample_list = samples.entry(clean_key.to_string()).or_default();
-        if sample_list.len() < 10 {
-            sample_list.push(t.title.clone());
+            let sample_list = samples.entry(clean_key).or_default();
+            if sample_list.len() < 10 {
+                sample_list.push(t.title.clone());
+            }
         }
     }
 
+    // real albums first, the Singles buckets after them
     let mut sorted_keys: Vec<_> = counts.keys().collect();
-    sorted_keys.sort();
+    sorted_keys.sort_by_key(|k| (k.starts_with(SINGLES_PREFIX), k.as_str()));
 
-    let items: Vec<TagItem> = sorted_keys
+    sorted_keys
         .into_iter()
         .map(|k| {
             let count = *counts.get(k).unwrap();
@@ -143,82 +481,196 @@ pub fn run_tag_picker(
                 count,
                 samples: sample_list,
                 icon: icon.to_string(),
+                expected: None,
             }
         })
-        .collect();
+        .collect()
+}
 
-    let opts = SkimOptionsBuilder::default()
-        .multi(true)
+// albums whose TRACKTOTAL tags promise more than the index holds show "9 of 12"
+fn mark_incomplete(
+    items: &mut [TagItem],
+    tracks: &[indexer::Track],
+    labels: impl Fn(&indexer::Track) -> Vec<String>,
+) {
+    let mut albums: HashMap<String, Vec<&indexer::Track>> = HashMap::new();
+    for t in tracks {
+        for label in labels(t) {
+            if !label.starts_with(SINGLES_PREFIX) {
+                albums.entry(label).or_default().push(t);
+            }
+        }
+    }
+    for item in items {
+        item.expected = albums
+            .get(&item.name)
+            .and_then(|album| queue::expected_tracks(album))
+            .filter(|&expected| expected > item.count);
+    }
+}
+
+// runs a tag picker and returns the picked names, None on ESC or an empty pick.
+// `mode` names the picker for remember_queries
+fn pick_tags(
+    items: Vec<TagItem>,
+    prompt: &str,
+    multi: bool,
+    cfg: &config::Config,
+    session: &mut Session,
+    mode: &str,
+) -> Result<Option<HashSet<String>>> {
+    let mut builder = SkimOptionsBuilder::default();
+    builder
+        .multi(multi)
         .prompt(prompt)
         .preview("")
         .reverse(true)
         //.typos(2)
-        .inline_info(true)
-        .build()
-        .unwrap();
-
-    let output = Skim::run_items(opts, items).ok().context("Skim failed")?;
-
-    if output.is_abort {
-        return Ok(false);
+        .inline_info(true);
+    if let Some(query) = session.recall.last(cfg, mode) {
+        builder.query(query);
     }
 
-    let selected_items = output.selected_items;
-    if selected_items.is_empty() {
-        return Ok(false);
+    let output = Skim::run_items(builder.build().unwrap(), items)
+        .ok()
+        .with_context(skim_failed)?;
+    session.recall.keep(cfg, mode, &output.query);
+
+    if output.is_abort || output.selected_items.is_empty() {
+        return Ok(None);
     }
 
     // TagItem.text() returns "Name (Count)" and we need just "Name".
     let mut selected_names = HashSet::new();
-    for item in selected_items {
+    for item in output.selected_items {
         let text = item.text();
         let name = text.rsplit_once(" (").map(|(n, _)| n).unwrap_or(&text);
         selected_names.insert(name.to_string());
     }
+    Ok(Some(selected_names))
+}
 
-    // Reference approach: just collect references, no cloning here.
-    let filtered: Vec<&indexer::Track> = tracks
+/// Classical browsing: pick a composer, then their works, played in movement order.
+/// Untagged tracks still show up, as artist -> album. See leave_after_play for the result.
+pub fn run_work_mode(
+    tracks: &[indexer::Track],
+    cfg: &config::Config,
+    session: &mut Session,
+    extra_args: &[String],
+) -> Result<bool> {
+    if !cfg.read_classical_tags {
+        println!(
+            "ℹ️  read_classical_tags is off, browsing by artist/album. Enable it and run --reindex for composer/work tags."
+        );
+    }
+    let tracks: Vec<&indexer::Track> = tracks
         .iter()
-        .filter(|t| {
-            let val = match key {
-                "genre" => &t.genre,
-                "artist" => &t.artist,
-                "album" => &t.album,
-                _ => "",
-            };
-            let clean_val = if val.trim().is_empty() {
-                "UNKNOWN"
-            } else {
-                val
-            };
-            selected_names.contains(clean_val)
-        })
+        .filter(|t| t.media_type != "playlist")
         .collect();
 
-    run_post_filter_action(&filtered, cfg, extra_args)?;
+    loop {
+        let composers = tag_items(
+            tracks.iter().copied(),
+            |t| vec![clean_tag(queue::composer_of(t)).to_string()],
+            &glyphs::get(cfg, Glyph::Composer),
+        );
+        let Some(composer) = pick_tags(
+            composers,
+            &glyphs::prompt(cfg, Glyph::Composer, "Pick Composer"),
+            false,
+            cfg,
+            session,
+            "composer",
+        )?
+        else {
+            return Ok(false);
+        };
+
+        let by_composer: Vec<&indexer::Track> = tracks
+            .iter()
+            .copied()
+            .filter(|t| composer.contains(clean_tag(queue::composer_of(t))))
+            .collect();
+        let works = tag_items(
+            by_composer.iter().copied(),
+            |t| vec![clean_tag(queue::work_of(t)).to_string()],
+            &glyphs::get(cfg, Glyph::Work),
+        );
+        // ESC on the works goes back to the composers
+        let Some(picked) = pick_tags(
+            works,
+            &glyphs::prompt(cfg, Glyph::Work, "Pick Work"),
+            true,
+            cfg,
+            session,
+            "work",
+        )?
+        else {
+            continue;
+        };
 
-    Ok(true)
+        let filtered = by_composer
+            .into_iter()
+            .filter(|t| picked.contains(clean_tag(queue::work_of(t))))
+            .collect();
+        if run_post_filter_action(&queue::movement_order(filtered), cfg, session, extra_args)? {
+            return Ok(true);
+        }
+    }
+}
+
+const SINGLES_PREFIX: &str = "Singles · ";
+
+fn clean_tag(val: &str) -> &str {
+    if val.trim().is_empty() {
+        "UNKNOWN"
+    } else {
+        val
+    }
+}
+
+/// (album artist, album) pairs with at most `threshold` tracks (UNKNOWN never
+/// counts as one). Keyed by artist too, so one artist's "Greatest Hits" doesn't
+/// make another's look big.
+fn single_albums(tracks: &[indexer::Track], threshold: usize) -> HashSet<(String, String)> {
+    let mut sizes: HashMap<(&str, &str), usize> = HashMap::new();
+    for t in tracks {
+        *sizes
+            .entry((queue::album_artist_of(t), clean_tag(&t.album)))
+            .or_default() += 1;
+    }
+    sizes
+        .into_iter()
+        .filter(|((_, album), n)| *album != "UNKNOWN" && *n <= threshold)
+        .map(|((artist, album), _)| (artist.to_string(), album.to_string()))
+        .collect()
 }
 
 pub fn run_post_filter_action<T>(
     tracks: &[T],
     cfg: &config::Config,
+    session: &mut Session,
     extra_args: &[String],
-) -> Result<()>
+) -> Result<bool>
 where
     T: Borrow<indexer::Track>,
 {
     if tracks.is_empty() {
-        return Ok(());
+        return Ok(false);
     }
 
     if tracks.len() == 1 {
         let t = tracks[0].borrow();
-        player::play(&t.path, cfg, extra_args)?;
-        return Ok(());
+        player::play(&t.path, &player::queue_config(tracks, cfg), extra_args)?;
+        return Ok(leave_after_play(cfg));
     }
 
-    let paths: Vec<String> = tracks.iter().map(|t| t.borrow().path.clone()).collect();
+    // nobody to ask when piped or scripted, the filter already said what to play
+    if !interactive() {
+        log::info!("No terminal, playing all {} filtered tracks", tracks.len());
+        player::play_tracks(tracks, cfg, extra_args)?;
+        return Ok(true);
+    }
 
     let opts = [
         format!("1) Play all {} tracks", tracks.len()),
@@ -226,9 +678,12 @@ where
     ];
     let pick = run_skim_simple(opts.iter().map(|s| s.as_str()).collect(), "What's next? ");
     match pick.as_deref() {
-        Some(s) if s.starts_with("1)") => player::play_files(&paths, cfg, extra_args),
-        Some(s) if s.starts_with("2)") => run_track_mode(tracks, cfg, extra_args),
-        _ => Ok(()),
+        Some(s) if s.starts_with("1)") => {
+            player::play_tracks(tracks, cfg, extra_args)?;
+            Ok(leave_after_play(cfg))
+        }
+        Some(s) if s.starts_with("2)") => run_track_mode(tracks, cfg, session, extra_args),
+        _ => Ok(false),
     }
 }
 
@@ -237,24 +692,18 @@ pub fn run_manage_dirs_mode(cfg: &mut config::Config) -> Result<bool> {
 
     loop {
         let count = cfg.music_dirs.len();
-        let prompt = format!("📂 Manage ({} dirs) >    ", count);
+        let prompt = format!(
+            "{}   ",
+            glyphs::prompt(cfg, Glyph::Manage, &format!("Manage ({} dirs)", count))
+        );
 
         let options = vec!["1) Add Directory", "2) Remove Directory", "q) Back"];
 
         let sel = run_skim_simple(options, &prompt);
         match sel.as_deref() {
-            Some(s) if s.starts_with("1)") => {
-                // true = mark state as dirty
-                if manage_add_loop(cfg)? {
-                    any_changes = true;
-                }
-            }
-            Some(s) if s.starts_with("2)") => {
-                // true = mark state as dirty
-                if manage_remove_menu(cfg)? {
-                    any_changes = true;
-                }
-            }
+            // true = mark state as dirty
+            Some(s) if s.starts_with("1)") && manage_add_loop(cfg)? => any_changes = true,
+            Some(s) if s.starts_with("2)") && manage_remove_menu(cfg)? => any_changes = true,
             Some(s) if s.starts_with("q)") => break,
             None => break,
             _ => {}
@@ -316,7 +765,10 @@ pub fn manage_remove_menu(cfg: &mut config::Config) -> Result<bool> {
 
     let opts = SkimOptionsBuilder::default()
         .multi(true)
-        .prompt("🗑️  Remove >    ")
+        .prompt(format!(
+            "{}   ",
+            glyphs::prompt(cfg, Glyph::Remove, "Remove")
+        ))
         .header("   Select directories to remove (TAB to select)")
         .reverse(true)
         //.typos(2)
@@ -324,7 +776,9 @@ pub fn manage_remove_menu(cfg: &mut config::Config) -> Result<bool> {
         .build()
         .unwrap();
 
-    let output = Skim::run_items(opts, items).ok().context("Skim failed")?;
+    let output = Skim::run_items(opts, items)
+        .ok()
+        .with_context(skim_failed)?;
 
     if output.is_abort {
         return Ok(false);
@@ -378,7 +832,8 @@ pub fn add_directory(cfg: &mut config::Config, dir: String) -> Result<bool> {
         }
     };
 
-    if !cfg.music_dirs.contains(&path) {
+    let path = config::normalize_dir(&path);
+    if !cfg.music_dirs.iter().any(|d| config::same_dir(d, &path)) {
         cfg.music_dirs.push(path.clone());
         println!("Added: {:?}", path);
         Ok(true)
@@ -389,13 +844,15 @@ pub fn add_directory(cfg: &mut config::Config, dir: String) -> Result<bool> {
 }
 
 pub fn remove_directory(cfg: &mut config::Config, dir: String) -> Result<bool> {
+    // a folder that is gone can't be canonicalized, it's matched as typed
     let path = dunce::canonicalize(&dir).unwrap_or_else(|_| PathBuf::from(&dir));
     let start_len = cfg.music_dirs.len();
 
-    cfg.music_dirs.retain(|d| d != &path);
+    cfg.music_dirs.retain(|d| !config::same_dir(d, &path));
 
     if cfg.music_dirs.len() < start_len {
         println!("Removed: {:?}", path);
+        indexer::forget_dir(&path, &cfg.index_format)?;
         Ok(true)
     } else {
         println!("Not found in config: {:?}", path);
@@ -403,9 +860,60 @@ pub fn remove_directory(cfg: &mut config::Config, dir: String) -> Result<bool> {
     }
 }
 
+/// Indexed values of `key` starting with `prefix` (case-insensitive), sorted and
+/// de-duplicated. Multi-value tags ("Rock; Pop") are split like the exact filter
+/// does, and for "ado,gen" only the part after the last comma is completed.
+pub fn complete_tag(tracks: &[indexer::Track], key: &str, prefix: &str) -> Vec<String> {
+    let (done, partial) = match prefix.rsplit_once(',') {
+        Some((done, partial)) => (format!("{},", done), partial.trim_start()),
+        None => (String::new(), prefix),
+    };
+    let partial = partial.to_lowercase();
+
+    let mut seen = HashSet::new();
+    let mut values: Vec<String> = tracks
+        .iter()
+        .filter(|t| t.media_type != "playlist")
+        .flat_map(|t| {
+            let fields = match key {
+                "artist" => queue::artists_of(t),
+                "album" => vec![t.album.as_str()],
+                _ => vec![t.genre.as_str()],
+            };
+            fields
+                .into_iter()
+                .flat_map(|f| f.split([';', ',']))
+                .map(str::trim)
+                .map(String::from)
+                .collect::<Vec<_>>()
+        })
+        .filter(|v| !v.is_empty() && v != "UNKNOWN")
+        .filter(|v| v.to_lowercase().starts_with(&partial))
+        .filter(|v| seen.insert(v.to_lowercase()))
+        .collect();
+    values.sort_by_key(|v| v.to_lowercase());
+    values
+        .into_iter()
+        .map(|v| format!("{}{}", done, v))
+        .collect()
+}
+
+// with either bound set, a track of unknown bitrate never qualifies
+fn bitrate_ok(bitrate: Option<u32>, min: Option<u32>, max: Option<u32>) -> bool {
+    if min.is_none() && max.is_none() {
+        return true;
+    }
+    bitrate.is_some_and(|b| min.is_none_or(|m| b >= m) && max.is_none_or(|m| b <= m))
+}
+
+fn size_ok(size: u64, min: Option<u64>, max: Option<u64>) -> bool {
+    min.is_none_or(|m| size >= m) && max.is_none_or(|m| size <= m)
+}
+
 pub fn apply_cli_filters(
     tracks: &[indexer::Track],
     args: &Cli,
+    cfg: &config::Config,
     exact: bool,
 ) -> Vec<indexer::Track> {
     // prepare search terms ONCE before iterating
@@ -423,6 +931,16 @@ pub fn apply_cli_filters(
     let artist_terms = prepare_terms(&args.artist);
     let album_terms = prepare_terms(&args.album);
     let title_terms = prepare_terms(&args.title);
+    // free text, so always a partial match
+    let comment_terms = prepare_terms(&args.comment.clone().map(Some));
+    let added_after = args
+        .since
+        .map(|age| crate::history::now().saturating_sub(age));
+    let ratings = if args.min_rating.is_some() {
+        Ratings::load(&cfg.music_dirs)
+    } else {
+        Ratings::default()
+    };
 
     tracks
         .iter()
@@ -449,10 +967,28 @@ pub fn apply_cli_filters(
                 }
             };
 
+            // the credit as tagged ("A feat. B"), or any one credited artist
+            let artist_ok = std::iter::once(t.artist.as_str())
+                .chain(t.artists.iter().map(String::as_str))
+                .any(|a| matches(a, &artist_terms));
+
             matches(&t.genre, &genre_terms)
-                && matches(&t.artist, &artist_terms)
+                && artist_ok
                 && matches(&t.album, &album_terms)
                 && matches(&t.title, &title_terms)
+                && comment_terms.as_ref().is_none_or(|terms| {
+                    let comment = t.comment.to_lowercase();
+                    terms.iter().any(|term| comment.contains(term))
+                })
+                && args
+                    .min_rating
+                    .is_none_or(|min| ratings.get(&t.path).is_some_and(|r| r >= min))
+                && bitrate_ok(t.bitrate, args.min_bitrate, args.max_bitrate)
+                && size_ok(t.size, args.min_size, args.max_size)
+                && added_after.is_none_or(|after| queue::added_at(t, args.by_mtime) >= after)
+                && args
+                    .year
+                    .is_none_or(|range| range.contains(queue::year_of(t, cfg.prefer_original_year)))
         })
         .cloned()
         .collect()
@@ -465,33 +1001,27 @@ pub fn run_settings_menu(tracks: &mut Vec<indexer::Track>, cfg: &mut config::Con
             "2) Edit Config File",
             "3) Delete Config File (Reset)",
             "4) View Log File",
-            "5) Delete Log File",
-            "6) Refresh Index (Fast)",
-            "7) Rebuild Index (Full)",
+            "5) Open Log in Pager",
+            "6) Delete Log File",
+            "7) Refresh Index (Fast)",
+            "8) Rebuild Index (Full)",
             "q) Back",
         ];
 
-        let selection = run_skim_simple(options, "⚙️ Settings > ");
+        let selection = run_skim_simple(options, &glyphs::prompt(cfg, Glyph::Settings, "Settings"));
         match selection.as_deref() {
             // dirs
-            Some(s) if s.contains("Manage Directories") => {
-                if run_manage_dirs_mode(cfg)? {
-                    config::save(cfg)?;
-                    println!("Configuration saved. Syncing changes...");
-                    *tracks = indexer::scan(cfg, false)?;
-                    indexer::save(tracks)?;
-                }
+            Some(s) if s.contains("Manage Directories") && run_manage_dirs_mode(cfg)? => {
+                config::save(cfg)?;
+                println!("Configuration saved. Syncing changes...");
+                refresh::cancel();
+                release(tracks);
+                *tracks = indexer::rescan(cfg, false)?;
             }
 
             // conf management
             Some(s) if s.contains("Edit Config") => {
-                let editor = std::env::var("EDITOR").unwrap_or_else(|_| {
-                    if cfg!(windows) {
-                        "notepad".to_string()
-                    } else {
-                        "nano".to_string()
-                    }
-                });
+                let editor = config::resolve_editor(None, Some(cfg));
                 let config_path = ProjectDirs::from("com", "furqanhun", "mpv-music")
                     .unwrap()
                     .config_dir()
@@ -512,13 +1042,15 @@ pub fn run_settings_menu(tracks: &mut Vec<indexer::Track>, cfg: &mut config::Con
                     .unwrap()
                     .config_dir()
                     .join("config.toml");
-                if config_path.exists() {
+                if !config_path.exists() {
+                    println!("No config file found.");
+                } else if confirm("\nDelete config file?")? {
                     std::fs::remove_file(&config_path)?;
                     println!("Config deleted. Loading defaults...");
                     // reload = generate the defualt
                     *cfg = config::load(None)?;
                 } else {
-                    println!("No config file found.");
+                    println!("Kept it.");
                 }
                 std::thread::sleep(std::time::Duration::from_secs(1));
             }
@@ -529,13 +1061,19 @@ pub fn run_settings_menu(tracks: &mut Vec<indexer::Track>, cfg: &mut config::Con
                     .unwrap()
                     .data_dir()
                     .join("mpv-music.log");
-                let viewer = std::env::var("PAGER").unwrap_or_else(|_| {
-                    if cfg!(windows) {
-                        "more".to_string()
-                    } else {
-                        "less".to_string()
-                    }
-                });
+                if log_path.exists() {
+                    logview::run(&log_path, cfg)?;
+                } else {
+                    println!("Log file does not exist.");
+                    std::thread::sleep(std::time::Duration::from_secs(1));
+                }
+            }
+            Some(s) if s.contains("Log in Pager") => {
+                let log_path = ProjectDirs::from("com", "furqanhun", "mpv-music")
+                    .unwrap()
+                    .data_dir()
+                    .join("mpv-music.log");
+                let viewer = config::resolve_pager(None, Some(cfg));
                 if log_path.exists() {
                     std::process::Command::new(viewer).arg(log_path).status()?;
                 } else {
@@ -548,11 +1086,13 @@ pub fn run_settings_menu(tracks: &mut Vec<indexer::Track>, cfg: &mut config::Con
                     .unwrap()
                     .data_dir()
                     .join("mpv-music.log");
-                if log_path.exists() {
+                if !log_path.exists() {
+                    println!("No log file to delete.");
+                } else if confirm("\nDelete log file?")? {
                     std::fs::remove_file(log_path)?;
                     println!("Log file nuked.");
                 } else {
-                    println!("No log file to delete.");
+                    println!("Kept it.");
                 }
                 std::thread::sleep(std::time::Duration::from_secs(1));
             }
@@ -560,14 +1100,16 @@ pub fn run_settings_menu(tracks: &mut Vec<indexer::Track>, cfg: &mut config::Con
             // maintain index
             Some(s) if s.contains("Refresh Index") => {
                 println!("Refreshing index...");
-                *tracks = indexer::scan(cfg, false)?;
-                indexer::save(tracks)?;
+                refresh::cancel();
+                release(tracks);
+                *tracks = indexer::rescan(cfg, false)?;
                 std::thread::sleep(std::time::Duration::from_secs(1));
             }
             Some(s) if s.contains("Rebuild Index") => {
                 println!("Rebuilding index...");
-                *tracks = indexer::scan(cfg, true)?;
-                indexer::save(tracks)?;
+                refresh::cancel();
+                release(tracks);
+                *tracks = indexer::rescan(cfg, true)?;
                 std::thread::sleep(std::time::Duration::from_secs(1));
             }
 
@@ -579,9 +1121,19 @@ pub fn run_settings_menu(tracks: &mut Vec<indexer::Track>, cfg: &mut config::Con
     Ok(())
 }
 
+// drop the in-memory library before a rescan streams a fresh copy to disk
+fn release(tracks: &mut Vec<indexer::Track>) {
+    tracks.clear();
+    tracks.shrink_to_fit();
+}
+
 // skim impl
 
 pub fn run_skim_simple(items: Vec<&str>, prompt: &str) -> Option<String> {
+    run_skim_menu(items, prompt, "")
+}
+
+fn run_skim_menu(items: Vec<&str>, prompt: &str, header: &str) -> Option<String> {
     let skim_items: Vec<MenuItem> = items
         .into_iter()
         .map(|i| MenuItem {
@@ -590,14 +1142,17 @@ pub fn run_skim_simple(items: Vec<&str>, prompt: &str) -> Option<String> {
         })
         .collect();
 
-    let opts = SkimOptionsBuilder::default()
+    let mut builder = SkimOptionsBuilder::default();
+    builder
         .height("50%")
         .reverse(true)
         .prompt(prompt)
         //.typos(2)
-        .inline_info(true)
-        .build()
-        .unwrap();
+        .inline_info(true);
+    if !header.is_empty() {
+        builder.header(header);
+    }
+    let opts = builder.build().unwrap();
 
     let output = Skim::run_items(opts, skim_items).ok()?;
     if output.is_abort {
@@ -647,82 +1202,536 @@ pub fn run_skim_multi_selection(items: Vec<String>, prompt: &str) -> Option<Vec<
     }
 }
 
-pub fn run_track_mode<T>(tracks: &[T], cfg: &config::Config, extra_args: &[String]) -> Result<()>
-where
-    T: Borrow<indexer::Track>,
-{
-    let skim_items: Vec<TrackItem> = tracks
-        .iter()
-        .filter_map(|item| {
-            let track = item.borrow();
-            if track.media_type == "playlist" {
-                return None;
-            }
-            let display = format!("{} - {}", track.artist, track.title);
+const DELETE_KEY: &str = "delete";
+const SHUFFLE_KEY: &str = "shuffle";
+const RATE_KEY: &str = "rate";
+#[cfg(feature = "lyrics")]
+const LYRICS_KEY: &str = "lyrics";
 
-            Some(TrackItem {
-                track: track.clone(),
-                display_text: display,
-            })
-        })
-        .collect();
+// asks before sending the picked tracks to the trash, returns the ones actually deleted
+fn confirm_delete(paths: &[String], cfg: &config::Config) -> Result<Vec<String>> {
+    if paths.is_empty() {
+        return Ok(Vec::new());
+    }
+    println!("\nAbout to move {} track(s) to the trash:", paths.len());
+    for path in paths {
+        println!("  {}", path);
+    }
+    if !confirm("Delete?")? {
+        log::info!("Delete cancelled");
+        return Ok(Vec::new());
+    }
 
-    let opts = SkimOptionsBuilder::default()
-        .height("100%")
-        .multi(true)
-        .preview("")
-        .prompt("🎵 Tracks > ")
-        .header("   Artist                Title")
-        .reverse(true)
-        //.typos(2)
-        .inline_info(true)
-        .build()
-        .unwrap();
+    refresh::cancel();
+    crate::trash::delete_tracks(paths, false, cfg)
+}
 
-    let output = Skim::run_items(opts, skim_items)
-        .ok()
-        .context("Skim failed")?;
-    if output.is_abort {
+/// Asks "<question> [y/N]", anything but y is a no.
+pub fn confirm(question: &str) -> Result<bool> {
+    print!("{} [y/N] > ", question);
+    use std::io::Write;
+    std::io::stdout().flush()?;
+
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    Ok(input.trim().eq_ignore_ascii_case("y"))
+}
+
+// asks for 1-5 stars (0 clears) and rates the picked tracks, ENTER alone changes nothing
+fn prompt_rating(paths: &[String], ratings: &mut Ratings, cfg: &config::Config) -> Result<()> {
+    if paths.is_empty() {
         return Ok(());
     }
+    print!("\nRate {} track(s) [1-5, 0 clears] > ", paths.len());
+    use std::io::Write;
+    std::io::stdout().flush()?;
+
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    match input.trim().parse::<u8>() {
+        Ok(rating) if rating <= 5 => ratings.set(paths, rating, cfg.write_ratings_to_tags),
+        _ if input.trim().is_empty() => {}
+        _ => eprintln!("❌ Ratings go from 1 to 5 (0 clears), nothing changed"),
+    }
+    Ok(())
+}
 
-    let paths: Vec<String> = output
-        .selected_items
+pub fn run_track_mode<T>(
+    tracks: &[T],
+    cfg: &config::Config,
+    session: &mut Session,
+    extra_args: &[String],
+) -> Result<bool>
+where
+    T: Borrow<indexer::Track>,
+{
+    let library: Vec<indexer::Track> = tracks
         .iter()
-        .map(|i| i.output().to_string())
+        .map(|item| item.borrow())
+        .filter(|track| track.media_type != "playlist")
+        .cloned()
         .collect();
-    if paths.is_empty() {
-        return Ok(());
-    }
+    let mut ratings = Ratings::load(&cfg.music_dirs);
+    let shared = TrackShared::new(cfg);
+
+    let lazy = cfg.lazy_track_threshold > 0 && library.len() > cfg.lazy_track_threshold;
+    // the lazy collector owns the library, the plain picker builds its items once
+    // and hands the same ones to every reopened picker
+    let (collector, mut items): (Option<Rc<RefCell<LibraryQuery>>>, Vec<Arc<TrackItem>>) = if lazy {
+        log::info!(
+            "{} tracks is above lazy_track_threshold ({}), using incremental search",
+            library.len(),
+            cfg.lazy_track_threshold
+        );
+        (
+            Some(Rc::new(RefCell::new(LibraryQuery::new(
+                library, &ratings, &shared,
+            )))),
+            Vec::new(),
+        )
+    } else {
+        let items = library
+            .into_iter()
+            .map(|track| {
+                let rating = ratings.get(&track.path);
+                Arc::new(TrackItem::new(track, rating, &shared))
+            })
+            .collect();
+        (None, items)
+    };
 
-    player::play_files(&paths, cfg, extra_args)?;
-    Ok(())
+    // alt-q stages the selection, ctrl-y copies it, ctrl-x deletes it, alt-r rates
+    // it and alt-l shows lyrics (lyrics builds), all reopen the picker. ENTER
+    // plays, alt-s plays the selection shuffled
+    let binds = vec![
+        staging::bind(),
+        clipboard::bind(),
+        format!("ctrl-x:accept({})", DELETE_KEY),
+        format!("alt-s:accept({})", SHUFFLE_KEY),
+        format!("alt-r:accept({})", RATE_KEY),
+        #[cfg(feature = "lyrics")]
+        format!("alt-l:accept({})", LYRICS_KEY),
+    ];
+    let lyrics_hint = if cfg!(feature = "lyrics") {
+        " | alt-l: lyrics"
+    } else {
+        ""
+    };
+
+    let mut notice = String::new();
+    loop {
+        let mut builder = SkimOptionsBuilder::default();
+        builder
+            .height("100%")
+            .multi(true)
+            .preview("")
+            .prompt(glyphs::prompt(cfg, Glyph::Tracks, "Tracks"))
+            .header(format!(
+                "   Artist                Title          (alt-q: stage, {} staged | alt-s: shuffle & play | ctrl-y: copy | ctrl-x: delete | alt-r: rate{}) {}",
+                session.staged.len(),
+                lyrics_hint,
+                notice
+            ))
+            .bind(binds.clone())
+            .reverse(true)
+            //.typos(2)
+            .inline_info(true);
+        if let Some(query) = session.recall.last(cfg, "tracks") {
+            if collector.is_some() {
+                builder.cmd_query(query);
+            } else {
+                builder.query(query);
+            }
+        }
+
+        let output = if let Some(collector) = &collector {
+            let opts = builder
+                .interactive(true)
+                .cmd("{q}")
+                .cmd_collector(Rc::clone(collector) as Rc<RefCell<dyn CommandCollector>>)
+                .build()
+                .unwrap();
+            Skim::run_with(opts, None)
+        } else {
+            let (tx, rx) = unbounded();
+            let held: Vec<Arc<dyn SkimItem>> = items
+                .iter()
+                .map(|item| Arc::clone(item) as Arc<dyn SkimItem>)
+                .collect();
+            let _ = tx.send(held);
+            drop(tx);
+            Skim::run_with(builder.build().unwrap(), Some(rx))
+        }
+        .ok()
+        .with_context(skim_failed)?;
+        session.recall.keep(cfg, "tracks", &output.query);
+
+        notice.clear();
+        if staging::is_stage(&output.final_event) {
+            session
+                .staged
+                .add(output.selected_items.iter().map(|i| i.output().to_string()));
+            continue;
+        }
+        if clipboard::is_copy(&output.final_event) {
+            let paths: Vec<String> = output
+                .selected_items
+                .iter()
+                .map(|i| i.output().to_string())
+                .collect();
+            notice = clipboard::copy(&paths);
+            continue;
+        }
+        #[cfg(feature = "lyrics")]
+        if matches!(&output.final_event, Event::Action(Action::Accept(Some(key))) if key == LYRICS_KEY)
+        {
+            let track = output
+                .selected_items
+                .first()
+                .and_then(|i| i.item.as_any().downcast_ref::<TrackItem>())
+                .map(|i| i.track.clone());
+            if let Some(track) = track {
+                if let Err(e) = crate::lyrics::show(&track, cfg) {
+                    eprintln!("❌ {:#}", e);
+                }
+                print!("Press ENTER to go back > ");
+                use std::io::Write;
+                std::io::stdout().flush()?;
+                std::io::stdin().read_line(&mut String::new())?;
+            }
+            continue;
+        }
+        if matches!(&output.final_event, Event::Action(Action::Accept(Some(key))) if key == DELETE_KEY)
+        {
+            let paths: Vec<String> = output
+                .selected_items
+                .iter()
+                .map(|i| i.output().to_string())
+                .collect();
+            let deleted = confirm_delete(&paths, cfg)?;
+            if let Some(collector) = &collector {
+                collector.borrow_mut().remove(&deleted);
+            }
+            items.retain(|i| !deleted.contains(&i.track.path));
+            continue;
+        }
+        if matches!(&output.final_event, Event::Action(Action::Accept(Some(key))) if key == RATE_KEY)
+        {
+            let paths: Vec<String> = output
+                .selected_items
+                .iter()
+                .map(|i| i.output().to_string())
+                .collect();
+            prompt_rating(&paths, &mut ratings, cfg)?;
+            if let Some(collector) = &collector {
+                collector.borrow_mut().rerate(&ratings);
+            }
+            // only the rated rows change
+            let rated: HashSet<&String> = paths.iter().collect();
+            for item in items.iter_mut().filter(|i| rated.contains(&i.track.path)) {
+                let rating = ratings.get(&item.track.path);
+                *item = Arc::new(TrackItem::new(item.track.clone(), rating, &shared));
+            }
+            continue;
+        }
+        if output.is_abort {
+            return Ok(false);
+        }
+
+        let mut paths: Vec<String> = output
+            .selected_items
+            .iter()
+            .map(|i| i.output().to_string())
+            .collect();
+        if paths.is_empty() {
+            return Ok(false);
+        }
+        let picked: Vec<&indexer::Track> = output
+            .selected_items
+            .iter()
+            .filter_map(|i| i.item.as_any().downcast_ref::<TrackItem>())
+            .map(|i| &i.track)
+            .collect();
+        let queue_cfg = if picked.len() == paths.len() {
+            player::queue_config(&picked, cfg)
+        } else {
+            Cow::Borrowed(cfg)
+        };
+        if cfg.group_queue_by_album && !cfg.shuffle && picked.len() == paths.len() {
+            paths = queue::album_order(picked)
+                .iter()
+                .map(|t| t.path.clone())
+                .collect();
+        }
+
+        let shuffle_here = matches!(&output.final_event, Event::Action(Action::Accept(Some(key))) if key == SHUFFLE_KEY);
+        // audiobook chapters stay out of a shuffle, unless they're all that was picked
+        if shuffle_here || cfg.shuffle {
+            let music: Vec<String> = paths
+                .iter()
+                .filter(|p| !audiobook::is_audiobook(cfg, p))
+                .cloned()
+                .collect();
+            if !music.is_empty() && music.len() < paths.len() {
+                println!(
+                    "📚 Left {} audiobook chapters out of the shuffle.",
+                    paths.len() - music.len()
+                );
+                paths = music;
+            }
+        }
+
+        // shuffled once here whatever `shuffle` says, and kept in that order
+        if shuffle_here {
+            queue::shuffle_seeded(&mut paths, cfg.shuffle_seed);
+            let in_order = config::Config {
+                shuffle: false,
+                ..queue_cfg.into_owned()
+            };
+            player::play_files(&paths, &in_order, extra_args)?;
+        } else {
+            player::play_files(&paths, &queue_cfg, extra_args)?;
+        }
+        if leave_after_play(cfg) {
+            return Ok(true);
+        }
+    }
 }
 
 pub fn run_dir_mode(
     tracks: &[indexer::Track],
     cfg: &config::Config,
     extra_args: &[String],
-) -> Result<()> {
+) -> Result<bool> {
+    let mut autoplay = cfg.dir_autoplay_next;
+    let mut recursive = cfg.dir_mode_recursive;
+    loop {
+        let Some((selected, files)) = pick_dirs(tracks, cfg, &mut autoplay, &mut recursive)? else {
+            return Ok(false);
+        };
+        // books play once, in chapter order, from where they were left
+        if selected.iter().all(|dir| audiobook::is_audiobook(cfg, dir)) {
+            audiobook::play(files, cfg, extra_args)?;
+        } else {
+            play_dirs(
+                tracks, cfg, extra_args, selected, files, autoplay, recursive,
+            )?;
+        }
+        if leave_after_play(cfg) {
+            return Ok(true);
+        }
+    }
+}
+
+// every folder holding tracks with the names of its files, sorted so "next
+// directory" means the next one alphabetically. `recursive` adds each folder
+// above them (up to the music dir) with the whole subtree's files
+fn dir_list(
+    tracks: &[indexer::Track],
+    cfg: &config::Config,
+    recursive: bool,
+) -> Vec<(String, Vec<String>)> {
     let mut dir_map: HashMap<String, Vec<String>> = HashMap::new();
 
     for t in tracks {
-        let parent = std::path::Path::new(&t.path)
-            .parent()
-            .unwrap()
-            .to_string_lossy()
-            .to_string();
-
-        let file_name = std::path::Path::new(&t.path)
+        let path = std::path::Path::new(&t.path);
+        let parent = path.parent().unwrap();
+        let file_name = path
             .file_name()
             .map(|s| s.to_string_lossy().to_string())
             .unwrap_or_else(|| "???".to_string());
+        dir_map
+            .entry(parent.to_string_lossy().to_string())
+            .or_default()
+            .push(file_name);
+
+        if !recursive {
+            continue;
+        }
+        let Some(root) = cfg.music_dirs.iter().find(|d| parent.starts_with(d)) else {
+            continue;
+        };
+        for dir in parent.ancestors().skip(1) {
+            if dir == root.as_path() || !dir.starts_with(root) {
+                break;
+            }
+            let relative = path.strip_prefix(dir).unwrap_or(path);
+            dir_map
+                .entry(dir.to_string_lossy().to_string())
+                .or_default()
+                .push(relative.to_string_lossy().to_string());
+        }
+    }
+
+    let mut dir_list: Vec<(String, Vec<String>)> = dir_map.into_iter().collect();
+    dir_list.sort_by(|a, b| a.0.cmp(&b.0));
+    dir_list
+}
+
+// the picked folders and their files, None on ESC or when nothing playable was picked
+fn pick_dirs(
+    tracks: &[indexer::Track],
+    cfg: &config::Config,
+    autoplay: &mut bool,
+    recursive: &mut bool,
+) -> Result<Option<(Vec<String>, Vec<String>)>> {
+    let output = loop {
+        let skim_items = dir_items(&dir_list(tracks, cfg, *recursive), cfg);
+        let header = format!(
+            "   Directory Name    [auto-advance: {}, ctrl-n toggles] [whole tree: {}, alt-t toggles]",
+            if *autoplay { "on" } else { "off" },
+            if *recursive { "on" } else { "off" }
+        );
+        let opts = SkimOptionsBuilder::default()
+            .multi(true)
+            .prompt(glyphs::prompt(cfg, Glyph::Folders, "Folders"))
+            .header(header)
+            .bind(vec![
+                "ctrl-n:accept(toggle-autoplay)".to_string(),
+                "alt-t:accept(toggle-recursive)".to_string(),
+            ])
+            .reverse(true)
+            //.typos(2)
+            .inline_info(true)
+            .preview("")
+            .build()
+            .unwrap();
+
+        let output = Skim::run_items(opts, skim_items)
+            .ok()
+            .with_context(skim_failed)?;
+        match &output.final_event {
+            Event::Action(Action::Accept(Some(key))) if key == "toggle-autoplay" => {
+                *autoplay = !*autoplay;
+                log::info!("Directory auto-advance toggled: {}", autoplay);
+            }
+            Event::Action(Action::Accept(Some(key))) if key == "toggle-recursive" => {
+                *recursive = !*recursive;
+                log::info!("Directory whole-tree mode toggled: {}", recursive);
+            }
+            _ => break output,
+        }
+    };
+    if output.is_abort {
+        return Ok(None);
+    }
+
+    let selected: Vec<String> = output
+        .selected_items
+        .iter()
+        .map(|item| item.output().to_string())
+        .collect();
+    let files: Vec<String> = selected
+        .iter()
+        .flat_map(|dir| dir_files(tracks, dir, *recursive))
+        .collect();
+    if files.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some((selected, files)))
+}
+
+fn play_dirs(
+    tracks: &[indexer::Track],
+    cfg: &config::Config,
+    extra_args: &[String],
+    selected: Vec<String>,
+    files: Vec<String>,
+    autoplay: bool,
+    recursive: bool,
+) -> Result<()> {
+    if !autoplay {
+        let queue_cfg = player::queue_config(&tracks_of(tracks, &files), cfg);
+        return player::play_files(&files, &queue_cfg, extra_args);
+    }
 
-        dir_map.entry(parent).or_default().push(file_name);
+    // a looping queue never ends, so there'd be nothing to advance from
+    let mut once_cfg = cfg.clone();
+    if cfg.loop_mode != "no" {
+        println!(
+            "🔁 Auto-advance plays each folder once, loop_mode = \"{}\" is off until it's done.",
+            cfg.loop_mode
+        );
+        once_cfg.loop_mode = "no".to_string();
     }
 
-    let skim_items: Vec<DirItem> = dir_map
+    let dir_order: Vec<String> = dir_list(tracks, cfg, recursive)
+        .into_iter()
+        .map(|(dir, _)| dir)
+        .collect();
+    let mut current = selected.last().cloned().unwrap_or_default();
+    let mut queue = files;
+    while player::play_files_to_end(
+        &queue,
+        &player::queue_config(&tracks_of(tracks, &queue), &once_cfg),
+        extra_args,
+    )? {
+        let Some(next) = next_dir(&dir_order, &current, recursive) else {
+            break;
+        };
+        println!("⏭️  Up next: {}", next);
+        log::info!("Directory auto-advance: {} -> {}", current, next);
+        queue = dir_files(tracks, &next, recursive);
+        current = next;
+    }
+    Ok(())
+}
+
+// the index entries behind `paths`, for player::queue_config
+fn tracks_of<'a>(tracks: &'a [indexer::Track], paths: &[String]) -> Vec<&'a indexer::Track> {
+    let wanted: HashSet<&str> = paths.iter().map(String::as_str).collect();
+    tracks
+        .iter()
+        .filter(|t| wanted.contains(t.path.as_str()))
+        .collect()
+}
+
+// only the files directly in `dir`, "/m/A" mustn't pick up "/m/A2" or "/m/A/B"
+fn files_in_dir(tracks: &[indexer::Track], dir: &str) -> Vec<String> {
+    tracks
+        .iter()
+        .filter(|t| std::path::Path::new(&t.path).parent() == Some(std::path::Path::new(dir)))
+        .map(|t| t.path.clone())
+        .collect()
+}
+
+// the whole subtree below `dir` as one queue, see queue::folder_order
+fn files_in_tree(tracks: &[indexer::Track], dir: &str) -> Vec<String> {
+    let inside: Vec<&indexer::Track> = tracks
+        .iter()
+        .filter(|t| std::path::Path::new(&t.path).starts_with(dir))
+        .collect();
+    queue::folder_order(inside)
         .into_iter()
+        .map(|t| t.path.clone())
+        .collect()
+}
+
+fn dir_files(tracks: &[indexer::Track], dir: &str, recursive: bool) -> Vec<String> {
+    if recursive {
+        files_in_tree(tracks, dir)
+    } else {
+        files_in_dir(tracks, dir)
+    }
+}
+
+// the directory after `current`, wrapping back to the first one. Played
+// `recursive`ly, the folders inside `current` went with it and are passed over
+fn next_dir(order: &[String], current: &str, recursive: bool) -> Option<String> {
+    let pos = order.iter().position(|d| d == current)?;
+    let inside =
+        |d: &String| recursive && d != current && std::path::Path::new(d).starts_with(current);
+    order[pos + 1..]
+        .iter()
+        .chain(&order[..=pos])
+        .find(|d| !inside(d))
+        .cloned()
+}
+
+fn dir_items(dir_list: &[(String, Vec<String>)], cfg: &config::Config) -> Vec<DirItem> {
+    dir_list
+        .iter()
+        .cloned()
         .map(|(path, files)| {
             let count = files.len();
             let name = std::path::Path::new(&path)
@@ -731,19 +1740,57 @@ pub fn run_dir_mode(
                 .to_string_lossy()
                 .to_string();
 
+            let audiobook = audiobook::is_audiobook(cfg, &path);
             DirItem {
                 dirname: name,
+                icon: glyphs::get(
+                    cfg,
+                    if audiobook {
+                        Glyph::Audiobook
+                    } else {
+                        Glyph::Folders
+                    },
+                ),
+                audiobook,
                 path,
                 count,
                 samples: files,
             }
         })
+        .collect()
+}
+
+pub fn run_playlist_mode(
+    tracks: &[indexer::Track],
+    cfg: &config::Config,
+    extra_args: &[String],
+) -> Result<bool> {
+    loop {
+        let Some(target) = pick_playlist(tracks, cfg)? else {
+            return Ok(false);
+        };
+        player::play(&target, cfg, extra_args)?;
+        if leave_after_play(cfg) {
+            return Ok(true);
+        }
+    }
+}
+
+fn pick_playlist(tracks: &[indexer::Track], cfg: &config::Config) -> Result<Option<String>> {
+    let skim_items: Vec<PlaylistItem> = tracks
+        .iter()
+        .filter(|t| t.media_type == "playlist")
+        .map(|t| PlaylistItem {
+            name: t.title.clone(),
+            path: t.path.clone(),
+            preview: Default::default(),
+            icon: glyphs::get(cfg, Glyph::Playlists),
+        })
         .collect();
 
     let opts = SkimOptionsBuilder::default()
         .multi(true)
-        .prompt("📁 Folders > ")
-        .header("   Directory Name")
+        .prompt(glyphs::prompt(cfg, Glyph::Playlists, "Playlists"))
         .reverse(true)
         //.typos(2)
         .inline_info(true)
@@ -753,126 +1800,162 @@ pub fn run_dir_mode(
 
     let output = Skim::run_items(opts, skim_items)
         .ok()
-        .context("Skim failed")?;
+        .with_context(skim_failed)?;
     if output.is_abort {
+        return Ok(None);
+    }
+
+    Ok(output
+        .selected_items
+        .first()
+        .map(|item| item.output().to_string()))
+}
+
+/// Lists an online playlist's entries so a subset can be picked (TAB order = play order).
+pub fn run_expand_mode(url: &str, cfg: &config::Config, extra_args: &[String]) -> Result<()> {
+    if !cfg.ytdlp_available {
+        eprintln!("\n\x1b[33mFeature Unavailable:\x1b[0m yt-dlp is not installed.");
+        eprintln!("Please install 'yt-dlp' to expand online playlists.");
         return Ok(());
     }
 
-    let mut files = Vec::new();
-    for item in output.selected_items {
-        let dir = item.output();
-        for t in tracks {
-            if t.path.starts_with(dir.as_ref()) {
-                files.push(t.path.clone());
-            }
-        }
+    println!("Expanding playlist...");
+    let entries = search::list_playlist(url, cfg)?;
+    if entries.len() <= 1 {
+        log::info!(
+            "Nothing to expand ({} entries), playing directly",
+            entries.len()
+        );
+        return player::play(url, cfg, extra_args);
     }
-    if files.is_empty() {
+
+    let total = entries.len();
+    let skim_items: Vec<SearchItem> = entries
+        .into_iter()
+        .map(|r| SearchItem::new(r, cfg))
+        .collect();
+
+    let header = format!("   {} entries. TAB picks in play order, ENTER plays", total);
+    let opts = SkimOptionsBuilder::default()
+        .height("100%")
+        .multi(true)
+        .prompt(glyphs::prompt(cfg, Glyph::Playlists, "Playlist"))
+        .header(header)
+        .reverse(true)
+        .inline_info(true)
+        .preview("")
+        .build()
+        .unwrap();
+
+    let output = Skim::run_items(opts, skim_items)
+        .ok()
+        .with_context(skim_failed)?;
+    if output.is_abort {
         return Ok(());
     }
-    player::play_files(&files, cfg, extra_args)
-}
 
-pub fn run_playlist_mode(
-    tracks: &[indexer::Track],
-    cfg: &config::Config,
-    extra_args: &[String],
-) -> Result<()> {
-    let skim_items: Vec<PlaylistItem> = tracks
+    let urls: Vec<String> = output
+        .selected_items
         .iter()
-        .filter_map(|t| {
-            if t.media_type == "playlist" {
-                let (count, lines) = if let Ok(content) = std::fs::read_to_string(&t.path) {
-                    let playlist_dir = std::path::Path::new(&t.path)
-                        .parent()
-                        .unwrap_or_else(|| std::path::Path::new("."));
-
-                    // validate and canonicalize paths
-                    let all_valid_lines: Vec<String> = content
-                        .lines()
-                        .filter(|l| !l.starts_with('#') && !l.trim().is_empty())
-                        .filter_map(|line| {
-                            let line_trim = line.trim();
-
-                            if line_trim.starts_with("http://")
-                                || line_trim.starts_with("https://")
-                                || line_trim.starts_with("ftp://")
-                            {
-                                return Some(line_trim.to_string());
-                            }
-
-                            let path = std::path::PathBuf::from(line_trim);
-
-                            if path.is_absolute() {
-                                if path.exists() {
-                                    Some(line_trim.to_string())
-                                } else {
-                                    log::debug!(
-                                        "Skipping non-existent path in playlist: {}",
-                                        line_trim
-                                    );
-                                    None
-                                }
-                            } else {
-                                match dunce::canonicalize(playlist_dir.join(&path)) {
-                                    Ok(canonical) => Some(canonical.to_string_lossy().to_string()),
-                                    Err(_) => {
-                                        log::debug!(
-                                            "Could not resolve relative path in playlist: {}",
-                                            line_trim
-                                        );
-                                        None
-                                    }
-                                }
-                            }
-                        })
-                        .collect();
+        .map(|item| item.output().to_string())
+        .collect();
+    if urls.is_empty() {
+        return Ok(());
+    }
 
-                    let total = all_valid_lines.len();
-                    let sample = all_valid_lines.into_iter().take(15).collect();
+    log::info!("Playing {} of {} playlist entries", urls.len(), total);
+    player::play_files(&urls, cfg, extra_args)
+}
 
-                    (total, sample)
-                } else {
-                    (0, vec!["(Could not read file)".to_string()])
-                };
+/// Shows what `yt-dlp -F` offers for a URL and plays it with the picked format.
+pub fn run_format_mode(url: &str, cfg: &config::Config, extra_args: &[String]) -> Result<()> {
+    if !cfg.ytdlp_available {
+        eprintln!("\n\x1b[33mFeature Unavailable:\x1b[0m yt-dlp is not installed.");
+        eprintln!("Please install 'yt-dlp' to list stream formats.");
+        return Ok(());
+    }
 
-                Some(PlaylistItem {
-                    name: t.title.clone(),
-                    path: t.path.clone(),
-                    count,
-                    preview_lines: lines,
-                })
-            } else {
-                None
-            }
-        })
+    println!("Fetching formats...");
+    let (header, formats) = match search::list_formats(url, cfg) {
+        Ok(found) => found,
+        Err(e) => {
+            log::warn!("{:#}", e);
+            eprintln!("❌ {:#}", e);
+            return Ok(());
+        }
+    };
+
+    let skim_items: Vec<FormatItem> = formats
+        .into_iter()
+        .map(|format| FormatItem { format })
         .collect();
 
     let opts = SkimOptionsBuilder::default()
-        .multi(true)
-        .prompt("📜 Playlists > ")
+        .height("100%")
+        .prompt(glyphs::prompt(cfg, Glyph::Format, "Format"))
+        .header(header)
         .reverse(true)
-        //.typos(2)
         .inline_info(true)
-        .preview("")
+        .no_sort(true)
         .build()
         .unwrap();
 
     let output = Skim::run_items(opts, skim_items)
         .ok()
-        .context("Skim failed")?;
+        .with_context(skim_failed)?;
     if output.is_abort {
         return Ok(());
     }
+    let Some(item) = output.selected_items.first() else {
+        return Ok(());
+    };
 
-    if let Some(item) = output.selected_items.first() {
-        player::play(&item.output(), cfg, extra_args)?;
+    let mut format = item.output().to_string();
+    // a video-only stream would be silent, pair it with the best audio
+    if item.text().contains("video only") {
+        format.push_str("+bestaudio");
+    }
+    log::info!("Playing {} with format {}", url, format);
+
+    let mut args = extra_args.to_vec();
+    args.push(format!("--ytdl-format={}", format));
+    player::play(url, cfg, &args)
+}
+
+const BROWSER_KEY: &str = "browser";
+const PLAY_NOW_KEY: &str = "play-now";
+const REFINE_KEY: &str = "refine";
+
+// detached, so the results stay usable while the browser loads
+fn open_in_browser(url: &str, cfg: &config::Config) {
+    let browser = config::resolve_browser(cfg);
+    log::info!("Opening {} with '{}'", url, browser);
+
+    let mut cmd = if cfg!(windows) && browser == "start" {
+        // start is a cmd builtin, and its first quoted arg is a window title
+        let mut cmd = std::process::Command::new("cmd");
+        cmd.args(["/C", "start", ""]);
+        cmd
+    } else {
+        std::process::Command::new(&browser)
+    };
+    let spawned = cmd
+        .arg(url)
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .spawn();
+    if let Err(e) = spawned {
+        log::error!("Could not launch browser '{}': {}", browser, e);
+        eprintln!(
+            "❌ Could not launch '{}' ({}). Set `browser` in the config.",
+            browser, e
+        );
     }
-    Ok(())
 }
 
 pub fn run_search_mode(
     cfg: &config::Config,
+    session: &mut Session,
     initial_query: Option<String>,
     extra_args: &[String],
 ) -> Result<()> {
@@ -882,11 +1965,11 @@ pub fn run_search_mode(
         return Ok(());
     }
 
-    let query = if let Some(q) = initial_query {
+    let mut query = if let Some(q) = initial_query {
         q
     } else {
         println!("Search YouTube or Paste URL:");
-        print!("🔎 > ");
+        print!("{} > ", glyphs::get(cfg, Glyph::Search));
         use std::io::Write;
         std::io::stdout().flush()?;
 
@@ -895,43 +1978,112 @@ pub fn run_search_mode(
         input.trim().to_string()
     };
 
-    if query.is_empty() {
-        return Ok(());
-    }
+    // search -> results -> back to the search line to refine it, until
+    // something plays or ESC leaves the search line
+    loop {
+        if query.is_empty() {
+            return Ok(());
+        }
 
-    if query.starts_with("http") {
-        log::info!("Direct URL detected, playing...");
-        player::play(&query, cfg, extra_args)?;
-        return Ok(());
-    }
+        if query.starts_with("http") || query.starts_with(player::VIDEO_PREFIX) {
+            log::info!("Direct URL detected, playing...");
+            player::play(&query, cfg, extra_args)?;
+            return Ok(());
+        }
 
-    println!("Fetching results for '{}'...", query);
-    let results = search::search_youtube(&query, 25)?;
+        println!("Fetching results for '{}'...", query);
+        let results = search::search_youtube(&query, 25, cfg)?;
 
-    if results.is_empty() {
-        println!("No results found.");
-        return Ok(());
-    }
+        if results.is_empty() {
+            println!("No results found.");
+        } else if !pick_search_results(cfg, session, &results, extra_args)? {
+            return Ok(());
+        }
 
-    let skim_items: Vec<SearchItem> = results
-        .into_iter()
-        .map(|r| SearchItem { result: r })
-        .collect();
+        match refine_query(&query, cfg) {
+            Some(refined) => {
+                log::info!("Refined search: '{}' -> '{}'", query, refined);
+                query = refined;
+            }
+            None => return Ok(()),
+        }
+    }
+}
 
+// the search line again, holding `last` to edit. None on ESC or an empty line
+fn refine_query(last: &str, cfg: &config::Config) -> Option<String> {
     let opts = SkimOptionsBuilder::default()
-        .height("100%")
-        .multi(true)
-        .prompt("🎯 Search > ")
+        .prompt(glyphs::prompt(cfg, Glyph::Search, "Search YouTube"))
+        .header("   Edit the search (or paste a URL), ENTER searches again, ESC leaves")
+        .query(last)
         .reverse(true)
-        //.typos(2)
-        .inline_info(true)
-        .preview("")
         .build()
         .unwrap();
 
-    if let Ok(output) = Skim::run_items(opts, skim_items) {
-        if output.is_abort {
-            return Ok(());
+    let output = Skim::run_items(opts, Vec::<MenuItem>::new()).ok()?;
+    let query = output.query.trim();
+    if output.is_abort || query.is_empty() {
+        return None;
+    }
+    Some(query.to_string())
+}
+
+// the results of one search until something plays (false) or ESC / ctrl-r
+// asks to refine the query (true)
+fn pick_search_results(
+    cfg: &config::Config,
+    session: &mut Session,
+    results: &[search::SearchResult],
+    extra_args: &[String],
+) -> Result<bool> {
+    // alt-q stages the selection, ctrl-y copies it and ctrl-b opens it in the
+    // browser, all reopen the results. ENTER plays, or stages too with
+    // search_adds_to_queue (alt-p plays then)
+    let enter_hint = if cfg.search_adds_to_queue {
+        "ENTER/alt-q: stage, alt-p: play now"
+    } else {
+        "alt-q: stage for later"
+    };
+    let mut notice = String::new();
+    loop {
+        let skim_items: Vec<SearchItem> = results
+            .iter()
+            .map(|r| SearchItem::new(r.clone(), cfg))
+            .collect();
+
+        let mut builder = SkimOptionsBuilder::default();
+        builder
+            .height("100%")
+            .multi(true)
+            .prompt(glyphs::prompt(cfg, Glyph::Results, "Search"))
+            .header(format!(
+                "   {}, {} staged | ctrl-y: copy URL | ctrl-b: open in browser | ctrl-r/ESC: refine search {}",
+                enter_hint,
+                session.staged.len(),
+                notice
+            ))
+            .bind(vec![
+                staging::bind(),
+                clipboard::bind(),
+                format!("ctrl-b:accept({})", BROWSER_KEY),
+                format!("alt-p:accept({})", PLAY_NOW_KEY),
+                format!("ctrl-r:accept({})", REFINE_KEY),
+            ])
+            .reverse(true)
+            //.typos(2)
+            .inline_info(true)
+            .preview("");
+        if let Some(query) = session.recall.last(cfg, "search") {
+            builder.query(query);
+        }
+
+        let Ok(output) = Skim::run_items(builder.build().unwrap(), skim_items) else {
+            return Ok(false);
+        };
+        session.recall.keep(cfg, "search", &output.query);
+        let refine = matches!(&output.final_event, Event::Action(Action::Accept(Some(key))) if key == REFINE_KEY);
+        if output.is_abort || refine {
+            return Ok(true);
         }
 
         let selected_urls: Vec<String> = output
@@ -940,6 +2092,24 @@ pub fn run_search_mode(
             .map(|item| item.output().to_string())
             .collect();
 
+        notice.clear();
+        let play_now = matches!(&output.final_event, Event::Action(Action::Accept(Some(key))) if key == PLAY_NOW_KEY);
+        if staging::is_stage(&output.final_event) || (cfg.search_adds_to_queue && !play_now) {
+            session.staged.add(selected_urls);
+            continue;
+        }
+        if clipboard::is_copy(&output.final_event) {
+            notice = clipboard::copy(&selected_urls);
+            continue;
+        }
+        if matches!(&output.final_event, Event::Action(Action::Accept(Some(key))) if key == BROWSER_KEY)
+        {
+            for url in &selected_urls {
+                open_in_browser(url, cfg);
+            }
+            continue;
+        }
+
         if !selected_urls.is_empty() {
             if selected_urls.len() == 1 {
                 player::play(&selected_urls[0], cfg, extra_args)?;
@@ -948,8 +2118,23 @@ pub fn run_search_mode(
                 player::play_files(&selected_urls, cfg, extra_args)?;
             }
         }
+        return Ok(false);
     }
-    Ok(())
+}
+
+/// Plays whatever was staged, for pickers opened straight from the CLI: there is
+/// no main menu to play the staged queue from once they close.
+pub fn play_staged(
+    cfg: &config::Config,
+    session: &mut Session,
+    extra_args: &[String],
+) -> Result<()> {
+    let staged = session.staged.take();
+    if staged.is_empty() {
+        return Ok(());
+    }
+    log::info!("Playing staged queue of {} entries", staged.len());
+    player::play_files(&staged, cfg, extra_args)
 }
 
 pub fn run_radio_mode(
@@ -989,7 +2174,14 @@ pub fn run_radio_mode(
         }
     }
 
-    let selected = run_skim_simple(options, "📻 Choose Station (Please consider donating!) > ");
+    let selected = run_skim_simple(
+        options,
+        &glyphs::prompt(
+            cfg,
+            Glyph::Radio,
+            "Choose Station (Please consider donating!)",
+        ),
+    );
 
     if let Some(s) = selected.as_deref()
         && let Some((name, url, _is_listen_moe)) = RADIO_STATIONS.iter().find(|(n, _, _)| *n == s)
@@ -1003,6 +2195,233 @@ pub fn run_radio_mode(
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
+    #[test]
+    fn test_complete_tag_prefix() {
+        let track = |artist: &str| indexer::Track {
+            artist: artist.to_string(),
+            media_type: "audio".to_string(),
+            ..Default::default()
+        };
+        let tracks = [
+            track("Radiohead"),
+            track("radiohead"),
+            track("Rage Against the Machine; Radiohead"),
+            track("Ado"),
+            track("UNKNOWN"),
+        ];
+
+        assert_eq!(
+            complete_tag(&tracks, "artist", "ra"),
+            ["Radiohead", "Rage Against the Machine"]
+        );
+        assert_eq!(
+            complete_tag(&tracks, "artist", "ado, rad"),
+            ["ado,Radiohead"]
+        );
+        assert_eq!(complete_tag(&tracks, "artist", "").len(), 3);
+    }
+
+    #[test]
+    fn test_play_after_folder_fallback() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        for name in [
+            "01 a.mp3",
+            "02 b.flac",
+            "03 c.mp3",
+            "cover.jpg",
+            "notes.xyz",
+        ] {
+            std::fs::write(dir.join(name), b"").unwrap();
+        }
+        let cfg = config::Config {
+            audio_exts: vec!["mp3".to_string(), "flac".to_string()],
+            ..Default::default()
+        };
+
+        let from_second = files_from(&dir.join("02 b.flac"), &cfg);
+        let odd_one = files_from(&dir.join("notes.xyz"), &cfg);
+
+        let names: Vec<&str> = from_second
+            .iter()
+            .map(|p| p.rsplit(['/', '\\']).next().unwrap())
+            .collect();
+        assert_eq!(names, ["02 b.flac", "03 c.mp3"]);
+        assert_eq!(odd_one.len(), 1);
+    }
+
+    #[test]
+    fn test_comment_filter_is_partial() {
+        let track = |comment: &str| indexer::Track {
+            artist: "A".to_string(),
+            comment: comment.to_string(),
+            ..Default::default()
+        };
+        let tracks = [
+            track("Workout mix"),
+            track("wedding; first dance"),
+            track(""),
+        ];
+        let args =
+            <Cli as clap::Parser>::try_parse_from(["mpv-music", "--comment", "workout,DANCE"])
+                .unwrap();
+
+        // exact mode is for tag facets, comments still match partially
+        let hits = apply_cli_filters(&tracks, &args, &config::Config::default(), true);
+        assert_eq!(hits.len(), 2);
+        assert!(hits.iter().all(|t| !t.comment.is_empty()));
+    }
+
+    #[test]
+    fn test_bitrate_filter_bounds() {
+        assert!(bitrate_ok(None, None, None));
+        assert!(!bitrate_ok(None, Some(128), None));
+        assert!(bitrate_ok(Some(320), Some(320), None));
+        assert!(!bitrate_ok(Some(256), Some(320), None));
+        assert!(bitrate_ok(Some(96), None, Some(128)));
+        assert!(!bitrate_ok(Some(192), Some(128), Some(160)));
+    }
+
+    #[test]
+    fn test_size_filter_bounds() {
+        assert!(size_ok(0, None, None));
+        assert!(size_ok(1024, Some(1024), Some(2048)));
+        assert!(!size_ok(4096, None, Some(2048)));
+        assert!(!size_ok(10, Some(1024), None));
+    }
+
+    #[test]
+    fn test_term_size_minimum() {
+        assert!(fits((80, 24), (60, 15)));
+        assert!(!fits((40, 24), (60, 15)));
+        assert!(!fits((80, 10), (60, 15)));
+        // 0 turns that dimension's check off
+        assert!(fits((10, 5), (0, 0)));
+        assert!(fits((10, 20), (0, 15)));
+    }
+
+    #[test]
+    fn test_single_albums_below_threshold() {
+        let track = |path: &str, artist: &str, album: &str| indexer::Track {
+            path: path.to_string(),
+            artist: artist.to_string(),
+            album: album.to_string(),
+            ..Default::default()
+        };
+        let tracks = [
+            track("/m/1.mp3", "A", "Real Album"),
+            track("/m/2.mp3", "A", "Real Album"),
+            track("/m/3.mp3", "A", "Real Album"),
+            track("/m/4.mp3", "A", "One Off"),
+            track("/m/5.mp3", "A", "Two Off"),
+            track("/m/6.mp3", "A", "Two Off"),
+            track("/m/7.mp3", "A", "UNKNOWN"),
+            track("/m/8.mp3", "B", "Greatest Hits"),
+            track("/m/9.mp3", "C", "Greatest Hits"),
+        ];
+        let key = |artist: &str, album: &str| (artist.to_string(), album.to_string());
+
+        let singles = single_albums(&tracks, 1);
+        assert_eq!(
+            singles,
+            HashSet::from([
+                key("A", "One Off"),
+                key("B", "Greatest Hits"),
+                key("C", "Greatest Hits")
+            ])
+        );
+
+        let singles = single_albums(&tracks, 2);
+        assert!(singles.contains(&key("A", "Two Off")));
+        assert!(!singles.contains(&key("A", "Real Album")));
+        assert!(!singles.contains(&key("A", "UNKNOWN")));
+    }
+
+    #[test]
+    fn test_next_dir_wraps_around() {
+        let order = vec!["/m/A".to_string(), "/m/B".to_string(), "/m/C".to_string()];
+        assert_eq!(next_dir(&order, "/m/A", false).as_deref(), Some("/m/B"));
+        assert_eq!(next_dir(&order, "/m/C", false).as_deref(), Some("/m/A"));
+        assert_eq!(next_dir(&order, "/m/X", false), None);
+
+        // a folder's own files only, so the one nested in it still comes next
+        let nested = vec![
+            "/m/A".to_string(),
+            "/m/A/CD2".to_string(),
+            "/m/B".to_string(),
+        ];
+        assert_eq!(
+            next_dir(&nested, "/m/A", false).as_deref(),
+            Some("/m/A/CD2")
+        );
+        assert_eq!(next_dir(&nested, "/m/A", true).as_deref(), Some("/m/B"));
+    }
+
+    #[test]
+    fn test_files_in_dir_are_direct_children() {
+        let track = |path: &str| indexer::Track {
+            path: path.to_string(),
+            ..Default::default()
+        };
+        let tracks = [
+            track("/m/A/1.flac"),
+            track("/m/A/CD2/1.flac"),
+            track("/m/A2/1.flac"),
+        ];
+        assert_eq!(files_in_dir(&tracks, "/m/A"), ["/m/A/1.flac"]);
+        assert_eq!(files_in_dir(&tracks, "/m/A2"), ["/m/A2/1.flac"]);
+    }
+
+    #[test]
+    fn test_dir_list_whole_tree() {
+        let track = |path: &str| indexer::Track {
+            path: path.to_string(),
+            ..Default::default()
+        };
+        let cfg = config::Config {
+            music_dirs: vec![std::path::PathBuf::from("/m")],
+            ..Default::default()
+        };
+        let tracks = [
+            track("/m/Artist/A/Disc 1/1.flac"),
+            track("/m/Artist/A/Disc 2/1.flac"),
+            track("/m/Artist/B/1.flac"),
+            track("/m/Other/1.flac"),
+        ];
+        let leaves: Vec<String> = dir_list(&tracks, &cfg, false)
+            .into_iter()
+            .map(|(d, _)| d)
+            .collect();
+        assert_eq!(leaves.len(), 4);
+
+        let tree = dir_list(&tracks, &cfg, true);
+        let dirs: Vec<&str> = tree.iter().map(|(d, _)| d.as_str()).collect();
+        assert_eq!(
+            dirs,
+            [
+                "/m/Artist",
+                "/m/Artist/A",
+                "/m/Artist/A/Disc 1",
+                "/m/Artist/A/Disc 2",
+                "/m/Artist/B",
+                "/m/Other",
+            ]
+        );
+        assert_eq!(tree[1].1, ["Disc 1/1.flac", "Disc 2/1.flac"]);
+
+        // advancing from a whole artist skips its albums
+        let order: Vec<String> = dirs.iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            next_dir(&order, "/m/Artist", true).as_deref(),
+            Some("/m/Other")
+        );
+        assert_eq!(
+            next_dir(&order, "/m/Artist/A/Disc 1", true).as_deref(),
+            Some("/m/Artist/A/Disc 2")
+        );
+    }
 
     #[test]
     fn test_url_detection_https() {
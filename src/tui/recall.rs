@@ -0,0 +1,57 @@
+use crate::config::Config;
+use std::collections::HashMap;
+
+/// The last query typed per picker ("tracks", "search", "genre"...), kept for
+/// the session.
+#[derive(Default)]
+pub struct Recall {
+    queries: HashMap<String, String>,
+}
+
+impl Recall {
+    /// The query to reopen a picker with, None when `remember_queries` is off or
+    /// nothing was typed there yet.
+    pub fn last(&self, cfg: &Config, mode: &str) -> Option<String> {
+        if !cfg.remember_queries {
+            return None;
+        }
+        self.queries.get(mode).cloned()
+    }
+
+    /// Remembers the query a picker closed with. An empty one (ctrl-u clears the
+    /// line) forgets it.
+    pub fn keep(&mut self, cfg: &Config, mode: &str, query: &str) {
+        if !cfg.remember_queries {
+            return;
+        }
+        if query.trim().is_empty() {
+            self.queries.remove(mode);
+        } else {
+            self.queries.insert(mode.to_string(), query.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_queries_are_per_mode_and_opt_in() {
+        let mut recall = Recall::default();
+        let off = Config::default();
+        recall.keep(&off, "tracks", "radiohead");
+        assert_eq!(recall.last(&off, "tracks"), None);
+
+        let on = Config {
+            remember_queries: true,
+            ..Default::default()
+        };
+        recall.keep(&on, "tracks", "radiohead");
+        recall.keep(&on, "genre", "jazz");
+        assert_eq!(recall.last(&on, "tracks").as_deref(), Some("radiohead"));
+        recall.keep(&on, "tracks", "");
+        assert_eq!(recall.last(&on, "tracks"), None);
+        assert_eq!(recall.last(&on, "genre").as_deref(), Some("jazz"));
+    }
+}
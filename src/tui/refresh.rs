@@ -0,0 +1,61 @@
+use crate::config::Config;
+use crate::indexer::{self, Track};
+use std::sync::Mutex;
+use std::thread::JoinHandle;
+
+// the `background_refresh` scan, its result is picked up by the main menu
+static WORKER: Mutex<Option<JoinHandle<Option<Vec<Track>>>>> = Mutex::new(None);
+
+/// Runs an incremental rescan on its own thread while the menu stays usable with
+/// the tracks already loaded. The scan's spinner is hidden, skim owns the terminal.
+pub fn start(cfg: &Config) {
+    let cfg = Config {
+        quiet_scan: true,
+        ..cfg.clone()
+    };
+    let handle = std::thread::spawn(move || match indexer::rescan(&cfg, false) {
+        Ok(tracks) => {
+            log::info!("Background refresh done: {} tracks", tracks.len());
+            Some(tracks)
+        }
+        Err(e) => {
+            log::warn!("Background refresh failed: {:#}", e);
+            None
+        }
+    });
+    *WORKER.lock().unwrap_or_else(|e| e.into_inner()) = Some(handle);
+    log::info!("Background index refresh started");
+}
+
+pub fn running() -> bool {
+    WORKER
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .as_ref()
+        .is_some_and(|handle| !handle.is_finished())
+}
+
+/// The refreshed library once the scan is over. None while it still runs, when
+/// it failed, or when there was none.
+pub fn take_finished() -> Option<Vec<Track>> {
+    let handle = {
+        let mut worker = WORKER.lock().unwrap_or_else(|e| e.into_inner());
+        if !worker.as_ref()?.is_finished() {
+            return None;
+        }
+        worker.take()?
+    };
+    handle.join().ok().flatten()
+}
+
+/// Waits out a running scan and drops its result. Called before anything else
+/// rewrites the index, both writing it at once would clash and theirs is newer.
+pub fn cancel() {
+    let handle = WORKER.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some(handle) = handle {
+        if !handle.is_finished() {
+            println!("Waiting for the background refresh to finish...");
+        }
+        let _ = handle.join();
+    }
+}
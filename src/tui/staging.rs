@@ -0,0 +1,65 @@
+use skim::prelude::*;
+use std::collections::HashSet;
+
+const KEY: &str = "stage";
+
+/// skim binding that adds the selection to the staged queue instead of playing it.
+pub fn bind() -> String {
+    format!("alt-q:accept({})", KEY)
+}
+
+pub fn is_stage(event: &Event) -> bool {
+    matches!(event, Event::Action(Action::Accept(Some(key))) if key == KEY)
+}
+
+/// Local paths and stream URLs picked across modes, played together from the
+/// main menu.
+#[derive(Default)]
+pub struct Staging {
+    entries: Vec<String>,
+    // the same entries, so staging a big selection twice stays cheap
+    seen: HashSet<String>,
+}
+
+impl Staging {
+    /// Adds entries (skipping ones already staged) and returns the new queue length.
+    pub fn add(&mut self, entries: impl IntoIterator<Item = String>) -> usize {
+        for entry in entries {
+            if self.seen.insert(entry.clone()) {
+                self.entries.push(entry);
+            }
+        }
+        log::info!("Staged queue now holds {} entries", self.entries.len());
+        self.entries.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Empties the queue, handing back what was in it (in staging order).
+    pub fn take(&mut self) -> Vec<String> {
+        self.seen.clear();
+        std::mem::take(&mut self.entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_staging_keeps_order_and_skips_duplicates() {
+        let mut staged = Staging::default();
+        staged.add(["/m/a.mp3".to_string(), "https://youtu.be/x".to_string()]);
+        let total = staged.add(["/m/a.mp3".to_string(), "/m/b.mp3".to_string()]);
+        assert_eq!(total, 3);
+        assert_eq!(
+            staged.take(),
+            ["/m/a.mp3", "https://youtu.be/x", "/m/b.mp3"]
+        );
+        assert_eq!(staged.len(), 0);
+        staged.add(["/m/a.mp3".to_string()]);
+        assert_eq!(staged.len(), 1);
+    }
+}
@@ -0,0 +1,109 @@
+use crate::config::Config;
+use crate::indexer;
+use anyhow::Result;
+use rayon::prelude::*;
+use std::io::ErrorKind;
+use std::path::Path;
+
+// paths printed per category, the log has the full list
+const SAMPLE: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Problem {
+    Missing,
+    Unreadable,
+    Empty,
+}
+
+impl Problem {
+    fn label(self) -> &'static str {
+        match self {
+            Problem::Missing => "missing",
+            Problem::Unreadable => "unreadable",
+            Problem::Empty => "zero-byte",
+        }
+    }
+}
+
+// a stat plus an open, nothing gets read
+fn check(path: &Path) -> Option<Problem> {
+    let meta = match std::fs::metadata(path) {
+        Ok(meta) => meta,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Some(Problem::Missing),
+        Err(_) => return Some(Problem::Unreadable),
+    };
+    if !meta.is_file() || std::fs::File::open(path).is_err() {
+        return Some(Problem::Unreadable);
+    }
+    (meta.len() == 0).then_some(Problem::Empty)
+}
+
+/// Checks that every indexed path still exists and can be opened, without
+/// touching the index. Returns whether everything was fine, so callers can
+/// exit nonzero for cron and monitoring.
+pub fn run(cfg: &Config) -> Result<bool> {
+    let (tracks, _) = indexer::load_index(&cfg.index_format)?;
+    println!("🔎 Verifying {} indexed paths...", tracks.len());
+
+    let problems: Vec<(Problem, &str)> = tracks
+        .par_iter()
+        .filter_map(|t| check(Path::new(&t.path)).map(|p| (p, t.path.as_str())))
+        .collect();
+
+    if problems.is_empty() {
+        println!("✅ All {} paths are present and readable.", tracks.len());
+        return Ok(true);
+    }
+
+    for kind in [Problem::Missing, Problem::Unreadable, Problem::Empty] {
+        let paths: Vec<&str> = problems
+            .iter()
+            .filter(|(p, _)| *p == kind)
+            .map(|(_, path)| *path)
+            .collect();
+        if paths.is_empty() {
+            continue;
+        }
+
+        println!("\n❌ {} {}:", paths.len(), kind.label());
+        for path in paths.iter().take(SAMPLE) {
+            println!("   {}", path);
+        }
+        if paths.len() > SAMPLE {
+            println!("   ...and {} more", paths.len() - SAMPLE);
+        }
+        for path in &paths {
+            log::warn!("Verify: {} {}", kind.label(), path);
+        }
+    }
+
+    println!(
+        "\n{} of {} paths have problems. The index was not changed, run -r to drop missing entries.",
+        problems.len(),
+        tracks.len()
+    );
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verify_categories() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path();
+        std::fs::write(dir.join("ok.mp3"), b"ID3").unwrap();
+        std::fs::write(dir.join("empty.mp3"), b"").unwrap();
+
+        let ok = check(&dir.join("ok.mp3"));
+        let empty = check(&dir.join("empty.mp3"));
+        let missing = check(&dir.join("gone.mp3"));
+        let folder = check(dir);
+
+        assert_eq!(ok, None);
+        assert_eq!(empty, Some(Problem::Empty));
+        assert_eq!(missing, Some(Problem::Missing));
+        assert_eq!(folder, Some(Problem::Unreadable));
+    }
+}